@@ -0,0 +1,1141 @@
+use std::fmt::{Debug, Formatter};
+
+use lers_windows_macro::PCWSTR;
+use windows::core::PCWSTR;
+use windows::Win32::Foundation::{LocalFree, HLOCAL};
+use windows::Win32::Security::Authorization::{ConvertStringSecurityDescriptorToSecurityDescriptorW, SDDL_REVISION_1};
+use windows::Win32::Security::{
+    DACL_SECURITY_INFORMATION, GROUP_SECURITY_INFORMATION, OBJECT_SECURITY_INFORMATION, OWNER_SECURITY_INFORMATION,
+    PSECURITY_DESCRIPTOR, SACL_SECURITY_INFORMATION,
+};
+use windows::Win32::System::Services::{
+    CreateServiceW, DeleteService, OpenServiceW, SetServiceObjectSecurity, ENUM_SERVICE_TYPE, SC_HANDLE, SERVICE_DRIVER,
+    SERVICE_ERROR, SERVICE_START_TYPE,
+};
+
+use crate::dword::{ScManagerAccess, ServiceAccess, ServiceError, ServiceErrorControl, ServiceStartType, ServiceType};
+use crate::config::{ConfigDiff, ServiceConfig};
+use crate::handle::OwnedScHandle;
+use crate::{close_service_handle, handle_opened, is_elevated, path, validate_display_name, validate_service_name, RawServiceConfig, ServiceName, WindowsService};
+
+/// 服务构建器
+/// ## 例子
+/// ```
+/// use windows_service_controller::builder::ServiceBuilder;
+/// use windows_service_controller::dword::{ServiceErrorControl, ServiceStartType};
+/// // 只需要名称和路径,启动类型默认为按需启动
+/// let service = ServiceBuilder::new("Lers", "C:\\WINDOWS\\system32\\cmd.exe").create();
+/// let service = ServiceBuilder::new("LersAuto", "C:\\WINDOWS\\system32\\cmd.exe")
+///     .display_name("Lers Service")
+///     .start_type(ServiceStartType::SERVICE_AUTO_START)
+///     .error_control(ServiceErrorControl::SERVICE_ERROR_SEVERE)
+///     .create();
+/// ```
+#[derive(Clone)]
+pub struct ServiceBuilder {
+    name: String,
+    display_name: Option<String>,
+    binary_path: String,
+    service_type: ServiceType,
+    start_type: ServiceStartType,
+    error_control: ServiceErrorControl,
+    load_order_group: Option<String>,
+    dependencies: Vec<String>,
+    service_start_name: Option<String>,
+    password: Option<String>,
+    security_descriptor: Option<String>,
+    allow_missing_binary: bool,
+    sc_manager_access: ScManagerAccess,
+    service_access: ServiceAccess,
+}
+
+/// 服务的创建参数,与ServiceBuilder相同
+pub type ServiceSpec = ServiceBuilder;
+
+impl Debug for ServiceBuilder {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ServiceBuilder")
+            .field("name", &self.name)
+            .field("display_name", &self.display_name)
+            .field("binary_path", &self.binary_path)
+            .field("service_type", &self.service_type)
+            .field("start_type", &self.start_type)
+            .field("error_control", &self.error_control)
+            .field("load_order_group", &self.load_order_group)
+            .field("dependencies", &self.dependencies)
+            .field("service_start_name", &self.service_start_name)
+            // 不输出密码
+            .field("password", &self.password.as_ref().map(|_| "***"))
+            .field("security_descriptor", &self.security_descriptor)
+            .field("allow_missing_binary", &self.allow_missing_binary)
+            .field("sc_manager_access", &self.sc_manager_access)
+            .field("service_access", &self.service_access)
+            .finish()
+    }
+}
+
+impl ServiceBuilder {
+    /// # 新建服务构建器
+    /// 启动类型默认为SERVICE_DEMAND_START,错误控制默认为SERVICE_ERROR_NORMAL,与`sc create`一致
+    /// ## 参数
+    /// ### input:
+    /// - name: 服务名称(最长256个UTF-16字符,斜杠无效)
+    /// - binary_path: 需要启动的文件路径,路径可以包含启动的参数
+    pub fn new(name: &str, binary_path: &str) -> ServiceBuilder {
+        ServiceBuilder {
+            name: name.to_string(),
+            display_name: None,
+            binary_path: binary_path.to_string(),
+            service_type: ServiceType::SERVICE_WIN32_OWN_PROCESS,
+            start_type: ServiceStartType::default(),
+            error_control: ServiceErrorControl::default(),
+            load_order_group: None,
+            dependencies: Vec::new(),
+            service_start_name: None,
+            password: None,
+            security_descriptor: None,
+            allow_missing_binary: false,
+            sc_manager_access: ScManagerAccess::SC_MANAGER_CREATE_SERVICE,
+            service_access: ServiceAccess::SERVICE_ALL_ACCESS,
+        }
+    }
+
+    /// # 以已存在服务的配置为模板新建构建器
+    /// 复制服务类型、启动类型、错误控制、可执行文件路径、加载顺序组、依赖项和运行账户,
+    /// 修改需要的设置并通过name设置新的服务名称后即可创建一个相似的服务。以下设置不会复制:
+    /// - 显示名称与服务名称相同时不复制,默认与新的服务名称一致;不同时原样复制,
+    ///   显示名称在SCM中必须唯一,以新名称创建前需要通过display_name修改,否则返回ERROR_DUPLICATE_SERVICE_NAME
+    /// - 运行账户为LocalSystem时不复制(即默认值);驱动的运行账户是驱动对象名称,不复制
+    /// - 组内标记由SCM分配;密码无法读取,非内置账户需要通过password重新设置
+    /// - 安全描述符、故障恢复、延迟自动启动等不属于QUERY_SERVICE_CONFIGW的设置
+    ///
+    /// 访问权限与allow_missing_binary使用new的默认值
+    /// ## 参数
+    /// ### input:
+    /// - service: 作为模板的服务,需要打开时请求SERVICE_QUERY_CONFIG权限(open_for_delete打开的服务没有配置)
+    /// ### output:
+    /// - ServiceBuilder: 服务名称与模板相同
+    /// ## 例子
+    /// ```
+    /// use windows_service_controller::builder::ServiceBuilder;
+    /// use windows_service_controller::dword::ServiceAccess;
+    /// use windows_service_controller::WindowsService;
+    /// let template = WindowsService::open("Lers", Some(ServiceAccess::SERVICE_QUERY_CONFIG), None).unwrap();
+    /// let service = ServiceBuilder::from_existing(&template)
+    ///     .name("LersB")
+    ///     .display_name("Lers Service B")
+    ///     .create();
+    /// ```
+    pub fn from_existing(service: &WindowsService) -> ServiceBuilder {
+        ServiceBuilder::from_config(service.name(), &service.config())
+    }
+
+    /// 按from_existing的规则从配置中取出构建器的设置
+    pub(crate) fn from_config(name: &str, config: &ServiceConfig) -> ServiceBuilder {
+        let service_type: ENUM_SERVICE_TYPE = config.service_type.into();
+        let is_driver = service_type.0 & SERVICE_DRIVER.0 != 0;
+        let mut builder = ServiceBuilder::new(name, &config.binary_path)
+            .service_type(config.service_type)
+            .start_type(config.start_type)
+            .error_control(config.error_control);
+        builder.dependencies = config.dependencies.iter().filter(|d| !d.is_empty()).cloned().collect();
+        if !config.display_name.is_empty() && config.display_name != name {
+            builder.display_name = Some(config.display_name.clone());
+        }
+        if !config.load_order_group.is_empty() {
+            builder.load_order_group = Some(config.load_order_group.clone());
+        }
+        let account = &config.service_start_name;
+        if !is_driver && !account.is_empty() && !account.eq_ignore_ascii_case("LocalSystem") {
+            builder.service_start_name = Some(account.clone());
+        }
+        builder
+    }
+
+    /// # 服务名称(最长256个UTF-16字符,斜杠无效)
+    /// 一般与from_existing一起使用,为复制出的构建器设置新的名称
+    pub fn name(mut self, name: &str) -> ServiceBuilder {
+        self.name = name.to_string();
+        self
+    }
+
+    /// # 服务显示名称,不设置时与服务名称一致
+    /// 显示名称不能为空,设置为""时创建服务返回ERROR_INVALID_NAME;希望与服务名称一致时不要调用本方法
+    pub fn display_name(mut self, display_name: &str) -> ServiceBuilder {
+        self.display_name = Some(display_name.to_string());
+        self
+    }
+
+    /// # 服务启动选项,默认SERVICE_DEMAND_START
+    pub fn start_type(mut self, start_type: ServiceStartType) -> ServiceBuilder {
+        self.start_type = start_type;
+        self
+    }
+
+    /// # 错误控制,默认SERVICE_ERROR_NORMAL
+    pub fn error_control(mut self, error_control: ServiceErrorControl) -> ServiceBuilder {
+        self.error_control = error_control;
+        self
+    }
+
+    /// # 服务类型,默认SERVICE_WIN32_OWN_PROCESS
+    pub fn service_type(mut self, service_type: ServiceType) -> ServiceBuilder {
+        self.service_type = service_type;
+        self
+    }
+
+    /// # 服务的依赖项
+    pub fn dependencies(mut self, dependencies: &[&str]) -> ServiceBuilder {
+        self.dependencies = dependencies.iter().map(|s| s.to_string()).collect();
+        self
+    }
+
+    /// # 服务所属的加载顺序组,默认不属于任何组
+    pub fn load_order_group(mut self, group: &str) -> ServiceBuilder {
+        self.load_order_group = Some(group.to_string());
+        self
+    }
+
+    /// # 运行服务的账户,默认LocalSystem
+    pub fn service_start_name(mut self, account: &str) -> ServiceBuilder {
+        self.service_start_name = Some(account.to_string());
+        self
+    }
+
+    /// # 运行服务的账户的密码
+    /// LocalSystem、LocalService、NetworkService、虚拟账户和组托管服务账户(见managed_account)不需要密码
+    pub fn password(mut self, password: &str) -> ServiceBuilder {
+        self.password = Some(password.to_string());
+        self
+    }
+
+    /// # 以组托管服务账户(gMSA)运行服务
+    /// 设置运行账户并清除密码:gMSA的密码由域控制器管理,创建或修改服务时必须传入空密码。
+    /// 账户名称以`$`结尾(如`DOMAIN\svc_acct$`),运行服务的计算机需要有权获取该账户的密码。
+    /// 创建或修改服务时,如果账户以`$`结尾却设置了密码,返回ERROR_INVALID_PARAMETER
+    /// ## 例子
+    /// ```
+    /// use windows_service_controller::builder::ServiceBuilder;
+    /// let service = ServiceBuilder::new("Lers", "C:\\Lers\\lers.exe")
+    ///     .managed_account("CONTOSO\\svc_lers$")
+    ///     .create();
+    /// ```
+    pub fn managed_account(mut self, account: &str) -> ServiceBuilder {
+        self.service_start_name = Some(account.to_string());
+        self.password = None;
+        self
+    }
+
+    /// # 服务的初始安全描述符(SDDL格式)
+    /// CreateServiceW不接受安全描述符,因此创建服务后立即通过SetServiceObjectSecurity设置,设置失败时删除刚创建的服务。
+    /// 这不是原子操作:在两次调用之间服务使用默认的安全描述符,其他进程可能在这段时间内打开服务。
+    ///
+    /// 创建服务时会根据SDDL包含的部分自动追加需要的访问权限:
+    /// - D: WRITE_DAC
+    /// - O:或G: WRITE_OWNER
+    /// - S: ACCESS_SYSTEM_SECURITY(调用者还需要启用SeSecurityPrivilege)
+    ///
+    /// 另外会追加DELETE权限用于回滚
+    /// ## 例子
+    /// ```
+    /// use windows_service_controller::builder::ServiceBuilder;
+    /// let service = ServiceBuilder::new("Lers", "C:\\WINDOWS\\system32\\cmd.exe")
+    ///     .security_descriptor("D:(A;;CCLCSWRPWPDTLOCRRC;;;SY)(A;;CCDCLCSWRPWPDTLOCRSDRCWDWO;;;BA)(A;;CCLCSWLOCRRC;;;AU)")
+    ///     .create();
+    /// ```
+    pub fn security_descriptor(mut self, sddl: &str) -> ServiceBuilder {
+        self.security_descriptor = Some(sddl.to_string());
+        self
+    }
+
+    /// # 解析`sc create`的参数
+    /// 支持`sc create`的`键= 值`语法,键不区分大小写,等号后的空格可以省略,值可以加引号:
+    /// - binPath=: 可执行文件路径(必需)
+    /// - type=: own、share、kernel、filesys、rec、adapt,默认own
+    /// - start=: boot、system、auto、demand、disabled,默认demand
+    /// - error=: normal、severe、critical、ignore,默认normal
+    /// - group=: 加载顺序组
+    /// - depend=: 依赖项,以'/'分隔
+    /// - obj=: 运行服务的账户
+    /// - password=: 账户的密码
+    /// - DisplayName=: 显示名称
+    ///
+    /// 开头的`sc`和`create`可以省略;不支持的键或值(如start= delayed-auto)返回ERROR_INVALID_PARAMETER
+    /// ## 参数
+    /// ### input:
+    /// - args: 服务名称以及后面的参数
+    /// ### output:
+    /// - Result<ServiceSpec,ServiceError>
+    /// ## 例子
+    /// ```
+    /// use windows_service_controller::builder::ServiceSpec;
+    /// let spec = ServiceSpec::from_sc_args(
+    ///     r#"sc create Lers binPath= "C:\Lers\lers.exe --run" start= auto depend= Tcpip/Dnscache DisplayName= "Lers Service""#,
+    /// )
+    /// .unwrap();
+    /// let service = spec.create();
+    /// ```
+    pub fn from_sc_args(args: &str) -> Result<ServiceSpec, ServiceError> {
+        let mut tokens = path::split_args(args).into_iter().peekable();
+        if tokens.peek().is_some_and(|t| t.eq_ignore_ascii_case("sc") || t.eq_ignore_ascii_case("sc.exe")) {
+            tokens.next();
+        }
+        if tokens.peek().is_some_and(|t| t.eq_ignore_ascii_case("create")) {
+            tokens.next();
+        }
+        let name = tokens.next().ok_or(ServiceError::ERROR_INVALID_PARAMETER)?;
+        let mut options: Vec<(String, String)> = Vec::new();
+        while let Some(token) = tokens.next() {
+            let (key, value) = match token.find('=') {
+                // sc的语法要求等号后有空格,值是下一个参数
+                Some(i) if i + 1 == token.len() => {
+                    (token[..i].to_string(), tokens.next().ok_or(ServiceError::ERROR_INVALID_PARAMETER)?)
+                }
+                Some(i) => (token[..i].to_string(), token[i + 1..].to_string()),
+                None => return Err(ServiceError::ERROR_INVALID_PARAMETER),
+            };
+            options.push((key.to_lowercase(), value));
+        }
+        let binary_path = options
+            .iter()
+            .find(|(key, _)| key == "binpath")
+            .map(|(_, value)| value.clone())
+            .ok_or(ServiceError::ERROR_INVALID_PARAMETER)?;
+        let mut spec = ServiceBuilder::new(&name, &binary_path);
+        for (key, value) in options {
+            spec = match key.as_str() {
+                "binpath" => spec,
+                "type" => spec.service_type(sc_value(&SC_SERVICE_TYPES, &value)?),
+                "start" => {
+                    spec.start_type = sc_value(&SC_START_TYPES, &value)?;
+                    spec
+                }
+                "error" => {
+                    spec.error_control = sc_value(&SC_ERROR_CONTROLS, &value)?;
+                    spec
+                }
+                "group" => spec.load_order_group(&value),
+                "depend" => {
+                    let dependencies: Vec<&str> = value.split('/').filter(|s| !s.is_empty()).collect();
+                    spec.dependencies(&dependencies)
+                }
+                "obj" => spec.service_start_name(&value),
+                "password" => spec.password(&value),
+                "displayname" => spec.display_name(&value),
+                _ => return Err(ServiceError::ERROR_INVALID_PARAMETER),
+            };
+        }
+        Ok(spec)
+    }
+
+    /// # 生成等价的`sc create`命令
+    /// 用于调试和文档,输出的命令可以在命令行中手动执行,也可以再由from_sc_args解析。
+    /// 包含空格或引号的值按命令行规则加上引号;type=、start=、error=总是输出,其他参数只在设置时输出。
+    /// 为避免泄露,不输出密码,需要时请手动追加`password= <密码>`。
+    /// 安全描述符、访问权限等sc create不支持的设置不会体现在命令中。
+    /// sc没有对应名称的服务类型(如带SERVICE_INTERACTIVE_PROCESS位)、启动类型和错误控制输出为数字,
+    /// 服务类型为十六进制(如`type= 0x110`),sc.exe和from_sc_args都不接受这种写法,只用于如实显示配置
+    /// ## 参数
+    /// ### output:
+    /// - String
+    /// ## 例子
+    /// ```
+    /// use windows_service_controller::builder::ServiceBuilder;
+    /// use windows_service_controller::dword::ServiceStartType;
+    /// let spec = ServiceBuilder::new("Lers", "C:\\Program Files\\Lers\\lers.exe")
+    ///     .start_type(ServiceStartType::SERVICE_AUTO_START)
+    ///     .dependencies(&["RpcSs", "Tcpip"]);
+    /// assert_eq!(
+    ///     spec.to_sc_command(),
+    ///     r#"sc.exe create Lers binPath= "C:\Program Files\Lers\lers.exe" type= own start= auto error= normal depend= RpcSs/Tcpip"#
+    /// );
+    /// ```
+    pub fn to_sc_command(&self) -> String {
+        let mut args = vec![
+            "sc.exe".to_string(),
+            "create".to_string(),
+            path::quote_arg(&self.name),
+            "binPath=".to_string(),
+            path::quote_arg(&self.binary_path),
+        ];
+        let mut option = |key: &str, value: &str| {
+            args.push(format!("{}=", key));
+            args.push(path::quote_arg(value));
+        };
+        // 无法用sc的名称表示的值输出为数字,避免显示成另一种配置
+        let service_type = sc_keyword(&SC_SERVICE_TYPES, self.service_type).map_or_else(
+            || format!("0x{:X}", ENUM_SERVICE_TYPE::from(self.service_type).0),
+            str::to_string,
+        );
+        option("type", &service_type);
+        let start_type = sc_keyword(&SC_START_TYPES, self.start_type)
+            .map_or_else(|| SERVICE_START_TYPE::from(self.start_type).0.to_string(), str::to_string);
+        option("start", &start_type);
+        let error_control = sc_keyword(&SC_ERROR_CONTROLS, self.error_control)
+            .map_or_else(|| SERVICE_ERROR::from(self.error_control).0.to_string(), str::to_string);
+        option("error", &error_control);
+        if let Some(group) = &self.load_order_group {
+            option("group", group);
+        }
+        if !self.dependencies.is_empty() {
+            option("depend", &self.dependencies.join("/"));
+        }
+        if let Some(account) = &self.service_start_name {
+            option("obj", account);
+        }
+        if let Some(display_name) = &self.display_name {
+            option("DisplayName", display_name);
+        }
+        args.join(" ")
+    }
+
+    /// # 允许可执行文件暂不存在
+    /// CreateServiceW本身不检查lpBinaryPathName,为了尽早发现路径错误,create默认会先确认可执行文件存在,
+    /// 不存在时返回ERROR_PATH_NOT_FOUND且不创建服务。
+    /// 先注册服务、之后再部署可执行文件时,设置为true跳过该检查
+    /// ## 例子
+    /// ```
+    /// use windows_service_controller::builder::ServiceBuilder;
+    /// let service = ServiceBuilder::new("Lers", "C:\\Lers\\lers.exe")
+    ///     .allow_missing_binary(true)
+    ///     .create()
+    ///     .unwrap();
+    /// ```
+    pub fn allow_missing_binary(mut self, allow: bool) -> ServiceBuilder {
+        self.allow_missing_binary = allow;
+        self
+    }
+
+    /// # SCM的访问权限
+    /// 默认只请求SC_MANAGER_CREATE_SERVICE,这是CreateServiceW需要的最小权限,
+    /// 在委派管理等无法获得SC_MANAGER_ALL_ACCESS的场景下也能创建服务
+    pub fn sc_manager_access(mut self, access: ScManagerAccess) -> ServiceBuilder {
+        self.sc_manager_access = access;
+        self
+    }
+
+    /// # 对服务的访问权限,默认SERVICE_ALL_ACCESS
+    pub fn service_access(mut self, access: ServiceAccess) -> ServiceBuilder {
+        self.service_access = access;
+        self
+    }
+
+    /// # 检查可执行文件
+    /// 确认binary_path中的可执行文件存在,check_signature为true时还检查其Authenticode签名。
+    /// 路径中的环境变量(如%SystemRoot%)按当前进程的环境展开,驱动的相对路径相对于%SystemRoot%,
+    /// 其他相对路径按当前目录解析。
+    /// 签名检查需要启用signature特性
+    /// ## 参数
+    /// ### input:
+    /// - check_signature: 是否检查签名
+    /// ### output:
+    /// - Result<(),ServiceError>:
+    ///   - 文件不存在时返回ERROR_PATH_NOT_FOUND
+    ///   - 没有签名时返回BINARY_NOT_SIGNED,签名无效或不受信任时返回BINARY_NOT_TRUSTED
+    ///   - 未启用signature特性而check_signature为true时返回ERROR_NOT_SUPPORTED
+    /// ## 例子
+    /// ```
+    /// use windows_service_controller::builder::ServiceBuilder;
+    /// let builder = ServiceBuilder::new("Lers", "C:\\Lers\\lers.exe");
+    /// match builder.verify_binary(true) {
+    ///     Ok(_) => {
+    ///         builder.create().unwrap();
+    ///     }
+    ///     Err(e) => println!("拒绝安装:{}", e),
+    /// }
+    /// ```
+    pub fn verify_binary(&self, check_signature: bool) -> Result<(), ServiceError> {
+        let executable = self.existing_binary()?;
+        if !check_signature {
+            return Ok(());
+        }
+        #[cfg(feature = "signature")]
+        return crate::signature::verify_signature(&executable);
+        #[cfg(not(feature = "signature"))]
+        {
+            let _ = executable;
+            Err(ServiceError::ERROR_NOT_SUPPORTED)
+        }
+    }
+
+    /// 解析binary_path中的可执行文件,文件不存在时返回ERROR_PATH_NOT_FOUND
+    fn existing_binary(&self) -> Result<String, ServiceError> {
+        let service_type: ENUM_SERVICE_TYPE = self.service_type.into();
+        let is_driver = service_type.0 & SERVICE_DRIVER.0 != 0;
+        let executable = path::resolve_executable(&self.binary_path, is_driver, |name| std::env::var(name).ok());
+        if std::path::Path::new(&executable).is_file() {
+            Ok(executable)
+        } else {
+            Err(ServiceError::ERROR_PATH_NOT_FOUND)
+        }
+    }
+
+    /// # 创建服务
+    /// 默认先确认可执行文件存在,不存在时返回ERROR_PATH_NOT_FOUND(见allow_missing_binary)。
+    /// 服务创建成功但随后设置安全描述符或查询配置失败时,会删除刚创建的服务并返回原来的错误;
+    /// 删除也失败时返回SERVICE_CREATED_NOT_QUERIED,此时服务仍然存在;
+    /// 因为没有以管理员身份运行而被拒绝访问时返回REQUIRES_ELEVATION(分类仍为AccessDenied)
+    /// ## 参数
+    /// ### output:
+    /// - Result<WindowsService,ServiceError>
+    pub fn create(&self) -> Result<WindowsService, ServiceError> {
+        self.with_sc_manager(|sc_manager_handle| self.create_in(sc_manager_handle))
+    }
+
+    /// # 确保服务存在且配置与构建器一致
+    /// 服务不存在时按create创建;已存在时读取当前配置,只修改与构建器不同的字段,相同的字段保持不变,
+    /// 配置已经一致且没有设置密码时不做任何修改。可以重复调用,适合声明式的部署工具。
+    /// - 所有差异通过一次ChangeServiceConfigW调用修改,失败时不会留下部分修改
+    /// - 未设置service_start_name时不管理运行账户,保持服务当前的账户
+    /// - 密码无法读取比较,设置了密码时总会写入(配置一致时只修改密码),可以用来轮换运行账户的密码
+    /// - security_descriptor只在创建服务时设置,已存在的服务不修改其安全描述符
+    /// - 可执行文件路径需要修改时同样检查文件是否存在(见allow_missing_binary)
+    ///
+    /// 需要SC_MANAGER_CREATE_SERVICE(服务不存在时)以及SERVICE_QUERY_CONFIG、SERVICE_CHANGE_CONFIG权限
+    /// ## 参数
+    /// ### output:
+    /// - Result<WindowsService,ServiceError>
+    /// ## 例子
+    /// ```
+    /// use windows_service_controller::builder::ServiceBuilder;
+    /// use windows_service_controller::dword::ServiceStartType;
+    /// let service = ServiceBuilder::new("Lers", "C:\\Lers\\lers.exe")
+    ///     .display_name("Lers Service")
+    ///     .start_type(ServiceStartType::SERVICE_AUTO_START)
+    ///     .dependencies(&["Tcpip"])
+    ///     .ensure()
+    ///     .unwrap();
+    /// ```
+    pub fn ensure(&self) -> Result<WindowsService, ServiceError> {
+        self.with_sc_manager(|sc_manager_handle| {
+            match WindowsService::open_service(sc_manager_handle, &self.name, self.service_access) {
+                Ok(service_handle) => self.converge(service_handle),
+                Err(e) if e == ServiceError::ERROR_SERVICE_DOES_NOT_EXIST => self.create_in(sc_manager_handle),
+                Err(e) => Err(e),
+            }
+        })
+    }
+
+    /// 使已存在的服务的配置与构建器一致,service_handle由返回的WindowsService持有
+    fn converge(&self, service_handle: SC_HANDLE) -> Result<WindowsService, ServiceError> {
+        let mut service = WindowsService {
+            // with_sc_manager已经校验过名称
+            name: ServiceName(self.name.clone()),
+            service_handle: OwnedScHandle::new(service_handle),
+            sc_manager_handle: OwnedScHandle::default(),
+            access: self.service_access,
+            config: RawServiceConfig::default(),
+            _config_buffer: Vec::new(),
+        };
+        self.validate_account()?;
+        (service.config, service._config_buffer) = WindowsService::get_config(service_handle)?;
+        let current = service.config();
+        let desired = self.desired_config(&current);
+        let diffs = current.diff(&desired);
+        if !self.needs_change(&diffs) {
+            return Ok(service);
+        }
+        if !self.allow_missing_binary && diffs.iter().any(|d| matches!(d, ConfigDiff::BinaryPath { .. })) {
+            self.existing_binary()?;
+        }
+        service.apply_config_diff(&diffs, &desired, self.password.as_deref())?;
+        Ok(service)
+    }
+
+    /// 已存在的服务是否需要修改:配置有差异,或者设置了密码(无法读取当前密码进行比较)
+    fn needs_change(&self, diffs: &[ConfigDiff]) -> bool {
+        !diffs.is_empty() || self.password.is_some()
+    }
+
+    /// 在打开SCM之前校验服务名称和显示名称,显示名称为Some("")时不会按未设置处理
+    fn validate_names(&self) -> Result<(), ServiceError> {
+        validate_service_name(&self.name)?;
+        match &self.display_name {
+            Some(display_name) => validate_display_name(display_name),
+            None => Ok(()),
+        }
+    }
+
+    /// 以`$`结尾的账户是托管服务账户,不能设置密码
+    fn validate_account(&self) -> Result<(), ServiceError> {
+        match (&self.service_start_name, &self.password) {
+            (Some(account), Some(_)) if account.ends_with('$') => Err(ServiceError::ERROR_INVALID_PARAMETER),
+            _ => Ok(()),
+        }
+    }
+
+    /// 构建器描述的配置,构建器不管理的字段(标记、未设置的运行账户)沿用current
+    fn desired_config(&self, current: &ServiceConfig) -> ServiceConfig {
+        ServiceConfig {
+            service_type: self.service_type,
+            start_type: self.start_type,
+            error_control: self.error_control,
+            binary_path: self.binary_path.clone(),
+            load_order_group: self.load_order_group.clone().unwrap_or_default(),
+            tag_id: current.tag_id,
+            dependencies: self.dependencies.clone(),
+            service_start_name: self.service_start_name.clone().unwrap_or_else(|| current.service_start_name.clone()),
+            display_name: self.display_name.clone().unwrap_or_else(|| self.name.clone()),
+        }
+    }
+
+    /// 打开SCM后执行f,成功时返回的服务持有该SCM句柄,失败时关闭该SCM句柄
+    fn with_sc_manager<F>(&self, f: F) -> Result<WindowsService, ServiceError>
+    where
+        F: FnOnce(SC_HANDLE) -> Result<WindowsService, ServiceError>,
+    {
+        self.validate_names()?;
+        let sc_manager_handle =
+            OwnedScHandle::new(WindowsService::open_sc_manager(self.sc_manager_access).map_err(elevation_hint)?);
+        let mut service = f(sc_manager_handle.get())?;
+        service.sc_manager_handle = sc_manager_handle;
+        Ok(service)
+    }
+
+    /// 使用已打开的SCM句柄创建服务,返回的服务不持有(也不会关闭)该SCM句柄
+    pub(crate) fn create_in(&self, sc_manager_handle: SC_HANDLE) -> Result<WindowsService, ServiceError> {
+        self.validate_names()?;
+        self.validate_account()?;
+        if !self.allow_missing_binary {
+            self.existing_binary()?;
+        }
+        let name: &str = &self.name;
+        let display_name: &str = self.display_name.as_deref().unwrap_or(name);
+        let binary_path: &str = &self.binary_path;
+        let dependencies = to_multi_sz(&self.dependencies);
+        let wide = |s: &Option<String>| s.as_ref().map(|s| s.encode_utf16().chain(Some(0)).collect::<Vec<u16>>());
+        let load_order_group = wide(&self.load_order_group);
+        let service_start_name = wide(&self.service_start_name);
+        let password = wide(&self.password);
+        // 先转换SDDL,格式错误时不会创建服务
+        let security_descriptor = match &self.security_descriptor {
+            None => None,
+            Some(sddl) => Some((LocalSecurityDescriptor::from_sddl(sddl)?, security_information(sddl))),
+        };
+        let service_access = match &security_descriptor {
+            None => self.service_access,
+            Some((_, information)) => self.service_access | security_access(*information) | ServiceAccess::DELETE,
+        };
+        let service_handle = unsafe {
+            CreateServiceW(
+                sc_manager_handle,
+                PCWSTR!(name),
+                PCWSTR!(display_name),
+                service_access.into(),
+                self.service_type.into(),
+                self.start_type.into(),
+                self.error_control.into(),
+                PCWSTR!(binary_path),
+                match &load_order_group {
+                    None => PCWSTR::null(),
+                    Some(v) => PCWSTR(v.as_ptr()),
+                },
+                None,
+                match &dependencies {
+                    None => PCWSTR::null(),
+                    Some(v) => PCWSTR(v.as_ptr()),
+                },
+                match &service_start_name {
+                    None => PCWSTR::null(),
+                    Some(v) => PCWSTR(v.as_ptr()),
+                },
+                match &password {
+                    None => PCWSTR::null(),
+                    Some(v) => PCWSTR(v.as_ptr()),
+                },
+            )
+        };
+        match service_handle {
+            Ok(handle) => {
+                handle_opened();
+                if let Some((descriptor, information)) = &security_descriptor {
+                    if unsafe { SetServiceObjectSecurity(handle, *information, descriptor.0) }.is_err() {
+                        let error = ServiceError::from_last_error();
+                        remove_created(sc_manager_handle, handle, name);
+                        return Err(error);
+                    }
+                }
+                // 查询配置失败时删除刚创建的服务,避免返回错误的同时留下服务
+                let (config, config_buffer) = match WindowsService::get_config(handle) {
+                    Ok(v) => v,
+                    Err(e) => {
+                        return if remove_created(sc_manager_handle, handle, name) {
+                            Err(e)
+                        } else {
+                            Err(ServiceError::SERVICE_CREATED_NOT_QUERIED)
+                        };
+                    }
+                };
+                Ok(WindowsService {
+                    name: ServiceName(self.name.clone()),
+                    service_handle: OwnedScHandle::new(handle),
+                    sc_manager_handle: OwnedScHandle::default(),
+                    access: service_access,
+                    config,
+                    _config_buffer: config_buffer,
+                })
+            }
+            Err(_) => Err(elevation_hint(ServiceError::from_last_error())),
+        }
+    }
+}
+
+/// 创建服务时被拒绝访问且当前进程没有提升时,改为提示以管理员身份运行的REQUIRES_ELEVATION。
+/// 无法判断是否提升时保留原来的错误
+/// sc的type=关键字,from_sc_args和to_sc_command共用
+const SC_SERVICE_TYPES: [(ServiceType, &str); 8] = [
+    (ServiceType::SERVICE_WIN32_OWN_PROCESS, "own"),
+    (ServiceType::SERVICE_WIN32_SHARE_PROCESS, "share"),
+    (ServiceType::SERVICE_USER_OWN_PROCESS, "userown"),
+    (ServiceType::SERVICE_USER_SHARE_PROCESS, "usershare"),
+    (ServiceType::SERVICE_KERNEL_DRIVER, "kernel"),
+    (ServiceType::SERVICE_FILE_SYSTEM_DRIVER, "filesys"),
+    (ServiceType::SERVICE_RECOGNIZER_DRIVER, "rec"),
+    (ServiceType::SERVICE_ADAPTER, "adapt"),
+];
+
+/// sc的start=关键字
+const SC_START_TYPES: [(ServiceStartType, &str); 5] = [
+    (ServiceStartType::SERVICE_BOOT_START, "boot"),
+    (ServiceStartType::SERVICE_SYSTEM_START, "system"),
+    (ServiceStartType::SERVICE_AUTO_START, "auto"),
+    (ServiceStartType::SERVICE_DEMAND_START, "demand"),
+    (ServiceStartType::SERVICE_DISABLED, "disabled"),
+];
+
+/// sc的error=关键字
+const SC_ERROR_CONTROLS: [(ServiceErrorControl, &str); 4] = [
+    (ServiceErrorControl::SERVICE_ERROR_IGNORE, "ignore"),
+    (ServiceErrorControl::SERVICE_ERROR_NORMAL, "normal"),
+    (ServiceErrorControl::SERVICE_ERROR_SEVERE, "severe"),
+    (ServiceErrorControl::SERVICE_ERROR_CRITICAL, "critical"),
+];
+
+/// 按sc关键字(不区分大小写)查找对应的值,没有时返回ERROR_INVALID_PARAMETER
+fn sc_value<T: Copy>(table: &[(T, &str)], keyword: &str) -> Result<T, ServiceError> {
+    table
+        .iter()
+        .find(|(_, name)| name.eq_ignore_ascii_case(keyword))
+        .map(|(value, _)| *value)
+        .ok_or(ServiceError::ERROR_INVALID_PARAMETER)
+}
+
+/// 值对应的sc关键字
+fn sc_keyword<T: PartialEq>(table: &[(T, &'static str)], value: T) -> Option<&'static str> {
+    table.iter().find(|(v, _)| *v == value).map(|(_, name)| *name)
+}
+
+fn elevation_hint(error: ServiceError) -> ServiceError {
+    if error == ServiceError::ERROR_ACCESS_DENIED && is_elevated() == Ok(false) {
+        ServiceError::REQUIRES_ELEVATION
+    } else {
+        error
+    }
+}
+
+/// 由ConvertStringSecurityDescriptorToSecurityDescriptorW分配的安全描述符,drop时释放
+struct LocalSecurityDescriptor(PSECURITY_DESCRIPTOR);
+
+impl LocalSecurityDescriptor {
+    /// 转换SDDL字符串,格式错误时返回ERROR_INVALID_PARAMETER等错误
+    fn from_sddl(sddl: &str) -> Result<LocalSecurityDescriptor, ServiceError> {
+        if security_information(sddl).0 == 0 {
+            return Err(ServiceError::ERROR_INVALID_PARAMETER);
+        }
+        let mut descriptor = PSECURITY_DESCRIPTOR::default();
+        match unsafe { ConvertStringSecurityDescriptorToSecurityDescriptorW(PCWSTR!(sddl), SDDL_REVISION_1, &mut descriptor, None) } {
+            Ok(_) => Ok(LocalSecurityDescriptor(descriptor)),
+            Err(_) => Err(ServiceError::from_last_error()),
+        }
+    }
+}
+
+impl Drop for LocalSecurityDescriptor {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = LocalFree(HLOCAL(self.0 .0));
+        }
+    }
+}
+
+/// 删除刚创建的服务并关闭其句柄,返回服务是否已被删除
+/// 句柄没有DELETE权限时,用SCM句柄重新以DELETE权限打开服务再删除
+fn remove_created(sc_manager_handle: SC_HANDLE, handle: SC_HANDLE, name: &str) -> bool {
+    unsafe {
+        let mut deleted = DeleteService(handle).is_ok();
+        let _ = close_service_handle(handle);
+        if !deleted {
+            if let Ok(handle) = OpenServiceW(sc_manager_handle, PCWSTR!(name), ServiceAccess::DELETE.into()) {
+                handle_opened();
+                deleted = DeleteService(handle).is_ok();
+                let _ = close_service_handle(handle);
+            }
+        }
+        deleted
+    }
+}
+
+/// 根据SDDL包含的部分(O:、G:、D:、S:)确定需要设置的安全信息
+/// 括号内是ACE,其中的冒号不是部分的开始
+fn security_information(sddl: &str) -> OBJECT_SECURITY_INFORMATION {
+    let mut information = OBJECT_SECURITY_INFORMATION(0);
+    let mut depth = 0;
+    let mut previous: Option<char> = None;
+    for c in sddl.chars() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ':' if depth == 0 => match previous {
+                Some('O') => information |= OWNER_SECURITY_INFORMATION,
+                Some('G') => information |= GROUP_SECURITY_INFORMATION,
+                Some('D') => information |= DACL_SECURITY_INFORMATION,
+                Some('S') => information |= SACL_SECURITY_INFORMATION,
+                _ => {}
+            },
+            _ => {}
+        }
+        previous = Some(c);
+    }
+    information
+}
+
+/// 设置安全信息需要的服务访问权限
+fn security_access(information: OBJECT_SECURITY_INFORMATION) -> ServiceAccess {
+    let mut access = ServiceAccess::from(0u32);
+    if information.contains(DACL_SECURITY_INFORMATION) {
+        access = access | ServiceAccess::WRITE_DAC;
+    }
+    if information.contains(OWNER_SECURITY_INFORMATION) || information.contains(GROUP_SECURITY_INFORMATION) {
+        access = access | ServiceAccess::WRITE_OWNER;
+    }
+    if information.contains(SACL_SECURITY_INFORMATION) {
+        access = access | ServiceAccess::ACCESS_SYSTEM_SECURITY;
+    }
+    access
+}
+
+/// 将字符串列表转换为以两个NUL结尾的宽字符串列表,列表为空时返回None
+pub(crate) fn to_multi_sz(items: &[String]) -> Option<Vec<u16>> {
+    if items.is_empty() {
+        return None;
+    }
+    let mut result: Vec<u16> = Vec::new();
+    for item in items {
+        result.extend(item.encode_utf16());
+        result.push(0);
+    }
+    result.push(0);
+    Some(result)
+}
+
+#[cfg(test)]
+mod test {
+    use windows::Win32::Security::{DACL_SECURITY_INFORMATION, GROUP_SECURITY_INFORMATION, OWNER_SECURITY_INFORMATION};
+    use windows::Win32::System::Services::{ENUM_SERVICE_TYPE, SC_HANDLE, SERVICE_ERROR, SERVICE_START_TYPE};
+
+    use crate::builder::{
+        sc_value, security_access, security_information, ServiceSpec, SC_ERROR_CONTROLS, SC_SERVICE_TYPES, SC_START_TYPES,
+    };
+    use crate::config::{ConfigDiff, ServiceConfig};
+    use crate::dword::{ServiceAccess, ServiceError, ServiceErrorControl, ServiceStartType, ServiceType};
+    use crate::WindowsService;
+
+    #[test]
+    fn parse_sc_args() {
+        let spec = ServiceSpec::from_sc_args(
+            r#"sc create Lers binPath= "\"C:\Program Files\Lers\lers.exe\" --run" type= share start= auto error= severe depend= Tcpip/Dnscache obj= "NT AUTHORITY\LocalService" DisplayName= "Lers Service""#,
+        )
+        .unwrap();
+        assert_eq!(spec.name, "Lers");
+        assert_eq!(spec.binary_path, "\"C:\\Program Files\\Lers\\lers.exe\" --run");
+        assert_eq!(spec.service_type, ServiceType::SERVICE_WIN32_SHARE_PROCESS);
+        assert_eq!(spec.start_type, ServiceStartType::SERVICE_AUTO_START);
+        assert_eq!(spec.error_control, ServiceErrorControl::SERVICE_ERROR_SEVERE);
+        assert_eq!(spec.dependencies, vec!["Tcpip", "Dnscache"]);
+        assert_eq!(spec.service_start_name.as_deref(), Some("NT AUTHORITY\\LocalService"));
+        assert_eq!(spec.display_name.as_deref(), Some("Lers Service"));
+    }
+
+    #[test]
+    fn parse_sc_args_defaults() {
+        let spec = ServiceSpec::from_sc_args("Lers binpath=C:\\Lers\\lers.exe").unwrap();
+        assert_eq!(spec.binary_path, "C:\\Lers\\lers.exe");
+        assert_eq!(spec.service_type, ServiceType::SERVICE_WIN32_OWN_PROCESS);
+        assert_eq!(spec.start_type, ServiceStartType::SERVICE_DEMAND_START);
+        assert_eq!(spec.error_control, ServiceErrorControl::SERVICE_ERROR_NORMAL);
+    }
+
+    #[test]
+    fn builder_defaults() {
+        let spec = ServiceSpec::new("Lers", "C:\\Lers\\lers.exe");
+        assert_eq!(spec.start_type, ServiceStartType::SERVICE_DEMAND_START);
+        assert_eq!(spec.error_control, ServiceErrorControl::SERVICE_ERROR_NORMAL);
+        let spec = spec.start_type(ServiceStartType::SERVICE_AUTO_START);
+        assert_eq!(spec.start_type, ServiceStartType::SERVICE_AUTO_START);
+    }
+
+    #[test]
+    fn from_config() {
+        let config = ServiceConfig {
+            service_type: ServiceType::SERVICE_WIN32_SHARE_PROCESS,
+            start_type: ServiceStartType::SERVICE_AUTO_START,
+            error_control: ServiceErrorControl::SERVICE_ERROR_SEVERE,
+            binary_path: "C:\\Lers\\lers.exe -k lers".to_string(),
+            load_order_group: "LersGroup".to_string(),
+            tag_id: 3,
+            dependencies: vec!["RpcSs".to_string(), "+NetworkProvider".to_string()],
+            service_start_name: "NT AUTHORITY\\LocalService".to_string(),
+            display_name: "Lers Service".to_string(),
+        };
+        let spec = ServiceSpec::from_config("Lers", &config).name("LersB");
+        assert_eq!(spec.name, "LersB");
+        assert_eq!(spec.binary_path, config.binary_path);
+        assert_eq!(spec.service_type, ServiceType::SERVICE_WIN32_SHARE_PROCESS);
+        assert_eq!(spec.start_type, ServiceStartType::SERVICE_AUTO_START);
+        assert_eq!(spec.error_control, ServiceErrorControl::SERVICE_ERROR_SEVERE);
+        assert_eq!(spec.load_order_group.as_deref(), Some("LersGroup"));
+        assert_eq!(spec.dependencies, config.dependencies);
+        assert_eq!(spec.service_start_name.as_deref(), Some("NT AUTHORITY\\LocalService"));
+        assert_eq!(spec.display_name.as_deref(), Some("Lers Service"));
+        assert_eq!(spec.password, None);
+        // 与服务名称相同的显示名称、LocalSystem和空的加载顺序组使用默认值
+        let config = ServiceConfig {
+            load_order_group: String::new(),
+            service_start_name: "LocalSystem".to_string(),
+            display_name: "Lers".to_string(),
+            ..config
+        };
+        let spec = ServiceSpec::from_config("Lers", &config);
+        assert_eq!(spec.display_name, None);
+        assert_eq!(spec.load_order_group, None);
+        assert_eq!(spec.service_start_name, None);
+        // 驱动的运行账户是驱动对象名称
+        let config = ServiceConfig {
+            service_type: ServiceType::SERVICE_KERNEL_DRIVER,
+            service_start_name: "\\Driver\\Lers".to_string(),
+            ..config
+        };
+        assert_eq!(ServiceSpec::from_config("Lers", &config).service_start_name, None);
+    }
+
+    #[test]
+    fn from_existing() {
+        match WindowsService::open("WSearch", Some(ServiceAccess::SERVICE_QUERY_CONFIG), None) {
+            Ok(service) => {
+                let spec = ServiceSpec::from_existing(&service).name("LersB");
+                assert_eq!(spec.binary_path, service.config().binary_path);
+                println!("{}", spec.to_sc_command());
+            }
+            Err(e) => {
+                println!("{}", e);
+            }
+        }
+    }
+
+    #[test]
+    fn display_name() {
+        // 名称在访问SCM之前校验,空的SCM句柄不会被使用
+        let spec = ServiceSpec::new("Lers", "C:\\Lers\\lers.exe").allow_missing_binary(true);
+        for invalid in ["", "Lers\0Lers", &"a".repeat(257)] {
+            let result = spec.clone().display_name(invalid).create_in(SC_HANDLE::default());
+            assert_eq!(result.err(), Some(ServiceError::ERROR_INVALID_NAME));
+        }
+        assert_eq!(spec.display_name, None);
+        let result = WindowsService::new(
+            "Lers",
+            Some(""),
+            None,
+            None,
+            ServiceType::SERVICE_WIN32_OWN_PROCESS,
+            ServiceStartType::SERVICE_DEMAND_START,
+            ServiceErrorControl::SERVICE_ERROR_NORMAL,
+            "C:\\Lers\\lers.exe",
+            None,
+        );
+        assert_eq!(result.err(), Some(ServiceError::ERROR_INVALID_NAME));
+    }
+
+    #[test]
+    fn missing_binary() {
+        let spec = ServiceSpec::new("Lers", "\"C:\\Lers\\missing.exe\" --run");
+        assert_eq!(spec.create_in(SC_HANDLE::default()).err(), Some(ServiceError::ERROR_PATH_NOT_FOUND));
+    }
+
+    #[test]
+    fn managed_account() {
+        let spec = ServiceSpec::new("Lers", "C:\\Lers\\lers.exe")
+            .password("secret")
+            .managed_account("CONTOSO\\svc_lers$");
+        assert_eq!(spec.service_start_name.as_deref(), Some("CONTOSO\\svc_lers$"));
+        assert_eq!(spec.password, None);
+        assert_eq!(spec.validate_account(), Ok(()));
+        // 托管服务账户设置了密码,不会调用CreateServiceW
+        let spec = spec.password("secret").allow_missing_binary(true);
+        assert_eq!(spec.create_in(SC_HANDLE::default()).err(), Some(ServiceError::ERROR_INVALID_PARAMETER));
+        let spec = ServiceSpec::new("Lers", "C:\\Lers\\lers.exe").service_start_name(".\\lers").password("secret");
+        assert_eq!(spec.validate_account(), Ok(()));
+    }
+
+    #[test]
+    fn ensure_desired_config() {
+        let current = ServiceConfig {
+            service_type: ServiceType::SERVICE_WIN32_OWN_PROCESS,
+            start_type: ServiceStartType::SERVICE_DEMAND_START,
+            error_control: ServiceErrorControl::SERVICE_ERROR_NORMAL,
+            binary_path: "C:\\Lers\\lers.exe".to_string(),
+            load_order_group: String::new(),
+            tag_id: 3,
+            dependencies: vec!["Tcpip".to_string()],
+            service_start_name: "NT AUTHORITY\\LocalService".to_string(),
+            display_name: "Lers".to_string(),
+        };
+        // 未设置运行账户时沿用当前账户,标记不参与比较
+        let spec = ServiceSpec::new("Lers", "C:\\Lers\\lers.exe").dependencies(&["tcpip"]);
+        assert!(current.diff(&spec.desired_config(&current)).is_empty());
+        assert!(!spec.needs_change(&[]));
+        // 密码无法比较,配置一致时也需要写入
+        assert!(spec.clone().password("secret").needs_change(&[]));
+        let spec = spec.start_type(ServiceStartType::SERVICE_AUTO_START).display_name("Lers Service");
+        assert_eq!(
+            current.diff(&spec.desired_config(&current)),
+            vec![
+                ConfigDiff::StartType {
+                    from: ServiceStartType::SERVICE_DEMAND_START,
+                    to: ServiceStartType::SERVICE_AUTO_START
+                },
+                ConfigDiff::DisplayName { from: "Lers".to_string(), to: "Lers Service".to_string() },
+            ]
+        );
+    }
+
+    #[test]
+    fn ensure_service() {
+        let spec = ServiceSpec::new("LersEnsure", "C:\\WINDOWS\\system32\\cmd.exe");
+        match spec.ensure() {
+            Ok(_) => {
+                // 第二次调用只修改启动类型
+                let spec = spec.start_type(ServiceStartType::SERVICE_DISABLED);
+                match spec.ensure() {
+                    Ok(s) => {
+                        println!("{:?}", s.config());
+                        let _ = s.delete_service();
+                    }
+                    Err(e) => {
+                        println!("{}", e);
+                    }
+                }
+            }
+            Err(e) => {
+                println!("{}", e);
+            }
+        }
+    }
+
+    #[test]
+    fn render_sc_command() {
+        let spec = ServiceSpec::new("Lers", "\"C:\\Program Files\\Lers\\lers.exe\" --run")
+            .service_type(ServiceType::SERVICE_WIN32_SHARE_PROCESS)
+            .error_control(ServiceErrorControl::SERVICE_ERROR_SEVERE)
+            .dependencies(&["Tcpip", "Dnscache"])
+            .service_start_name("NT AUTHORITY\\LocalService")
+            .display_name("Lers Service")
+            .password("secret");
+        let command = spec.to_sc_command();
+        assert_eq!(
+            command,
+            r#"sc.exe create Lers binPath= "\"C:\Program Files\Lers\lers.exe\" --run" type= share start= demand error= severe depend= Tcpip/Dnscache obj= "NT AUTHORITY\LocalService" DisplayName= "Lers Service""#
+        );
+        let parsed = ServiceSpec::from_sc_args(&command).unwrap();
+        assert_eq!(parsed.binary_path, spec.binary_path);
+        assert_eq!(parsed.service_type, spec.service_type);
+        assert_eq!(parsed.error_control, spec.error_control);
+        assert_eq!(parsed.dependencies, spec.dependencies);
+        assert_eq!(parsed.service_start_name, spec.service_start_name);
+        assert_eq!(parsed.display_name, spec.display_name);
+        assert_eq!(parsed.password, None);
+        // 用户服务有自己的名称,没有名称的值输出为数字
+        let spec = ServiceSpec::new("Lers", "C:\\Lers\\lers.exe").service_type(ServiceType::SERVICE_USER_OWN_PROCESS);
+        let command = spec.to_sc_command();
+        assert!(command.contains("type= userown"));
+        assert_eq!(ServiceSpec::from_sc_args(&command).unwrap().service_type, ServiceType::SERVICE_USER_OWN_PROCESS);
+        let mut spec = ServiceSpec::new("Lers", "C:\\Lers\\lers.exe");
+        spec.service_type = ServiceType::from(ENUM_SERVICE_TYPE(0x110));
+        spec.start_type = ServiceStartType::from(SERVICE_START_TYPE(7));
+        spec.error_control = ServiceErrorControl::from(SERVICE_ERROR(9));
+        assert!(spec.to_sc_command().ends_with("type= 0x110 start= 7 error= 9"));
+    }
+
+    #[test]
+    fn sc_command_round_trip() {
+        // 解析和生成共用关键字表,每个关键字都能原样还原
+        for (service_type, _) in SC_SERVICE_TYPES {
+            for (start_type, _) in SC_START_TYPES {
+                for (error_control, _) in SC_ERROR_CONTROLS {
+                    let spec = ServiceSpec::new("Lers App", "C:\\Lers\\lers.exe --run")
+                        .service_type(service_type)
+                        .start_type(start_type)
+                        .error_control(error_control)
+                        .load_order_group("Lers Group")
+                        .dependencies(&["RpcSs", "+NetworkProvider"])
+                        .service_start_name("NT AUTHORITY\\LocalService")
+                        .display_name("Lers \"Service\"");
+                    let parsed = ServiceSpec::from_sc_args(&spec.to_sc_command()).unwrap();
+                    assert_eq!(parsed.name, spec.name);
+                    assert_eq!(parsed.binary_path, spec.binary_path);
+                    assert_eq!(parsed.service_type, spec.service_type);
+                    assert_eq!(parsed.start_type, spec.start_type);
+                    assert_eq!(parsed.error_control, spec.error_control);
+                    assert_eq!(parsed.load_order_group, spec.load_order_group);
+                    assert_eq!(parsed.dependencies, spec.dependencies);
+                    assert_eq!(parsed.service_start_name, spec.service_start_name);
+                    assert_eq!(parsed.display_name, spec.display_name);
+                }
+            }
+        }
+        assert_eq!(sc_value(&SC_START_TYPES, "AUTO"), Ok(ServiceStartType::SERVICE_AUTO_START));
+    }
+
+    #[test]
+    fn parse_sc_args_invalid() {
+        assert_eq!(ServiceSpec::from_sc_args("Lers start= auto").err(), Some(ServiceError::ERROR_INVALID_PARAMETER));
+        assert_eq!(
+            ServiceSpec::from_sc_args("Lers binPath= C:\\lers.exe start= delayed-auto").err(),
+            Some(ServiceError::ERROR_INVALID_PARAMETER)
+        );
+        assert_eq!(ServiceSpec::from_sc_args("Lers binPath=").err(), Some(ServiceError::ERROR_INVALID_PARAMETER));
+    }
+
+    #[test]
+    fn sddl_information() {
+        assert_eq!(
+            security_information("D:(A;;CCLCSWRPWPDTLOCRRC;;;SY)(A;;CCLCSWLOCRRC;;;AU)"),
+            DACL_SECURITY_INFORMATION
+        );
+        assert_eq!(
+            security_information("O:BAG:SYD:P(A;;GA;;;BA)"),
+            OWNER_SECURITY_INFORMATION | GROUP_SECURITY_INFORMATION | DACL_SECURITY_INFORMATION
+        );
+        assert_eq!(security_information("").0, 0);
+        let access = security_access(security_information("O:BAD:(A;;GA;;;BA)"));
+        assert!(access.contains(ServiceAccess::WRITE_DAC | ServiceAccess::WRITE_OWNER));
+        assert!(!access.contains(ServiceAccess::ACCESS_SYSTEM_SECURITY));
+    }
+
+    #[test]
+    fn create_rollback_on_config_failure() {
+        // 句柄没有SERVICE_QUERY_CONFIG权限,创建后查询配置会失败,服务应当被删除
+        let spec = ServiceSpec::new("LersConfigRollback", "C:\\WINDOWS\\system32\\cmd.exe")
+            .service_access(ServiceAccess::SERVICE_START);
+        match spec.create() {
+            Ok(_) => {
+                println!("succeed")
+            }
+            Err(e) => {
+                println!("{}", e);
+                if e != ServiceError::SERVICE_CREATED_NOT_QUERIED {
+                    assert!(WindowsService::open("LersConfigRollback", None, None).is_err());
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,591 @@
+use windows::Win32::System::Services::{ENUM_SERVICE_TYPE, SERVICE_DRIVER};
+
+use crate::dword::{ServiceError, ServiceErrorControl, ServiceStartType, ServiceType};
+use crate::{multi_sz_to_vec, wide_to_string, RawServiceConfig};
+
+/// 服务配置(QueryServiceConfigW的结果)
+/// ## 例子
+/// ```
+/// use windows_service_controller::WindowsService;
+/// let mut service = WindowsService::open("Lers", None, None).unwrap();
+/// let mut config = service.config();
+/// config.display_name = "Lers Service".to_string();
+/// service.update_service_config(&config, None).unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct ServiceConfig {
+    /// 服务类型
+    pub service_type: ServiceType,
+    /// 启动类型
+    pub start_type: ServiceStartType,
+    /// 错误控制
+    pub error_control: ServiceErrorControl,
+    /// 可执行文件路径(包含启动参数)
+    pub binary_path: String,
+    /// 加载顺序组,不属于任何组时为空
+    pub load_order_group: String,
+    /// 组内的标记,没有时为0
+    pub tag_id: u32,
+    /// 依赖的服务或加载顺序组,组名以SC_GROUP_IDENTIFIER('+')开头
+    pub dependencies: Vec<String>,
+    /// 运行服务的账户
+    pub service_start_name: String,
+    /// 显示名称
+    pub display_name: String,
+}
+
+impl ServiceConfig {
+    /// 从QUERY_SERVICE_CONFIGW复制出配置,不再引用原结构体中的字符串
+    pub(crate) fn from_raw(config: &RawServiceConfig) -> ServiceConfig {
+        ServiceConfig {
+            service_type: config.dwServiceType.into(),
+            start_type: config.dwStartType.into(),
+            error_control: config.dwErrorControl.into(),
+            binary_path: wide_to_string(config.lpBinaryPathName),
+            load_order_group: wide_to_string(config.lpLoadOrderGroup),
+            tag_id: config.dwTagId,
+            dependencies: multi_sz_to_vec(config.lpDependencies),
+            service_start_name: wide_to_string(config.lpServiceStartName),
+            display_name: wide_to_string(config.lpDisplayName),
+        }
+    }
+
+    /// # 比较两个配置的差异
+    /// 以self为当前配置、other为期望配置,逐字段比较,返回所有不同的字段,没有差异时返回空列表。
+    /// - 依赖项不考虑顺序,服务名、加载顺序组和账户名不区分大小写(与SCM一致)
+    /// - 可执行文件路径和显示名称按原样比较
+    /// ## 参数
+    /// ### input:
+    /// - other: 期望的配置
+    /// ### output:
+    /// - Vec<ConfigDiff>
+    /// ## 例子
+    /// ```
+    /// use windows_service_controller::config::ConfigDiff;
+    /// use windows_service_controller::dword::ServiceStartType;
+    /// use windows_service_controller::WindowsService;
+    /// let service = WindowsService::open("Lers", None, None).unwrap();
+    /// let mut desired = service.config();
+    /// desired.start_type = ServiceStartType::SERVICE_AUTO_START;
+    /// for diff in service.config().diff(&desired) {
+    ///     println!("{:?}", diff);
+    /// }
+    /// ```
+    pub fn diff(&self, other: &ServiceConfig) -> Vec<ConfigDiff> {
+        let mut diffs = Vec::new();
+        if self.service_type != other.service_type {
+            diffs.push(ConfigDiff::ServiceType { from: self.service_type, to: other.service_type });
+        }
+        if self.start_type != other.start_type {
+            diffs.push(ConfigDiff::StartType { from: self.start_type, to: other.start_type });
+        }
+        if self.error_control != other.error_control {
+            diffs.push(ConfigDiff::ErrorControl { from: self.error_control, to: other.error_control });
+        }
+        if self.binary_path != other.binary_path {
+            diffs.push(ConfigDiff::BinaryPath { from: self.binary_path.clone(), to: other.binary_path.clone() });
+        }
+        if !self.load_order_group.eq_ignore_ascii_case(&other.load_order_group) {
+            diffs.push(ConfigDiff::LoadOrderGroup {
+                from: self.load_order_group.clone(),
+                to: other.load_order_group.clone(),
+            });
+        }
+        if self.tag_id != other.tag_id {
+            diffs.push(ConfigDiff::TagId { from: self.tag_id, to: other.tag_id });
+        }
+        let missing = |from: &[String], to: &[String]| -> Vec<String> {
+            to.iter()
+                .filter(|name| !from.iter().any(|existing| existing.eq_ignore_ascii_case(name)))
+                .cloned()
+                .collect()
+        };
+        let added = missing(&self.dependencies, &other.dependencies);
+        let removed = missing(&other.dependencies, &self.dependencies);
+        if !added.is_empty() || !removed.is_empty() {
+            diffs.push(ConfigDiff::Dependencies { added, removed });
+        }
+        if !self.service_start_name.eq_ignore_ascii_case(&other.service_start_name) {
+            diffs.push(ConfigDiff::ServiceStartName {
+                from: self.service_start_name.clone(),
+                to: other.service_start_name.clone(),
+            });
+        }
+        if self.display_name != other.display_name {
+            diffs.push(ConfigDiff::DisplayName { from: self.display_name.clone(), to: other.display_name.clone() });
+        }
+        diffs
+    }
+
+    /// # 从当前配置修改为期望配置后,修改何时生效
+    /// 返回所有差异中最晚生效的一项(见ConfigDiff::effect),没有差异时返回ChangeEffect::Immediate。
+    /// SCM修改配置后不会通知正在运行的服务,也不会提示需要重启,这里按已知的规则推断
+    /// ## 参数
+    /// ### input:
+    /// - desired: 期望的配置
+    /// ### output:
+    /// - ChangeEffect
+    /// ## 例子
+    /// ```
+    /// use windows_service_controller::config::ChangeEffect;
+    /// use windows_service_controller::WindowsService;
+    /// let mut service = WindowsService::open("Lers", None, None).unwrap();
+    /// let mut desired = service.config();
+    /// desired.binary_path = "C:\\Lers\\lers.exe --v2".to_string();
+    /// let effect = service.config().change_effect(&desired);
+    /// service.update_service_config(&desired, None).unwrap();
+    /// if effect == ChangeEffect::NextStart {
+    ///     println!("重新启动服务后生效");
+    /// }
+    /// ```
+    pub fn change_effect(&self, desired: &ServiceConfig) -> ChangeEffect {
+        self.diff(desired)
+            .iter()
+            .map(|diff| diff.effect(self.service_type))
+            .max()
+            .unwrap_or(ChangeEffect::Immediate)
+    }
+
+    /// # 是否是系统启动所必需的服务
+    /// 只根据配置判断,满足任意一项时返回true:
+    /// - 错误控制为SERVICE_ERROR_CRITICAL: 启动失败时系统会回退到最近一次的正确配置重新启动,
+    ///   已经是最近一次的正确配置时启动失败
+    /// - 启动类型为SERVICE_BOOT_START或SERVICE_SYSTEM_START: 系统加载程序或内核初始化时加载的驱动,
+    ///   停止或删除后系统可能无法启动
+    /// ## 参数
+    /// ### output:
+    /// - bool
+    pub fn is_boot_critical(&self) -> bool {
+        self.error_control == ServiceErrorControl::SERVICE_ERROR_CRITICAL
+            || self.start_type == ServiceStartType::SERVICE_BOOT_START
+            || self.start_type == ServiceStartType::SERVICE_SYSTEM_START
+    }
+
+    /// # 区分服务和服务组的依赖项
+    /// ## 参数
+    /// ### output:
+    /// - Vec<Dependency>: 与dependencies顺序一致
+    pub fn dependency_list(&self) -> Vec<Dependency> {
+        self.dependencies.iter().map(|raw| Dependency::parse(raw)).collect()
+    }
+}
+
+/// 依赖项中服务组名称的前缀
+pub const SC_GROUP_IDENTIFIER: char = '+';
+
+/// 服务的一个依赖项
+/// lpDependencies中的服务组以SC_GROUP_IDENTIFIER('+')开头,其他项为服务名称。
+/// 依赖服务组时,组内至少一个服务启动后才会启动该服务;把组名当作服务名称会让依赖静默失效
+/// ## 例子
+/// ```
+/// use windows_service_controller::config::Dependency;
+/// assert_eq!(Dependency::parse("+NetworkProvider"), Dependency::Group("NetworkProvider".to_string()));
+/// assert_eq!(Dependency::Service("RpcSs".to_string()).to_raw(), "RpcSs");
+/// assert_eq!(Dependency::Group("NetworkProvider".to_string()).to_raw(), "+NetworkProvider");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Dependency {
+    /// 依赖的服务名称
+    Service(String),
+    /// 依赖的加载顺序组名称,不含'+'前缀
+    Group(String),
+}
+
+impl Dependency {
+    /// # 解析lpDependencies中的一项,'+'开头的是服务组
+    pub fn parse(raw: &str) -> Dependency {
+        match raw.strip_prefix(SC_GROUP_IDENTIFIER) {
+            Some(group) => Dependency::Group(group.to_string()),
+            None => Dependency::Service(raw.to_string()),
+        }
+    }
+
+    /// # 服务或服务组的名称,不含'+'前缀
+    pub fn name(&self) -> &str {
+        match self {
+            Dependency::Service(name) | Dependency::Group(name) => name,
+        }
+    }
+
+    /// # 转换为lpDependencies中的一项,服务组加上'+'前缀
+    pub fn to_raw(&self) -> String {
+        match self {
+            Dependency::Service(name) => name.clone(),
+            Dependency::Group(group) => format!("{}{}", SC_GROUP_IDENTIFIER, group),
+        }
+    }
+
+    /// 检查能否无歧义地写入lpDependencies:名称不能为空或包含NUL,
+    /// 服务名称不能以'+'开头(会被当作服务组),否则返回ERROR_INVALID_PARAMETER
+    pub(crate) fn validate(&self) -> Result<(), ServiceError> {
+        let name = self.name();
+        let ambiguous = matches!(self, Dependency::Service(name) if name.starts_with(SC_GROUP_IDENTIFIER));
+        if name.is_empty() || name.contains('\0') || ambiguous {
+            Err(ServiceError::ERROR_INVALID_PARAMETER)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl std::fmt::Display for Dependency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.to_raw())
+    }
+}
+
+/// 综合启动类型和触发器配置得到的实际启动方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StartBehavior {
+    /// 由系统加载程序加载(驱动程序)
+    Boot,
+    /// 由IoInitSystem加载(驱动程序)
+    System,
+    /// 系统启动时由SCM自动启动
+    Auto,
+    /// 按需启动,配置了触发器,在触发事件发生时由SCM启动
+    Triggered,
+    /// 按需启动,没有触发器,只在调用StartService时启动
+    Manual,
+    /// 已禁用,触发器也不会启动服务
+    Disabled,
+}
+
+impl StartBehavior {
+    /// 按需启动的服务配置了触发器时为Triggered;其他启动类型不受触发器影响
+    pub(crate) fn from_start_type(start_type: ServiceStartType, trigger_count: u32) -> StartBehavior {
+        if start_type == ServiceStartType::SERVICE_BOOT_START {
+            StartBehavior::Boot
+        } else if start_type == ServiceStartType::SERVICE_SYSTEM_START {
+            StartBehavior::System
+        } else if start_type == ServiceStartType::SERVICE_AUTO_START {
+            StartBehavior::Auto
+        } else if start_type == ServiceStartType::SERVICE_DISABLED {
+            StartBehavior::Disabled
+        } else if trigger_count > 0 {
+            StartBehavior::Triggered
+        } else {
+            StartBehavior::Manual
+        }
+    }
+}
+
+/// 配置修改何时生效,按生效的早晚排序
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ChangeEffect {
+    /// 立即生效
+    Immediate,
+    /// 服务下次启动时生效,正在运行的服务需要重新启动
+    NextStart,
+    /// 系统重新启动后生效
+    Reboot,
+}
+
+/// 两个服务配置之间的一项差异,from为当前值,to为期望值
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigDiff {
+    ServiceType { from: ServiceType, to: ServiceType },
+    StartType { from: ServiceStartType, to: ServiceStartType },
+    ErrorControl { from: ServiceErrorControl, to: ServiceErrorControl },
+    BinaryPath { from: String, to: String },
+    LoadOrderGroup { from: String, to: String },
+    TagId { from: u32, to: u32 },
+    /// 依赖项的增减,不考虑顺序
+    Dependencies { added: Vec<String>, removed: Vec<String> },
+    ServiceStartName { from: String, to: String },
+    DisplayName { from: String, to: String },
+}
+
+impl ConfigDiff {
+    /// # 这项修改何时生效
+    /// - 显示名称:立即生效
+    /// - 启动类型:改为自动、引导或系统启动时只在系统启动过程中体现,需要重启系统;
+    ///   改为按需启动或禁用时立即影响后续的启动请求
+    /// - 加载顺序组、标记:只影响系统启动时的加载顺序,需要重启系统
+    /// - 可执行文件路径、账户、服务类型、依赖项、错误控制:SCM在启动服务时读取,下次启动生效;
+    ///   驱动程序通常无法停止,其中可执行文件路径、账户和服务类型的修改按需要重启系统处理
+    /// ## 参数
+    /// ### input:
+    /// - service_type: 当前的服务类型
+    /// ### output:
+    /// - ChangeEffect
+    pub fn effect(&self, service_type: ServiceType) -> ChangeEffect {
+        let driver: ENUM_SERVICE_TYPE = service_type.into();
+        let driver = driver.0 & SERVICE_DRIVER.0 != 0;
+        match self {
+            ConfigDiff::DisplayName { .. } => ChangeEffect::Immediate,
+            ConfigDiff::StartType { to, .. } => {
+                if *to == ServiceStartType::SERVICE_DEMAND_START || *to == ServiceStartType::SERVICE_DISABLED {
+                    ChangeEffect::Immediate
+                } else {
+                    ChangeEffect::Reboot
+                }
+            }
+            ConfigDiff::LoadOrderGroup { .. } | ConfigDiff::TagId { .. } => ChangeEffect::Reboot,
+            ConfigDiff::BinaryPath { .. } | ConfigDiff::ServiceStartName { .. } | ConfigDiff::ServiceType { .. }
+                if driver =>
+            {
+                ChangeEffect::Reboot
+            }
+            ConfigDiff::BinaryPath { .. }
+            | ConfigDiff::ServiceStartName { .. }
+            | ConfigDiff::ServiceType { .. }
+            | ConfigDiff::Dependencies { .. }
+            | ConfigDiff::ErrorControl { .. } => ChangeEffect::NextStart,
+        }
+    }
+}
+
+/// 对服务配置的一次修改,传给WindowsService::apply_config_change
+/// 每个字段为None时保持不变(数值字段传SERVICE_NO_CHANGE,字符串字段传NULL),为Some时修改为该值。
+/// 与update_service_config不同,不会把缓存的整份配置重新写回,其他程序同时修改的字段不会被覆盖
+/// - load_order_group为Some("")时移除服务组,dependencies为Some(空列表)时移除所有依赖项
+/// - 依赖项中的服务组以SC_GROUP_IDENTIFIER('+')开头
+/// - tag_id无法通过ChangeServiceConfigW修改,因此没有对应字段
+/// ## 例子
+/// ```
+/// use windows_service_controller::config::ConfigChange;
+/// use windows_service_controller::dword::ServiceStartType;
+/// use windows_service_controller::WindowsService;
+/// let mut service = WindowsService::open("Lers", None, None).unwrap();
+/// service
+///     .apply_config_change(ConfigChange {
+///         start_type: Some(ServiceStartType::SERVICE_AUTO_START),
+///         display_name: Some("Lers Service".to_string()),
+///         ..Default::default()
+///     })
+///     .unwrap();
+/// ```
+#[derive(Clone, Default)]
+pub struct ConfigChange {
+    /// 服务类型
+    pub service_type: Option<ServiceType>,
+    /// 启动类型
+    pub start_type: Option<ServiceStartType>,
+    /// 错误控制
+    pub error_control: Option<ServiceErrorControl>,
+    /// 可执行文件路径(包含启动参数)
+    pub binary_path: Option<String>,
+    /// 加载顺序组
+    pub load_order_group: Option<String>,
+    /// 依赖的服务或加载顺序组
+    pub dependencies: Option<Vec<String>>,
+    /// 运行服务的账户
+    pub service_start_name: Option<String>,
+    /// 账户的密码
+    pub password: Option<String>,
+    /// 显示名称
+    pub display_name: Option<String>,
+}
+
+impl std::fmt::Debug for ConfigChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ConfigChange")
+            .field("service_type", &self.service_type)
+            .field("start_type", &self.start_type)
+            .field("error_control", &self.error_control)
+            .field("binary_path", &self.binary_path)
+            .field("load_order_group", &self.load_order_group)
+            .field("dependencies", &self.dependencies)
+            .field("service_start_name", &self.service_start_name)
+            // 不输出密码
+            .field("password", &self.password.as_ref().map(|_| "***"))
+            .field("display_name", &self.display_name)
+            .finish()
+    }
+}
+
+impl ConfigChange {
+    /// 只包含diffs中列出的字段,取desired中的值;标记(tag_id)无法修改,忽略该项差异
+    pub(crate) fn from_diffs(diffs: &[ConfigDiff], desired: &ServiceConfig, password: Option<&str>) -> ConfigChange {
+        let mut change = ConfigChange { password: password.map(str::to_string), ..Default::default() };
+        for diff in diffs {
+            match diff {
+                ConfigDiff::ServiceType { .. } => change.service_type = Some(desired.service_type),
+                ConfigDiff::StartType { .. } => change.start_type = Some(desired.start_type),
+                ConfigDiff::ErrorControl { .. } => change.error_control = Some(desired.error_control),
+                ConfigDiff::BinaryPath { .. } => change.binary_path = Some(desired.binary_path.clone()),
+                ConfigDiff::LoadOrderGroup { .. } => change.load_order_group = Some(desired.load_order_group.clone()),
+                ConfigDiff::TagId { .. } => {}
+                ConfigDiff::Dependencies { .. } => change.dependencies = Some(desired.dependencies.clone()),
+                ConfigDiff::ServiceStartName { .. } => {
+                    change.service_start_name = Some(desired.service_start_name.clone())
+                }
+                ConfigDiff::DisplayName { .. } => change.display_name = Some(desired.display_name.clone()),
+            }
+        }
+        change
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use windows::core::PWSTR;
+    use windows::Win32::System::Services::{SERVICE_AUTO_START, SERVICE_ERROR_NORMAL, SERVICE_WIN32_OWN_PROCESS};
+
+    use crate::config::{ChangeEffect, ConfigChange, ConfigDiff, Dependency, ServiceConfig, StartBehavior};
+    use crate::dword::{ServiceError, ServiceErrorControl, ServiceStartType, ServiceType};
+    use crate::RawServiceConfig;
+
+    fn wide(s: &str) -> Vec<u16> {
+        s.encode_utf16().chain(Some(0)).collect()
+    }
+
+    #[test]
+    fn from_raw() {
+        let mut binary_path = wide("C:\\WINDOWS\\system32\\SearchIndexer.exe /Embedding");
+        let mut dependencies: Vec<u16> = "RPCSS\0BrokerInfrastructure\0\0".encode_utf16().collect();
+        let mut start_name = wide("LocalSystem");
+        let mut display_name = wide("Windows Search");
+        let raw = RawServiceConfig {
+            dwServiceType: SERVICE_WIN32_OWN_PROCESS,
+            dwStartType: SERVICE_AUTO_START,
+            dwErrorControl: SERVICE_ERROR_NORMAL,
+            lpBinaryPathName: PWSTR(binary_path.as_mut_ptr()),
+            lpLoadOrderGroup: PWSTR::null(),
+            dwTagId: 0,
+            lpDependencies: PWSTR(dependencies.as_mut_ptr()),
+            lpServiceStartName: PWSTR(start_name.as_mut_ptr()),
+            lpDisplayName: PWSTR(display_name.as_mut_ptr()),
+        };
+        let config = ServiceConfig::from_raw(&raw);
+        assert_eq!(config.binary_path, "C:\\WINDOWS\\system32\\SearchIndexer.exe /Embedding");
+        assert_eq!(config.load_order_group, "");
+        assert_eq!(config.dependencies, vec!["RPCSS", "BrokerInfrastructure"]);
+        assert_eq!(config.service_start_name, "LocalSystem");
+        assert_eq!(config.display_name, "Windows Search");
+    }
+
+    #[test]
+    fn config_diff() {
+        let current = ServiceConfig {
+            service_type: ServiceType::SERVICE_WIN32_OWN_PROCESS,
+            start_type: ServiceStartType::SERVICE_DEMAND_START,
+            error_control: ServiceErrorControl::SERVICE_ERROR_NORMAL,
+            binary_path: "C:\\Lers\\lers.exe".to_string(),
+            load_order_group: String::new(),
+            tag_id: 0,
+            dependencies: vec!["Tcpip".to_string(), "Dnscache".to_string()],
+            service_start_name: "LocalSystem".to_string(),
+            display_name: "Lers".to_string(),
+        };
+        let mut desired = current.clone();
+        desired.dependencies = vec!["dnscache".to_string(), "TCPIP".to_string()];
+        desired.service_start_name = "localsystem".to_string();
+        assert!(current.diff(&desired).is_empty());
+
+        desired.start_type = ServiceStartType::SERVICE_AUTO_START;
+        desired.dependencies = vec!["Tcpip".to_string(), "RpcSs".to_string()];
+        assert_eq!(
+            current.diff(&desired),
+            vec![
+                ConfigDiff::StartType {
+                    from: ServiceStartType::SERVICE_DEMAND_START,
+                    to: ServiceStartType::SERVICE_AUTO_START
+                },
+                ConfigDiff::Dependencies {
+                    added: vec!["RpcSs".to_string()],
+                    removed: vec!["Dnscache".to_string()]
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn start_behavior() {
+        let demand = ServiceStartType::SERVICE_DEMAND_START;
+        assert_eq!(StartBehavior::from_start_type(demand, 0), StartBehavior::Manual);
+        assert_eq!(StartBehavior::from_start_type(demand, 2), StartBehavior::Triggered);
+        assert_eq!(StartBehavior::from_start_type(ServiceStartType::SERVICE_AUTO_START, 1), StartBehavior::Auto);
+        assert_eq!(StartBehavior::from_start_type(ServiceStartType::SERVICE_DISABLED, 1), StartBehavior::Disabled);
+        assert_eq!(StartBehavior::from_start_type(ServiceStartType::SERVICE_BOOT_START, 0), StartBehavior::Boot);
+    }
+
+    #[test]
+    fn boot_critical() {
+        let mut config = ServiceConfig::from_raw(&RawServiceConfig::default());
+        config.start_type = ServiceStartType::SERVICE_AUTO_START;
+        config.error_control = ServiceErrorControl::SERVICE_ERROR_NORMAL;
+        assert!(!config.is_boot_critical());
+        config.error_control = ServiceErrorControl::SERVICE_ERROR_CRITICAL;
+        assert!(config.is_boot_critical());
+        config.error_control = ServiceErrorControl::SERVICE_ERROR_NORMAL;
+        config.start_type = ServiceStartType::SERVICE_SYSTEM_START;
+        assert!(config.is_boot_critical());
+        config.start_type = ServiceStartType::SERVICE_BOOT_START;
+        assert!(config.is_boot_critical());
+    }
+
+    #[test]
+    fn dependency() {
+        let mut config = ServiceConfig::from_raw(&RawServiceConfig::default());
+        config.dependencies = vec!["RpcSs".to_string(), "+NetworkProvider".to_string()];
+        let dependencies = config.dependency_list();
+        assert_eq!(
+            dependencies,
+            vec![Dependency::Service("RpcSs".to_string()), Dependency::Group("NetworkProvider".to_string())]
+        );
+        assert_eq!(dependencies[1].name(), "NetworkProvider");
+        let raw: Vec<String> = dependencies.iter().map(Dependency::to_raw).collect();
+        assert_eq!(raw, config.dependencies);
+        assert_eq!(dependencies[1].to_string(), "+NetworkProvider");
+        assert!(dependencies.iter().all(|d| d.validate().is_ok()));
+        for invalid in [
+            Dependency::Service(String::new()),
+            Dependency::Group(String::new()),
+            Dependency::Service("+RpcSs".to_string()),
+            Dependency::Service("Rpc\0Ss".to_string()),
+        ] {
+            assert_eq!(invalid.validate(), Err(ServiceError::ERROR_INVALID_PARAMETER));
+        }
+    }
+
+    #[test]
+    fn config_change_from_diffs() {
+        let current = ServiceConfig::from_raw(&RawServiceConfig::default());
+        let mut desired = current.clone();
+        desired.display_name = "Lers".to_string();
+        desired.start_type = ServiceStartType::SERVICE_AUTO_START;
+        desired.tag_id = 3;
+        let change = ConfigChange::from_diffs(&current.diff(&desired), &desired, Some("secret"));
+        assert_eq!(change.display_name.as_deref(), Some("Lers"));
+        assert_eq!(change.start_type, Some(ServiceStartType::SERVICE_AUTO_START));
+        assert_eq!(change.password.as_deref(), Some("secret"));
+        assert!(change.service_type.is_none());
+        assert!(change.binary_path.is_none());
+        assert!(change.dependencies.is_none());
+        assert!(!format!("{:?}", change).contains("secret"));
+        assert!(ConfigChange::from_diffs(&[], &desired, None).display_name.is_none());
+    }
+
+    #[test]
+    fn change_effect() {
+        let current = ServiceConfig {
+            service_type: ServiceType::SERVICE_WIN32_OWN_PROCESS,
+            start_type: ServiceStartType::SERVICE_DEMAND_START,
+            error_control: ServiceErrorControl::SERVICE_ERROR_NORMAL,
+            binary_path: "C:\\Lers\\lers.exe".to_string(),
+            load_order_group: String::new(),
+            tag_id: 0,
+            dependencies: Vec::new(),
+            service_start_name: "LocalSystem".to_string(),
+            display_name: "Lers".to_string(),
+        };
+        assert_eq!(current.change_effect(&current), ChangeEffect::Immediate);
+
+        let mut desired = current.clone();
+        desired.display_name = "Lers Service".to_string();
+        desired.start_type = ServiceStartType::SERVICE_DISABLED;
+        assert_eq!(current.change_effect(&desired), ChangeEffect::Immediate);
+
+        desired.binary_path = "C:\\Lers\\lers.exe --v2".to_string();
+        assert_eq!(current.change_effect(&desired), ChangeEffect::NextStart);
+
+        desired.start_type = ServiceStartType::SERVICE_AUTO_START;
+        assert_eq!(current.change_effect(&desired), ChangeEffect::Reboot);
+
+        let binary_path = ConfigDiff::BinaryPath { from: String::new(), to: String::new() };
+        assert_eq!(binary_path.effect(ServiceType::SERVICE_WIN32_SHARE_PROCESS), ChangeEffect::NextStart);
+        assert_eq!(binary_path.effect(ServiceType::SERVICE_KERNEL_DRIVER), ChangeEffect::Reboot);
+    }
+}
@@ -0,0 +1,169 @@
+//! 服务端控制事件的解析
+//!
+//! 本库目前只包含服务控制端的功能,没有提供服务处理程序的封装。
+//! 这里提供的类型用于在自行注册的`RegisterServiceCtrlHandlerExW`回调中,
+//! 把电源、会话、时间、设备事件附带的lpEventData解析为Rust类型。
+use std::ffi::c_void;
+
+use windows::core::GUID;
+use windows::Win32::System::Services;
+
+/// PBT_POWERSETTINGCHANGE,此时lpEventData指向POWERBROADCAST_SETTING
+const PBT_POWERSETTINGCHANGE: u32 = 0x8013;
+
+/// 电源事件(SERVICE_CONTROL_POWEREVENT)
+#[derive(Debug, Clone, PartialEq)]
+pub struct PowerEvent {
+    /// 电源事件类型(PBT_*)
+    pub event_type: u32,
+    /// 事件类型为PBT_POWERSETTINGCHANGE时的电源设置GUID与数据
+    pub setting: Option<(GUID, Vec<u8>)>,
+}
+
+/// 会话变化事件(SERVICE_CONTROL_SESSIONCHANGE)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SessionChange {
+    /// 变化原因(WTS_*,如WTS_SESSION_LOGON)
+    pub reason: u32,
+    /// 会话ID
+    pub session_id: u32,
+}
+
+/// 系统时间变化事件(SERVICE_CONTROL_TIMECHANGE)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeChange {
+    /// 新的系统时间(FILETIME,100纳秒为单位)
+    pub new_time: i64,
+    /// 旧的系统时间(FILETIME,100纳秒为单位)
+    pub old_time: i64,
+}
+
+/// 设备事件(SERVICE_CONTROL_DEVICEEVENT)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeviceEvent {
+    /// 设备事件类型(DBT_*)
+    pub event_type: u32,
+    /// 设备类型(DBT_DEVTYP_*),lpEventData为空时为None
+    pub device_type: Option<u32>,
+}
+
+/// 服务处理程序收到的控制事件
+#[derive(Debug, Clone, PartialEq)]
+pub enum ControlEvent {
+    PowerEvent(PowerEvent),
+    SessionChange(SessionChange),
+    TimeChange(TimeChange),
+    DeviceEvent(DeviceEvent),
+    /// 不附带事件数据的其他控制代码
+    Other(u32),
+}
+
+impl ControlEvent {
+    /// # 解析处理程序回调的参数
+    /// ## 参数
+    /// ### input:
+    /// - control: dwControl
+    /// - event_type: dwEventType
+    /// - event_data: lpEventData
+    /// ### output:
+    /// - ControlEvent
+    /// ## Safety
+    /// event_data必须是系统传给处理程序的原始指针,并且只能在回调返回前使用
+    pub unsafe fn parse(control: u32, event_type: u32, event_data: *const c_void) -> ControlEvent {
+        let data = event_data as *const u8;
+        match control {
+            Services::SERVICE_CONTROL_POWEREVENT => {
+                let setting = if event_type == PBT_POWERSETTINGCHANGE && !data.is_null() {
+                    // POWERBROADCAST_SETTING { PowerSetting: GUID, DataLength: u32, Data: [u8] }
+                    let guid = std::ptr::read_unaligned(data as *const GUID);
+                    let length = std::ptr::read_unaligned(data.add(16) as *const u32);
+                    let bytes = std::slice::from_raw_parts(data.add(20), length as usize).to_vec();
+                    Some((guid, bytes))
+                } else {
+                    None
+                };
+                ControlEvent::PowerEvent(PowerEvent { event_type, setting })
+            }
+            Services::SERVICE_CONTROL_SESSIONCHANGE if !data.is_null() => {
+                // WTSSESSION_NOTIFICATION { cbSize: u32, dwSessionId: u32 }
+                ControlEvent::SessionChange(SessionChange {
+                    reason: event_type,
+                    session_id: std::ptr::read_unaligned(data.add(4) as *const u32),
+                })
+            }
+            Services::SERVICE_CONTROL_TIMECHANGE if !data.is_null() => {
+                // SERVICE_TIMECHANGE_INFO { liNewTime: i64, liOldTime: i64 }
+                ControlEvent::TimeChange(TimeChange {
+                    new_time: std::ptr::read_unaligned(data as *const i64),
+                    old_time: std::ptr::read_unaligned(data.add(8) as *const i64),
+                })
+            }
+            Services::SERVICE_CONTROL_DEVICEEVENT => {
+                // DEV_BROADCAST_HDR { dbch_size: u32, dbch_devicetype: u32, dbch_reserved: u32 }
+                let device_type = if data.is_null() {
+                    None
+                } else {
+                    Some(std::ptr::read_unaligned(data.add(4) as *const u32))
+                };
+                ControlEvent::DeviceEvent(DeviceEvent { event_type, device_type })
+            }
+            _ => ControlEvent::Other(control),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::ffi::c_void;
+
+    use windows::core::GUID;
+    use windows::Win32::System::Services;
+
+    use crate::control_event::{ControlEvent, PowerEvent, SessionChange, TimeChange};
+
+    #[test]
+    fn parse_session_change() {
+        let data: [u32; 2] = [8, 3];
+        let event = unsafe {
+            ControlEvent::parse(Services::SERVICE_CONTROL_SESSIONCHANGE, 5, data.as_ptr() as *const c_void)
+        };
+        assert_eq!(event, ControlEvent::SessionChange(SessionChange { reason: 5, session_id: 3 }));
+    }
+
+    #[test]
+    fn parse_time_change() {
+        let data: [i64; 2] = [200, 100];
+        let event = unsafe {
+            ControlEvent::parse(Services::SERVICE_CONTROL_TIMECHANGE, 0, data.as_ptr() as *const c_void)
+        };
+        assert_eq!(event, ControlEvent::TimeChange(TimeChange { new_time: 200, old_time: 100 }));
+    }
+
+    #[test]
+    fn parse_power_setting() {
+        let guid = GUID::from_u128(0x0123_4567_89ab_cdef_0123_4567_89ab_cdef);
+        let mut data = Vec::new();
+        data.extend_from_slice(&guid.data1.to_le_bytes());
+        data.extend_from_slice(&guid.data2.to_le_bytes());
+        data.extend_from_slice(&guid.data3.to_le_bytes());
+        data.extend_from_slice(&guid.data4);
+        data.extend_from_slice(&4u32.to_le_bytes());
+        data.extend_from_slice(&[1, 0, 0, 0]);
+        let event = unsafe {
+            ControlEvent::parse(Services::SERVICE_CONTROL_POWEREVENT, 0x8013, data.as_ptr() as *const c_void)
+        };
+        assert_eq!(
+            event,
+            ControlEvent::PowerEvent(PowerEvent {
+                event_type: 0x8013,
+                setting: Some((guid, vec![1, 0, 0, 0])),
+            })
+        );
+    }
+
+    #[test]
+    fn parse_other() {
+        let event = unsafe { ControlEvent::parse(Services::SERVICE_CONTROL_STOP, 0, std::ptr::null()) };
+        assert_eq!(event, ControlEvent::Other(Services::SERVICE_CONTROL_STOP));
+    }
+}
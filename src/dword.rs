@@ -2,6 +2,7 @@ use std::collections::HashMap;
 use std::convert::Into;
 use std::fmt::{Display, Formatter};
 use std::hash::{Hash, Hasher};
+use std::ops::BitOr;
 
 use lazy_static::lazy_static;
 use lers_windows_macro::{FromInto, self_attr};
@@ -9,30 +10,32 @@ use windows::Win32::Foundation;
 use windows::Win32::Foundation::WIN32_ERROR;
 use windows::Win32::System::Services;
 use windows::Win32::System::Services::{ENUM_SERVICE_TYPE,
-                                       SERVICE_ERROR, SERVICE_START_TYPE,
+                                       SERVICE_CONFIG, SERVICE_ERROR, SERVICE_RUNS_IN_PROCESS, SERVICE_START_TYPE,
                                        SERVICE_STATUS_CURRENT_STATE};
 
-#[derive(Debug, FromInto)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, FromInto)]
 pub struct ServiceError(WIN32_ERROR);
 
+/// 访问权限不足的错误代码前缀
+const INSUFFICIENT_ACCESS: u32 = 0x2000_0000;
+
 impl Hash for ServiceError {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.0.0.hash(state)
     }
 }
 
-impl PartialEq<Self> for ServiceError {
-    fn eq(&self, other: &Self) -> bool {
-        self.0 == other.0
-    }
-}
-
-impl Eq for ServiceError {}
-
+/// 已知的错误显示为`错误(代码, 常量名称):描述`,常量名称便于在日志中搜索
 impl Display for ServiceError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         if SERVICE_ERRORS.contains_key(self) {
-            write!(f, "错误({}):{}", self.0.0, SERVICE_ERRORS.get(self).unwrap())
+            match self.name() {
+                Some(name) => write!(f, "错误({}, {}):{}", self.0.0, name, SERVICE_ERRORS.get(self).unwrap()),
+                None => write!(f, "错误({}):{}", self.0.0, SERVICE_ERRORS.get(self).unwrap()),
+            }
+        } else if self.kind() == ServiceErrorKind::InsufficientAccess {
+            // 没有单独常量的访问权限(如ACCESS_SYSTEM_SECURITY)
+            write!(f, "错误({}):访问权限不足:需要0x{:X}。", self.0.0, self.0.0 & !INSUFFICIENT_ACCESS)
         } else {
             write!(f, "未知错误({}),请查看官方文档", self.0.0)
         }
@@ -41,25 +44,260 @@ impl Display for ServiceError {
 
 #[self_attr(
     Foundation::ERROR_ACCESS_DENIED,
+    Foundation::ERROR_BAD_NETPATH,
+    Foundation::ERROR_BOOT_ALREADY_ACCEPTED,
     Foundation::ERROR_CIRCULAR_DEPENDENCY,
     Foundation::ERROR_DUPLICATE_SERVICE_NAME,
     Foundation::ERROR_INVALID_HANDLE,
+    Foundation::ERROR_INVALID_LEVEL,
     Foundation::ERROR_INVALID_NAME,
     Foundation::ERROR_INVALID_PARAMETER,
     Foundation::ERROR_INVALID_SERVICE_ACCOUNT,
+    Foundation::ERROR_INVALID_SERVICE_CONTROL,
+    Foundation::ERROR_NOT_SUPPORTED,
     Foundation::ERROR_SERVICE_EXISTS,
     Foundation::ERROR_SERVICE_MARKED_FOR_DELETE,
     Foundation::ERROR_PATH_NOT_FOUND,
     Foundation::ERROR_SERVICE_ALREADY_RUNNING,
+    Foundation::ERROR_SERVICE_CANNOT_ACCEPT_CTRL,
     Foundation::ERROR_SERVICE_DATABASE_LOCKED,
     Foundation::ERROR_SERVICE_DEPENDENCY_DELETED,
     Foundation::ERROR_SERVICE_DEPENDENCY_FAIL,
     Foundation::ERROR_SERVICE_DISABLED,
+    Foundation::ERROR_SERVICE_DOES_NOT_EXIST,
     Foundation::ERROR_SERVICE_LOGON_FAILED,
+    Foundation::ERROR_SERVICE_NEVER_STARTED,
     Foundation::ERROR_SERVICE_NO_THREAD,
+    Foundation::ERROR_SERVICE_NOT_ACTIVE,
     Foundation::ERROR_SERVICE_REQUEST_TIMEOUT
 )]
-impl ServiceError {}
+impl ServiceError {
+    /// 句柄缺少所需访问权限时的错误代码,由本库在调用Win32 API之前检测。
+    /// 设置了应用程序定义位(0x20000000),低位为所需的访问权限
+    pub const INSUFFICIENT_ACCESS_DELETE: ServiceError = ServiceError::insufficient_access(ServiceAccess::DELETE);
+    pub const INSUFFICIENT_ACCESS_SERVICE_CHANGE_CONFIG: ServiceError =
+        ServiceError::insufficient_access(ServiceAccess::SERVICE_CHANGE_CONFIG);
+    pub const INSUFFICIENT_ACCESS_SERVICE_ENUMERATE_DEPENDENTS: ServiceError =
+        ServiceError::insufficient_access(ServiceAccess::SERVICE_ENUMERATE_DEPENDENTS);
+    pub const INSUFFICIENT_ACCESS_SERVICE_INTERROGATE: ServiceError =
+        ServiceError::insufficient_access(ServiceAccess::SERVICE_INTERROGATE);
+    pub const INSUFFICIENT_ACCESS_SERVICE_PAUSE_CONTINUE: ServiceError =
+        ServiceError::insufficient_access(ServiceAccess::SERVICE_PAUSE_CONTINUE);
+    pub const INSUFFICIENT_ACCESS_SERVICE_QUERY_CONFIG: ServiceError =
+        ServiceError::insufficient_access(ServiceAccess::SERVICE_QUERY_CONFIG);
+    pub const INSUFFICIENT_ACCESS_SERVICE_QUERY_STATUS: ServiceError =
+        ServiceError::insufficient_access(ServiceAccess::SERVICE_QUERY_STATUS);
+    pub const INSUFFICIENT_ACCESS_SERVICE_START: ServiceError =
+        ServiceError::insufficient_access(ServiceAccess::SERVICE_START);
+    pub const INSUFFICIENT_ACCESS_SERVICE_STOP: ServiceError =
+        ServiceError::insufficient_access(ServiceAccess::SERVICE_STOP);
+    pub const INSUFFICIENT_ACCESS_SERVICE_USER_DEFINED_CONTROL: ServiceError =
+        ServiceError::insufficient_access(ServiceAccess::SERVICE_USER_DEFINED_CONTROL);
+
+    /// 远程计算机的RPC服务器不可用
+    pub const RPC_S_SERVER_UNAVAILABLE: ServiceError = ServiceError(WIN32_ERROR(1722u32));
+    /// 远程计算机的RPC服务器太忙
+    pub const RPC_S_SERVER_TOO_BUSY: ServiceError = ServiceError(WIN32_ERROR(1723u32));
+    /// 远程过程调用失败
+    pub const RPC_S_CALL_FAILED: ServiceError = ServiceError(WIN32_ERROR(1726u32));
+    /// 远程过程调用失败且未执行
+    pub const RPC_S_CALL_FAILED_DNE: ServiceError = ServiceError(WIN32_ERROR(1727u32));
+    /// 终结点映射器中没有可用的终结点
+    pub const EPT_S_NOT_REGISTERED: ServiceError = ServiceError(WIN32_ERROR(1753u32));
+
+    /// 服务正在运行但不接受停止控制(dwControlsAccepted不包含SERVICE_ACCEPT_STOP),由本库在发送控制前检测
+    pub const SERVICE_NOT_STOPPABLE: ServiceError = ServiceError(WIN32_ERROR(0x2010_0001));
+    /// 可执行文件没有Authenticode签名,由ServiceBuilder::verify_binary检测
+    pub const BINARY_NOT_SIGNED: ServiceError = ServiceError(WIN32_ERROR(0x2010_0002));
+    /// 可执行文件的Authenticode签名无效或不受信任,由ServiceBuilder::verify_binary检测
+    pub const BINARY_NOT_TRUSTED: ServiceError = ServiceError(WIN32_ERROR(0x2010_0003));
+    /// 多次扩大缓冲区后查询结果仍然放不下(数据在查询期间不断被修改),由本库检测
+    pub const BUFFER_STILL_TOO_SMALL: ServiceError = ServiceError(WIN32_ERROR(0x2010_0004));
+    /// 服务已创建,但随后查询配置失败且无法删除刚创建的服务,服务仍然存在,由本库检测
+    pub const SERVICE_CREATED_NOT_QUERIED: ServiceError = ServiceError(WIN32_ERROR(0x2010_0005));
+    /// 创建服务时被拒绝访问,且当前进程没有以管理员身份运行,由本库检测
+    pub const REQUIRES_ELEVATION: ServiceError = ServiceError(WIN32_ERROR(0x2010_0006));
+    /// 服务已创建,但随后启动失败且无法删除刚创建的服务,服务仍然存在,由本库检测
+    pub const SERVICE_CREATED_NOT_STARTED: ServiceError = ServiceError(WIN32_ERROR(0x2010_0007));
+
+    /// # 获取调用线程最近一次的错误代码(GetLastError)
+    /// 直接调用Win32服务API失败后,可以用它得到带有本库错误描述的ServiceError。
+    /// 必须紧跟在失败的调用之后,中间的其他调用可能覆盖错误代码
+    /// ## 参数
+    /// ### output:
+    /// - ServiceError
+    /// ## 例子
+    /// ```
+    /// use windows::Win32::System::Services::{OpenSCManagerW, SC_MANAGER_CONNECT};
+    /// use windows_service_controller::dword::ServiceError;
+    /// if unsafe { OpenSCManagerW(None, None, SC_MANAGER_CONNECT) }.is_err() {
+    ///     println!("{}", ServiceError::from_last_error());
+    /// }
+    /// ```
+    pub fn from_last_error() -> ServiceError {
+        ServiceError(unsafe { Foundation::GetLastError() })
+    }
+
+    /// 缺少指定访问权限时的错误
+    pub(crate) const fn insufficient_access(required: ServiceAccess) -> ServiceError {
+        ServiceError(WIN32_ERROR(INSUFFICIENT_ACCESS | required.0))
+    }
+
+    /// granted缺少required中的权限时,返回缺少的第一个(最低位的)权限对应的错误,
+    /// 使错误代码总是对应单个权限,能够显示名称和描述
+    pub(crate) fn missing_access(granted: ServiceAccess, required: ServiceAccess) -> Option<ServiceError> {
+        let missing = required.0 & !granted.0;
+        (missing != 0).then(|| ServiceError::insufficient_access(ServiceAccess(missing & missing.wrapping_neg())))
+    }
+
+    /// # 错误常量的名称
+    /// 返回与本库常量标识符一致的名称(如`ERROR_ACCESS_DENIED`),不随语言变化;未知的错误代码返回None
+    /// ## 例子
+    /// ```
+    /// use windows_service_controller::dword::ServiceError;
+    /// assert_eq!(ServiceError::ERROR_ACCESS_DENIED.name(), Some("ERROR_ACCESS_DENIED"));
+    /// ```
+    pub fn name(&self) -> Option<&'static str> {
+        SERVICE_ERROR_NAMES.get(self).copied()
+    }
+
+    /// # 是否为连接远程SCM时的网络或RPC错误
+    /// 这类错误通常是暂时的连接问题(目标机器不可达、RPC服务未运行等),
+    /// 与权限不足、服务不存在等错误不同,可以稍后重试
+    pub fn is_rpc_error(&self) -> bool {
+        *self == ServiceError::ERROR_BAD_NETPATH
+            || *self == ServiceError::RPC_S_SERVER_UNAVAILABLE
+            || *self == ServiceError::RPC_S_SERVER_TOO_BUSY
+            || *self == ServiceError::RPC_S_CALL_FAILED
+            || *self == ServiceError::RPC_S_CALL_FAILED_DNE
+            || *self == ServiceError::EPT_S_NOT_REGISTERED
+    }
+
+    /// # 是否为当前系统不支持该扩展配置的信息级别
+    /// 较早的Windows版本没有部分SERVICE_CONFIG_*信息级别(如触发器、启动保护、预关机),
+    /// QueryServiceConfig2W/ChangeServiceConfig2W会返回ERROR_INVALID_LEVEL。
+    /// 需要在多个Windows版本上运行的工具可以据此跳过该项配置,而不是当作失败处理
+    /// ## 例子
+    /// ```
+    /// use windows_service_controller::WindowsService;
+    /// let service = WindowsService::open("WSearch", None, None).unwrap();
+    /// match service.is_protected() {
+    ///     Ok(protected) => println!("{}", protected),
+    ///     Err(e) if e.is_unsupported_level() => println!("当前系统不支持启动保护"),
+    ///     Err(e) => println!("{}", e),
+    /// }
+    /// ```
+    pub fn is_unsupported_level(&self) -> bool {
+        *self == ServiceError::ERROR_INVALID_LEVEL
+    }
+
+    /// # 错误的分类
+    pub fn kind(&self) -> ServiceErrorKind {
+        match *self {
+            ServiceError::ERROR_ACCESS_DENIED | ServiceError::REQUIRES_ELEVATION => ServiceErrorKind::AccessDenied,
+            ServiceError::ERROR_BOOT_ALREADY_ACCEPTED => ServiceErrorKind::BootAlreadyAccepted,
+            ServiceError::ERROR_CIRCULAR_DEPENDENCY => ServiceErrorKind::CircularDependency,
+            ServiceError::ERROR_DUPLICATE_SERVICE_NAME => ServiceErrorKind::DuplicateServiceName,
+            ServiceError::ERROR_INVALID_HANDLE => ServiceErrorKind::InvalidHandle,
+            ServiceError::ERROR_INVALID_LEVEL => ServiceErrorKind::UnsupportedLevel,
+            ServiceError::ERROR_INVALID_NAME => ServiceErrorKind::InvalidName,
+            ServiceError::ERROR_INVALID_PARAMETER => ServiceErrorKind::InvalidParameter,
+            ServiceError::ERROR_INVALID_SERVICE_ACCOUNT => ServiceErrorKind::InvalidServiceAccount,
+            ServiceError::ERROR_INVALID_SERVICE_CONTROL => ServiceErrorKind::InvalidServiceControl,
+            ServiceError::ERROR_NOT_SUPPORTED => ServiceErrorKind::NotSupported,
+            ServiceError::ERROR_SERVICE_EXISTS => ServiceErrorKind::ServiceExists,
+            ServiceError::ERROR_SERVICE_MARKED_FOR_DELETE => ServiceErrorKind::ServiceMarkedForDelete,
+            ServiceError::ERROR_PATH_NOT_FOUND => ServiceErrorKind::PathNotFound,
+            ServiceError::ERROR_SERVICE_ALREADY_RUNNING => ServiceErrorKind::ServiceAlreadyRunning,
+            ServiceError::ERROR_SERVICE_CANNOT_ACCEPT_CTRL => ServiceErrorKind::ServiceCannotAcceptCtrl,
+            ServiceError::ERROR_SERVICE_DATABASE_LOCKED => ServiceErrorKind::ServiceDatabaseLocked,
+            ServiceError::ERROR_SERVICE_DEPENDENCY_DELETED => ServiceErrorKind::ServiceDependencyDeleted,
+            ServiceError::ERROR_SERVICE_DEPENDENCY_FAIL => ServiceErrorKind::ServiceDependencyFail,
+            ServiceError::ERROR_SERVICE_DISABLED => ServiceErrorKind::ServiceDisabled,
+            ServiceError::ERROR_SERVICE_DOES_NOT_EXIST => ServiceErrorKind::ServiceDoesNotExist,
+            ServiceError::ERROR_SERVICE_LOGON_FAILED => ServiceErrorKind::ServiceLogonFailed,
+            ServiceError::ERROR_SERVICE_NEVER_STARTED => ServiceErrorKind::ServiceNeverStarted,
+            ServiceError::ERROR_SERVICE_NO_THREAD => ServiceErrorKind::ServiceNoThread,
+            ServiceError::ERROR_SERVICE_NOT_ACTIVE => ServiceErrorKind::ServiceNotActive,
+            ServiceError::ERROR_SERVICE_REQUEST_TIMEOUT => ServiceErrorKind::ServiceRequestTimeout,
+            ServiceError::SERVICE_NOT_STOPPABLE => ServiceErrorKind::ServiceNotStoppable,
+            ServiceError::BINARY_NOT_SIGNED | ServiceError::BINARY_NOT_TRUSTED => ServiceErrorKind::UntrustedBinary,
+            ServiceError::BUFFER_STILL_TOO_SMALL => ServiceErrorKind::BufferStillTooSmall,
+            ServiceError::SERVICE_CREATED_NOT_QUERIED => ServiceErrorKind::ServiceCreatedNotQueried,
+            ServiceError::SERVICE_CREATED_NOT_STARTED => ServiceErrorKind::ServiceCreatedNotStarted,
+            _ if self.is_rpc_error() => ServiceErrorKind::Rpc,
+            // 低位是访问权限,包括ACCESS_SYSTEM_SECURITY(0x01000000)
+            ServiceError(WIN32_ERROR(code)) if code & 0xE000_0000 == INSUFFICIENT_ACCESS => {
+                ServiceErrorKind::InsufficientAccess
+            }
+            _ => ServiceErrorKind::Other,
+        }
+    }
+}
+
+/// 服务错误的分类,便于匹配常见的错误
+/// ## 例子
+/// ```
+/// use windows_service_controller::dword::{ServiceError, ServiceErrorKind};
+/// let error = ServiceError::ERROR_ACCESS_DENIED;
+/// assert!(error == ServiceErrorKind::AccessDenied);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ServiceErrorKind {
+    AccessDenied,
+    /// 本次启动的配置已经被接受为最近一次的正确配置
+    BootAlreadyAccepted,
+    CircularDependency,
+    DuplicateServiceName,
+    InvalidHandle,
+    InvalidName,
+    InvalidParameter,
+    InvalidServiceAccount,
+    InvalidServiceControl,
+    /// 不支持该请求
+    NotSupported,
+    ServiceExists,
+    ServiceMarkedForDelete,
+    PathNotFound,
+    ServiceAlreadyRunning,
+    ServiceCannotAcceptCtrl,
+    ServiceDatabaseLocked,
+    ServiceDependencyDeleted,
+    ServiceDependencyFail,
+    ServiceDisabled,
+    /// 指定的服务不存在
+    ServiceDoesNotExist,
+    ServiceLogonFailed,
+    ServiceNeverStarted,
+    ServiceNoThread,
+    ServiceNotActive,
+    ServiceRequestTimeout,
+    /// 句柄缺少操作所需的访问权限(由本库检测)
+    InsufficientAccess,
+    /// 服务不接受停止控制(由本库检测)
+    ServiceNotStoppable,
+    /// 可执行文件没有签名或签名不受信任(由本库检测)
+    UntrustedBinary,
+    /// 查询期间数据不断变化,缓冲区始终不足(由本库检测)
+    BufferStillTooSmall,
+    /// 服务已创建但无法查询配置,也无法回滚删除(由本库检测)
+    ServiceCreatedNotQueried,
+    /// 服务已创建但启动失败,也无法回滚删除(由本库检测)
+    ServiceCreatedNotStarted,
+    /// 当前系统不支持该扩展配置的信息级别
+    UnsupportedLevel,
+    /// 连接远程SCM时的网络或RPC错误,见ServiceError::is_rpc_error
+    Rpc,
+    /// 未分类的错误
+    Other,
+}
+
+impl PartialEq<ServiceErrorKind> for ServiceError {
+    fn eq(&self, other: &ServiceErrorKind) -> bool {
+        self.kind() == *other
+    }
+}
 
 lazy_static! {
     static ref SERVICE_ERRORS: HashMap<ServiceError, &'static str> = {
@@ -80,12 +318,19 @@ lazy_static! {
                 ServiceError::ERROR_INVALID_HANDLE,
                 "指定服务控制管理器数据库的句柄无效。",
             ),
-            (ServiceError::ERROR_INVALID_NAME, "指定的服务名称无效。"),
+            (
+                ServiceError::ERROR_INVALID_NAME,
+                "指定的服务名称无效。服务名称不能为空,不能包含'/'或'\\',且最长256个UTF-16字符。",
+            ),
             (ServiceError::ERROR_INVALID_PARAMETER, "指定的参数无效。"),
             (
                 ServiceError::ERROR_INVALID_SERVICE_ACCOUNT,
                 "ServiceStartName 参数中指定的用户帐户名不存在。",
             ),
+            (
+                ServiceError::ERROR_INVALID_SERVICE_CONTROL,
+                "请求的控制代码对此服务无效。",
+            ),
             (
                 ServiceError::ERROR_SERVICE_EXISTS,
                 "此数据库中已存在指定的服务。",
@@ -102,6 +347,10 @@ lazy_static! {
                 ServiceError::ERROR_SERVICE_ALREADY_RUNNING,
                 "服务的实例已在运行。"
             ),
+            (
+                ServiceError::ERROR_SERVICE_CANNOT_ACCEPT_CTRL,
+                "服务当前无法接受该控制代码。"
+            ),
             (
                 ServiceError::ERROR_SERVICE_DATABASE_LOCKED,
                 "数据库已锁定。"
@@ -118,24 +367,145 @@ lazy_static! {
                 ServiceError::ERROR_SERVICE_DISABLED,
                 "服务已被禁用。"
             ),
+            (ServiceError::ERROR_SERVICE_DOES_NOT_EXIST, "指定的服务不存在。"),
             (
                 ServiceError::ERROR_SERVICE_LOGON_FAILED,
                 "由于登录失败而无法启动服务。 如果将服务配置为在没有“作为服务登录”权限的帐户下运行，则会发生此错误。"
             ),
+            (
+                ServiceError::ERROR_SERVICE_NEVER_STARTED,
+                "自上次启动计算机以来，未尝试启动该服务。"
+            ),
             (
                 ServiceError::ERROR_SERVICE_NO_THREAD,
                 "无法为服务创建线程。"
             ),
+            (
+                ServiceError::ERROR_SERVICE_NOT_ACTIVE,
+                "服务尚未启动。"
+            ),
             (
                 ServiceError::ERROR_SERVICE_REQUEST_TIMEOUT,
                 "服务的进程已启动，但它未调用 StartServiceCtrlDispatcher，或者调用 StartServiceCtrlDispatcher 的线程可能在控制处理程序函数中被阻止。"
-            )
+            ),
+            (ServiceError::ERROR_BAD_NETPATH, "找不到网络路径,请检查远程计算机名称以及网络连接。"),
+            (ServiceError::RPC_S_SERVER_UNAVAILABLE, "RPC 服务器不可用,远程计算机可能未开机、不可达或被防火墙阻止。"),
+            (ServiceError::RPC_S_SERVER_TOO_BUSY, "RPC 服务器太忙,无法完成此操作,请稍后重试。"),
+            (ServiceError::RPC_S_CALL_FAILED, "远程过程调用失败,与远程计算机的连接可能已断开。"),
+            (ServiceError::RPC_S_CALL_FAILED_DNE, "远程过程调用失败且未执行。"),
+            (ServiceError::EPT_S_NOT_REGISTERED, "终结点映射器中没有更多的终结点可用,远程计算机上的服务控制管理器可能不可访问。"),
+            (ServiceError::SERVICE_NOT_STOPPABLE, "服务不接受停止控制,无法停止该服务。"),
+            (ServiceError::ERROR_NOT_SUPPORTED, "不支持该请求。"),
+            (
+                ServiceError::ERROR_INVALID_LEVEL,
+                "当前系统不支持该扩展配置的信息级别,较早的Windows版本没有触发器、启动保护、预关机等配置。",
+            ),
+            (ServiceError::ERROR_BOOT_ALREADY_ACCEPTED, "本次启动的配置已经被接受为最近一次的正确配置。"),
+            (
+                ServiceError::BUFFER_STILL_TOO_SMALL,
+                "多次扩大缓冲区后仍然无法容纳查询结果,服务配置可能在查询期间被其他进程反复修改,请稍后重试。",
+            ),
+            (
+                ServiceError::SERVICE_CREATED_NOT_QUERIED,
+                "服务已创建,但查询其配置失败,且删除该服务也失败,服务仍然存在,请手动检查或删除。",
+            ),
+            (
+                ServiceError::SERVICE_CREATED_NOT_STARTED,
+                "服务已创建,但启动失败,且删除该服务也失败,服务仍然存在,请手动检查或删除。",
+            ),
+            (
+                ServiceError::REQUIRES_ELEVATION,
+                "拒绝访问:创建服务需要管理员权限,请以管理员身份运行(在开启UAC的系统上需要右键\"以管理员身份运行\")。",
+            ),
+            (ServiceError::BINARY_NOT_SIGNED, "可执行文件没有Authenticode签名。"),
+            (
+                ServiceError::BINARY_NOT_TRUSTED,
+                "可执行文件的Authenticode签名无效或不受信任(文件已被修改、证书已过期或被吊销、根证书不受信任等)。",
+            ),
+            (ServiceError::INSUFFICIENT_ACCESS_DELETE, "访问权限不足:需要DELETE。"),
+            (ServiceError::INSUFFICIENT_ACCESS_SERVICE_CHANGE_CONFIG, "访问权限不足:需要SERVICE_CHANGE_CONFIG。"),
+            (
+                ServiceError::INSUFFICIENT_ACCESS_SERVICE_ENUMERATE_DEPENDENTS,
+                "访问权限不足:需要SERVICE_ENUMERATE_DEPENDENTS。",
+            ),
+            (ServiceError::INSUFFICIENT_ACCESS_SERVICE_INTERROGATE, "访问权限不足:需要SERVICE_INTERROGATE。"),
+            (ServiceError::INSUFFICIENT_ACCESS_SERVICE_PAUSE_CONTINUE, "访问权限不足:需要SERVICE_PAUSE_CONTINUE。"),
+            (ServiceError::INSUFFICIENT_ACCESS_SERVICE_QUERY_CONFIG, "访问权限不足:需要SERVICE_QUERY_CONFIG。"),
+            (ServiceError::INSUFFICIENT_ACCESS_SERVICE_QUERY_STATUS, "访问权限不足:需要SERVICE_QUERY_STATUS。"),
+            (ServiceError::INSUFFICIENT_ACCESS_SERVICE_START, "访问权限不足:需要SERVICE_START。"),
+            (ServiceError::INSUFFICIENT_ACCESS_SERVICE_STOP, "访问权限不足:需要SERVICE_STOP。"),
+            (
+                ServiceError::INSUFFICIENT_ACCESS_SERVICE_USER_DEFINED_CONTROL,
+                "访问权限不足:需要SERVICE_USER_DEFINED_CONTROL。",
+            ),
         ]);
         map
     };
 }
 
-#[derive(Debug, FromInto)]
+/// 由错误常量的标识符生成(错误, 常量名称)的映射,名称与常量的标识符保持一致
+macro_rules! error_names {
+    ($($name:ident),* $(,)?) => {
+        HashMap::from([$((ServiceError::$name, stringify!($name))),*])
+    };
+}
+
+lazy_static! {
+    static ref SERVICE_ERROR_NAMES: HashMap<ServiceError, &'static str> = error_names![
+        ERROR_ACCESS_DENIED,
+        ERROR_CIRCULAR_DEPENDENCY,
+        ERROR_DUPLICATE_SERVICE_NAME,
+        ERROR_INVALID_HANDLE,
+        ERROR_INVALID_NAME,
+        ERROR_INVALID_PARAMETER,
+        ERROR_INVALID_SERVICE_ACCOUNT,
+        ERROR_INVALID_SERVICE_CONTROL,
+        ERROR_SERVICE_EXISTS,
+        ERROR_SERVICE_MARKED_FOR_DELETE,
+        ERROR_PATH_NOT_FOUND,
+        ERROR_SERVICE_ALREADY_RUNNING,
+        ERROR_SERVICE_CANNOT_ACCEPT_CTRL,
+        ERROR_SERVICE_DATABASE_LOCKED,
+        ERROR_SERVICE_DEPENDENCY_DELETED,
+        ERROR_SERVICE_DEPENDENCY_FAIL,
+        ERROR_SERVICE_DISABLED,
+        ERROR_SERVICE_DOES_NOT_EXIST,
+        ERROR_SERVICE_LOGON_FAILED,
+        ERROR_SERVICE_NEVER_STARTED,
+        ERROR_SERVICE_NO_THREAD,
+        ERROR_SERVICE_NOT_ACTIVE,
+        ERROR_SERVICE_REQUEST_TIMEOUT,
+        ERROR_BAD_NETPATH,
+        RPC_S_SERVER_UNAVAILABLE,
+        RPC_S_SERVER_TOO_BUSY,
+        RPC_S_CALL_FAILED,
+        RPC_S_CALL_FAILED_DNE,
+        EPT_S_NOT_REGISTERED,
+        SERVICE_NOT_STOPPABLE,
+        ERROR_NOT_SUPPORTED,
+        ERROR_INVALID_LEVEL,
+        ERROR_BOOT_ALREADY_ACCEPTED,
+        BUFFER_STILL_TOO_SMALL,
+        SERVICE_CREATED_NOT_QUERIED,
+        SERVICE_CREATED_NOT_STARTED,
+        REQUIRES_ELEVATION,
+        BINARY_NOT_SIGNED,
+        BINARY_NOT_TRUSTED,
+        INSUFFICIENT_ACCESS_DELETE,
+        INSUFFICIENT_ACCESS_SERVICE_CHANGE_CONFIG,
+        INSUFFICIENT_ACCESS_SERVICE_ENUMERATE_DEPENDENTS,
+        INSUFFICIENT_ACCESS_SERVICE_INTERROGATE,
+        INSUFFICIENT_ACCESS_SERVICE_PAUSE_CONTINUE,
+        INSUFFICIENT_ACCESS_SERVICE_QUERY_CONFIG,
+        INSUFFICIENT_ACCESS_SERVICE_QUERY_STATUS,
+        INSUFFICIENT_ACCESS_SERVICE_START,
+        INSUFFICIENT_ACCESS_SERVICE_STOP,
+        INSUFFICIENT_ACCESS_SERVICE_USER_DEFINED_CONTROL,
+    ];
+}
+
+#[derive(Debug, Clone, Copy, FromInto)]
+#[must_use]
 pub struct ServiceStatus(SERVICE_STATUS_CURRENT_STATE);
 
 impl Hash for ServiceStatus {
@@ -191,9 +561,60 @@ lazy_static! {
     Services::SERVICE_STOP_PENDING,
     Services::SERVICE_STOPPED
 )]
-impl ServiceStatus {}
+impl ServiceStatus {
+    /// # 是否处于挂起(*_PENDING)状态
+    pub fn is_pending(&self) -> bool {
+        *self == ServiceStatus::SERVICE_CONTINUE_PENDING
+            || *self == ServiceStatus::SERVICE_PAUSE_PENDING
+            || *self == ServiceStatus::SERVICE_START_PENDING
+            || *self == ServiceStatus::SERVICE_STOP_PENDING
+    }
 
-#[derive(FromInto)]
+    /// # 能否从当前状态直接转换到目标状态
+    /// 按SCM的状态机判断,目标与当前状态相同时返回true(挂起状态下更新检查点)。
+    /// - 停止只能转换为启动中,不能直接暂停或运行
+    /// - 进程退出时SCM直接将服务置为已停止,因此除已停止外的任何状态都可以转换为已停止
+    /// - 挂起的操作失败时回到原来的状态,例如暂停中回到运行、继续中回到已暂停
+    /// - 启动中、运行、暂停中、已暂停和继续中都可以接受停止请求进入停止中
+    ///
+    /// 未知的状态总是返回false
+    /// ## 参数
+    /// ### input:
+    /// - target: 目标状态
+    /// ### output:
+    /// - bool
+    /// ## 例子
+    /// ```
+    /// use windows_service_controller::dword::ServiceStatus;
+    /// assert!(ServiceStatus::SERVICE_RUNNING.can_transition_to(ServiceStatus::SERVICE_PAUSE_PENDING));
+    /// assert!(!ServiceStatus::SERVICE_STOPPED.can_transition_to(ServiceStatus::SERVICE_PAUSED));
+    /// ```
+    pub fn can_transition_to(&self, target: ServiceStatus) -> bool {
+        let targets: &[ServiceStatus] = match self.0 {
+            Services::SERVICE_STOPPED => &[ServiceStatus::SERVICE_START_PENDING],
+            Services::SERVICE_START_PENDING => &[ServiceStatus::SERVICE_RUNNING, ServiceStatus::SERVICE_STOP_PENDING],
+            Services::SERVICE_RUNNING => &[ServiceStatus::SERVICE_PAUSE_PENDING, ServiceStatus::SERVICE_STOP_PENDING],
+            Services::SERVICE_PAUSE_PENDING => &[
+                ServiceStatus::SERVICE_PAUSED,
+                ServiceStatus::SERVICE_RUNNING,
+                ServiceStatus::SERVICE_STOP_PENDING,
+            ],
+            Services::SERVICE_PAUSED => &[ServiceStatus::SERVICE_CONTINUE_PENDING, ServiceStatus::SERVICE_STOP_PENDING],
+            Services::SERVICE_CONTINUE_PENDING => &[
+                ServiceStatus::SERVICE_RUNNING,
+                ServiceStatus::SERVICE_PAUSED,
+                ServiceStatus::SERVICE_STOP_PENDING,
+            ],
+            Services::SERVICE_STOP_PENDING => &[],
+            _ => return false,
+        };
+        *self == target
+            || targets.contains(&target)
+            || (target == ServiceStatus::SERVICE_STOPPED && *self != ServiceStatus::SERVICE_STOPPED)
+    }
+}
+
+#[derive(Debug, Clone, Copy, FromInto)]
 pub struct ScManagerAccess(u32);
 
 #[self_attr(
@@ -212,9 +633,21 @@ impl ScManagerAccess {
         ScManagerAccess(Services::SC_MANAGER_CREATE_SERVICE | Services::SC_MANAGER_MODIFY_BOOT_CONFIG);
     pub const GENERIC_EXECUTE: ScManagerAccess = ScManagerAccess(Services::SC_MANAGER_CONNECT | Services::SC_MANAGER_LOCK);
     pub const GENERIC_ALL: ScManagerAccess = ScManagerAccess::SC_MANAGER_ALL_ACCESS;
+    /// 枚举服务所需的最小权限(SC_MANAGER_CONNECT|SC_MANAGER_ENUMERATE_SERVICE),
+    /// 普通用户默认拥有,enumerate模块的函数都只请求该权限
+    pub const ENUMERATE: ScManagerAccess =
+        ScManagerAccess(Services::SC_MANAGER_CONNECT | Services::SC_MANAGER_ENUMERATE_SERVICE);
 }
 
-#[derive(FromInto)]
+impl BitOr for ScManagerAccess {
+    type Output = ScManagerAccess;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        ScManagerAccess(self.0 | rhs.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, FromInto)]
 pub struct ServiceAccess(u32);
 
 #[self_attr(
@@ -234,6 +667,7 @@ impl ServiceAccess {
     pub const READ_CONTROL: ServiceAccess = ServiceAccess(0x20000u32);
     pub const WRITE_DAC: ServiceAccess = ServiceAccess(0x40000u32);
     pub const WRITE_OWNER: ServiceAccess = ServiceAccess(0x80000u32);
+    pub const ACCESS_SYSTEM_SECURITY: ServiceAccess = ServiceAccess(0x100_0000u32);
     pub const GENERIC_READ: ServiceAccess = ServiceAccess(Services::SERVICE_QUERY_CONFIG
         | Services::SERVICE_QUERY_STATUS
         | Services::SERVICE_INTERROGATE
@@ -241,24 +675,94 @@ impl ServiceAccess {
     pub const GENERIC_WRITE: ServiceAccess = ServiceAccess::SERVICE_CHANGE_CONFIG;
     pub const GENERIC_EXECUTE: ServiceAccess =
         ServiceAccess(Services::SERVICE_START | Services::SERVICE_STOP | Services::SERVICE_PAUSE_CONTINUE | Services::SERVICE_USER_DEFINED_CONTROL);
+
+    /// # 是否包含指定的访问权限
+    pub fn contains(&self, other: ServiceAccess) -> bool {
+        self.0 & other.0 == other.0
+    }
 }
 
-#[derive(FromInto)]
+impl BitOr for ServiceAccess {
+    type Output = ServiceAccess;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        ServiceAccess(self.0 | rhs.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, FromInto)]
 pub struct ServiceType(ENUM_SERVICE_TYPE);
 
+impl Hash for ServiceType {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.0.hash(state)
+    }
+}
+
+impl PartialEq for ServiceType {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl Eq for ServiceType {}
+
 #[self_attr(
     Services::SERVICE_WIN32_OWN_PROCESS,
     Services::SERVICE_ADAPTER,
+    Services::SERVICE_DRIVER,
     Services::SERVICE_FILE_SYSTEM_DRIVER,
     Services::SERVICE_KERNEL_DRIVER,
     Services::SERVICE_RECOGNIZER_DRIVER,
-    Services::SERVICE_WIN32_SHARE_PROCESS
+    Services::SERVICE_WIN32,
+    Services::SERVICE_WIN32_SHARE_PROCESS,
+    Services::SERVICE_USER_OWN_PROCESS,
+    Services::SERVICE_USER_SHARE_PROCESS
 )]
 impl ServiceType {}
 
-#[derive(FromInto)]
+/// 服务类型的基本类型位(驱动、WIN32进程)
+const SERVICE_TYPE_BASE: u32 = 0x3F;
+/// 服务类型中所有已知的位:基本类型、每用户服务(0x40、0x80)、交互式进程(0x100)、打包服务(0x200)
+const SERVICE_TYPE_KNOWN: u32 = SERVICE_TYPE_BASE | 0x40 | 0x80 | 0x100 | 0x200;
+
+/// 从不受信任的来源(注册表、远程接口等)读取的DWORD转换为服务类型,
+/// 不包含基本类型位或者包含未知的位时返回ERROR_INVALID_PARAMETER
+impl TryFrom<u32> for ServiceType {
+    type Error = ServiceError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        if value & SERVICE_TYPE_BASE == 0 || value & !SERVICE_TYPE_KNOWN != 0 {
+            return Err(ServiceError::ERROR_INVALID_PARAMETER);
+        }
+        Ok(ServiceType(ENUM_SERVICE_TYPE(value)))
+    }
+}
+
+#[derive(Debug, Clone, Copy, FromInto)]
 pub struct ServiceStartType(SERVICE_START_TYPE);
 
+impl Hash for ServiceStartType {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.0.hash(state)
+    }
+}
+
+impl PartialEq for ServiceStartType {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl Eq for ServiceStartType {}
+
+/// 默认为按需启动(SERVICE_DEMAND_START),与`sc create`一致
+impl Default for ServiceStartType {
+    fn default() -> Self {
+        ServiceStartType::SERVICE_DEMAND_START
+    }
+}
+
 #[self_attr(
     Services::SERVICE_AUTO_START,
     Services::SERVICE_BOOT_START,
@@ -268,10 +772,43 @@ pub struct ServiceStartType(SERVICE_START_TYPE);
 )]
 impl ServiceStartType {}
 
+/// 从不受信任的来源读取的DWORD转换为启动类型,不是SERVICE_BOOT_START到SERVICE_DISABLED(0-4)时返回ERROR_INVALID_PARAMETER
+impl TryFrom<u32> for ServiceStartType {
+    type Error = ServiceError;
 
-#[derive(FromInto)]
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        if value > Services::SERVICE_DISABLED.0 {
+            return Err(ServiceError::ERROR_INVALID_PARAMETER);
+        }
+        Ok(ServiceStartType(SERVICE_START_TYPE(value)))
+    }
+}
+
+
+#[derive(Debug, Clone, Copy, FromInto)]
 pub struct ServiceErrorControl(SERVICE_ERROR);
 
+impl Hash for ServiceErrorControl {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.0.hash(state)
+    }
+}
+
+impl PartialEq for ServiceErrorControl {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl Eq for ServiceErrorControl {}
+
+/// 默认为SERVICE_ERROR_NORMAL,与`sc create`一致
+impl Default for ServiceErrorControl {
+    fn default() -> Self {
+        ServiceErrorControl::SERVICE_ERROR_NORMAL
+    }
+}
+
 #[self_attr(
     Services::SERVICE_ERROR_CRITICAL,
     Services::SERVICE_ERROR_IGNORE,
@@ -280,6 +817,18 @@ pub struct ServiceErrorControl(SERVICE_ERROR);
 )]
 impl ServiceErrorControl {}
 
+/// 从不受信任的来源读取的DWORD转换为错误控制,不是SERVICE_ERROR_IGNORE到SERVICE_ERROR_CRITICAL(0-3)时返回ERROR_INVALID_PARAMETER
+impl TryFrom<u32> for ServiceErrorControl {
+    type Error = ServiceError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        if value > Services::SERVICE_ERROR_CRITICAL.0 {
+            return Err(ServiceError::ERROR_INVALID_PARAMETER);
+        }
+        Ok(ServiceErrorControl(SERVICE_ERROR(value)))
+    }
+}
+
 #[derive(FromInto)]
 pub struct ServiceControlCode(u32);
 
@@ -294,4 +843,304 @@ pub struct ServiceControlCode(u32);
     Services::SERVICE_CONTROL_PAUSE,
     Services::SERVICE_CONTROL_STOP
 )]
-impl ServiceControlCode {}
\ No newline at end of file
+impl ServiceControlCode {
+    /// # 服务接受该控制代码所需的SERVICE_ACCEPT_*标志
+    /// 发送控制前可以与服务状态的dwControlsAccepted比较,判断服务是否接受该控制
+    /// ## 参数
+    /// ### output:
+    /// - Option<ControlsAccepted>: SERVICE_CONTROL_INTERROGATE和用户自定义控制代码(128-255)没有对应的标志,返回None
+    /// ## 例子
+    /// ```
+    /// use windows_service_controller::dword::{ControlsAccepted, ServiceControlCode};
+    /// let flag = ServiceControlCode::SERVICE_CONTROL_STOP.required_accept_flag().unwrap();
+    /// assert!(flag.contains(ControlsAccepted::SERVICE_ACCEPT_STOP));
+    /// ```
+    pub fn required_accept_flag(&self) -> Option<ControlsAccepted> {
+        let flag = match self.0 {
+            Services::SERVICE_CONTROL_STOP => Services::SERVICE_ACCEPT_STOP,
+            Services::SERVICE_CONTROL_PAUSE | Services::SERVICE_CONTROL_CONTINUE => Services::SERVICE_ACCEPT_PAUSE_CONTINUE,
+            Services::SERVICE_CONTROL_PARAMCHANGE => Services::SERVICE_ACCEPT_PARAMCHANGE,
+            Services::SERVICE_CONTROL_NETBINDADD
+            | Services::SERVICE_CONTROL_NETBINDREMOVE
+            | Services::SERVICE_CONTROL_NETBINDENABLE
+            | Services::SERVICE_CONTROL_NETBINDDISABLE => Services::SERVICE_ACCEPT_NETBINDCHANGE,
+            Services::SERVICE_CONTROL_SHUTDOWN => Services::SERVICE_ACCEPT_SHUTDOWN,
+            Services::SERVICE_CONTROL_PRESHUTDOWN => Services::SERVICE_ACCEPT_PRESHUTDOWN,
+            Services::SERVICE_CONTROL_HARDWAREPROFILECHANGE => Services::SERVICE_ACCEPT_HARDWAREPROFILECHANGE,
+            Services::SERVICE_CONTROL_POWEREVENT => Services::SERVICE_ACCEPT_POWEREVENT,
+            Services::SERVICE_CONTROL_SESSIONCHANGE => Services::SERVICE_ACCEPT_SESSIONCHANGE,
+            Services::SERVICE_CONTROL_TIMECHANGE => Services::SERVICE_ACCEPT_TIMECHANGE,
+            Services::SERVICE_CONTROL_TRIGGEREVENT => Services::SERVICE_ACCEPT_TRIGGEREVENT,
+            _ => return None,
+        };
+        Some(ControlsAccepted(flag))
+    }
+
+    /// # 服务处理该控制后应当到达的稳定状态
+    /// 用作control_and_wait的expected参数的默认值
+    /// ## 参数
+    /// ### output:
+    /// - Option<ServiceStatus>: 停止为SERVICE_STOPPED,暂停为SERVICE_PAUSED,继续为SERVICE_RUNNING;
+    ///   其他控制代码(包括用户自定义控制代码)不改变服务状态,返回None
+    /// ## 例子
+    /// ```
+    /// use windows_service_controller::dword::{ServiceControlCode, ServiceStatus};
+    /// let expected = ServiceControlCode::SERVICE_CONTROL_PAUSE.expected_state();
+    /// assert_eq!(expected, Some(ServiceStatus::SERVICE_PAUSED));
+    /// ```
+    pub fn expected_state(&self) -> Option<ServiceStatus> {
+        match self.0 {
+            Services::SERVICE_CONTROL_STOP => Some(ServiceStatus::SERVICE_STOPPED),
+            Services::SERVICE_CONTROL_PAUSE => Some(ServiceStatus::SERVICE_PAUSED),
+            Services::SERVICE_CONTROL_CONTINUE => Some(ServiceStatus::SERVICE_RUNNING),
+            _ => None,
+        }
+    }
+}
+
+/// 扩展配置的信息级别(QueryServiceConfig2W/ChangeServiceConfig2W的dwInfoLevel)
+#[derive(Debug, Clone, Copy, FromInto)]
+pub struct ServiceConfigInfoLevel(SERVICE_CONFIG);
+
+#[self_attr(
+    Services::SERVICE_CONFIG_DELAYED_AUTO_START_INFO,
+    Services::SERVICE_CONFIG_DESCRIPTION,
+    Services::SERVICE_CONFIG_FAILURE_ACTIONS,
+    Services::SERVICE_CONFIG_FAILURE_ACTIONS_FLAG,
+    Services::SERVICE_CONFIG_LAUNCH_PROTECTED,
+    Services::SERVICE_CONFIG_PREFERRED_NODE,
+    Services::SERVICE_CONFIG_PRESHUTDOWN_INFO,
+    Services::SERVICE_CONFIG_REQUIRED_PRIVILEGES_INFO,
+    Services::SERVICE_CONFIG_SERVICE_SID_INFO,
+    Services::SERVICE_CONFIG_TRIGGER_INFO
+)]
+impl ServiceConfigInfoLevel {}
+
+/// 服务的启动保护级别(SERVICE_LAUNCH_PROTECTED_INFO的dwLaunchProtected)
+/// 受保护的服务以受保护进程(PPL)运行,未受保护的进程(包括管理员进程)无法注入或终止它
+#[derive(Debug, Clone, Copy, FromInto)]
+pub struct LaunchProtected(u32);
+
+impl PartialEq for LaunchProtected {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl Eq for LaunchProtected {}
+
+#[self_attr(
+    Services::SERVICE_LAUNCH_PROTECTED_ANTIMALWARE_LIGHT,
+    Services::SERVICE_LAUNCH_PROTECTED_NONE,
+    Services::SERVICE_LAUNCH_PROTECTED_WINDOWS,
+    Services::SERVICE_LAUNCH_PROTECTED_WINDOWS_LIGHT
+)]
+impl LaunchProtected {}
+
+#[derive(Debug, Clone, Copy, FromInto)]
+pub struct ControlsAccepted(u32);
+
+#[self_attr(
+    Services::SERVICE_ACCEPT_HARDWAREPROFILECHANGE,
+    Services::SERVICE_ACCEPT_NETBINDCHANGE,
+    Services::SERVICE_ACCEPT_PARAMCHANGE,
+    Services::SERVICE_ACCEPT_PAUSE_CONTINUE,
+    Services::SERVICE_ACCEPT_POWEREVENT,
+    Services::SERVICE_ACCEPT_PRESHUTDOWN,
+    Services::SERVICE_ACCEPT_SESSIONCHANGE,
+    Services::SERVICE_ACCEPT_SHUTDOWN,
+    Services::SERVICE_ACCEPT_STOP,
+    Services::SERVICE_ACCEPT_TIMECHANGE,
+    Services::SERVICE_ACCEPT_TRIGGEREVENT,
+    Services::SERVICE_ACCEPT_USER_LOGOFF
+)]
+impl ControlsAccepted {
+    /// # 是否接受指定的控制
+    pub fn contains(&self, other: ControlsAccepted) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+/// 服务所在进程的标志(dwServiceFlags)
+#[derive(Debug, Clone, Copy, FromInto)]
+pub struct ServiceFlags(SERVICE_RUNS_IN_PROCESS);
+
+impl PartialEq for ServiceFlags {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl Eq for ServiceFlags {}
+
+#[self_attr(
+    Services::SERVICE_RUNS_IN_SYSTEM_PROCESS
+)]
+impl ServiceFlags {
+    /// # 是否包含指定的标志
+    pub fn contains(&self, other: ServiceFlags) -> bool {
+        self.0.0 & other.0.0 == other.0.0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use windows::Win32::Foundation::WIN32_ERROR;
+
+    use crate::dword::{
+        ControlsAccepted, ScManagerAccess, ServiceAccess, ServiceControlCode, ServiceError, ServiceErrorControl,
+        ServiceErrorKind, ServiceStartType, ServiceStatus, ServiceType, SERVICE_ERRORS, SERVICE_ERROR_NAMES,
+    };
+
+    #[test]
+    fn error_kind_eq() {
+        assert!(ServiceError::ERROR_ACCESS_DENIED == ServiceErrorKind::AccessDenied);
+        assert!(ServiceError::ERROR_SERVICE_NOT_ACTIVE == ServiceErrorKind::ServiceNotActive);
+        assert!(ServiceError::ERROR_ACCESS_DENIED != ServiceErrorKind::InvalidHandle);
+        assert_eq!(ServiceError::ERROR_INVALID_NAME.kind(), ServiceErrorKind::InvalidName);
+        assert!(ServiceError::REQUIRES_ELEVATION == ServiceErrorKind::AccessDenied);
+        assert!(ServiceError::REQUIRES_ELEVATION.to_string().contains("管理员"));
+        assert!(ServiceError::ERROR_INVALID_LEVEL.is_unsupported_level());
+        assert_eq!(ServiceError::ERROR_INVALID_LEVEL.kind(), ServiceErrorKind::UnsupportedLevel);
+        assert!(!ServiceError::ERROR_INVALID_PARAMETER.is_unsupported_level());
+        assert_eq!(ServiceError::ERROR_SERVICE_DOES_NOT_EXIST.kind(), ServiceErrorKind::ServiceDoesNotExist);
+        assert_eq!(ServiceError::ERROR_NOT_SUPPORTED.kind(), ServiceErrorKind::NotSupported);
+        assert_eq!(ServiceError::ERROR_BOOT_ALREADY_ACCEPTED.kind(), ServiceErrorKind::BootAlreadyAccepted);
+        assert_eq!(ServiceError::RPC_S_SERVER_UNAVAILABLE.kind(), ServiceErrorKind::Rpc);
+        assert_eq!(ServiceError::ERROR_BAD_NETPATH.kind(), ServiceErrorKind::Rpc);
+        assert_eq!(ServiceError::BINARY_NOT_TRUSTED.kind(), ServiceErrorKind::UntrustedBinary);
+        assert_eq!(ServiceError::SERVICE_CREATED_NOT_STARTED.kind(), ServiceErrorKind::ServiceCreatedNotStarted);
+        assert_eq!(ServiceError::from(WIN32_ERROR(123_456)).kind(), ServiceErrorKind::Other);
+    }
+
+    #[test]
+    fn error_display_name() {
+        assert_eq!(ServiceError::ERROR_SERVICE_NOT_ACTIVE.name(), Some("ERROR_SERVICE_NOT_ACTIVE"));
+        assert_eq!(ServiceError::SERVICE_NOT_STOPPABLE.name(), Some("SERVICE_NOT_STOPPABLE"));
+        assert!(ServiceError::ERROR_ACCESS_DENIED.to_string().starts_with("错误(5, ERROR_ACCESS_DENIED):"));
+        assert!(ServiceError::INSUFFICIENT_ACCESS_SERVICE_STOP.to_string().contains("INSUFFICIENT_ACCESS_SERVICE_STOP"));
+        let unknown = ServiceError::from(WIN32_ERROR(123_456));
+        assert_eq!(unknown.name(), None);
+        assert!(unknown.to_string().starts_with("未知错误(123456)"));
+    }
+
+    #[test]
+    fn error_tables_consistent() {
+        // 两张表分别维护,新增错误时必须同时添加描述和名称
+        for error in SERVICE_ERRORS.keys() {
+            assert!(SERVICE_ERROR_NAMES.contains_key(error), "错误{}没有名称", error.0.0);
+        }
+        for (error, name) in SERVICE_ERROR_NAMES.iter() {
+            assert!(SERVICE_ERRORS.contains_key(error), "{}没有描述", name);
+        }
+        assert_eq!(SERVICE_ERRORS.len(), SERVICE_ERROR_NAMES.len());
+    }
+
+    #[test]
+    fn insufficient_access() {
+        let error = ServiceError::INSUFFICIENT_ACCESS_SERVICE_STOP;
+        assert_eq!(error.kind(), ServiceErrorKind::InsufficientAccess);
+        assert!(error.to_string().contains("SERVICE_STOP"));
+        assert!(ServiceAccess::GENERIC_EXECUTE.contains(ServiceAccess::SERVICE_STOP));
+        assert!(!ServiceAccess::GENERIC_READ.contains(ServiceAccess::SERVICE_STOP));
+        assert_eq!(ServiceError::SERVICE_NOT_STOPPABLE.kind(), ServiceErrorKind::ServiceNotStoppable);
+        // 组合的权限只报告缺少的第一个
+        let required = ServiceAccess::SERVICE_QUERY_CONFIG | ServiceAccess::SERVICE_QUERY_STATUS;
+        assert_eq!(
+            ServiceError::missing_access(ServiceAccess::SERVICE_QUERY_CONFIG, required),
+            Some(ServiceError::INSUFFICIENT_ACCESS_SERVICE_QUERY_STATUS)
+        );
+        assert_eq!(
+            ServiceError::missing_access(ServiceAccess::DELETE, required),
+            Some(ServiceError::INSUFFICIENT_ACCESS_SERVICE_QUERY_CONFIG)
+        );
+        assert_eq!(ServiceError::missing_access(ServiceAccess::GENERIC_READ, required), None);
+        // 没有单独常量的权限也归类为InsufficientAccess,并显示所需的权限
+        let error = ServiceError::insufficient_access(ServiceAccess::ACCESS_SYSTEM_SECURITY);
+        assert_eq!(error.kind(), ServiceErrorKind::InsufficientAccess);
+        assert!(error.to_string().contains("0x1000000"));
+    }
+
+    #[test]
+    fn rpc_error() {
+        assert!(ServiceError::RPC_S_SERVER_UNAVAILABLE.is_rpc_error());
+        assert!(ServiceError::ERROR_BAD_NETPATH.is_rpc_error());
+        assert!(!ServiceError::ERROR_ACCESS_DENIED.is_rpc_error());
+        assert!(!ServiceError::RPC_S_CALL_FAILED.to_string().contains("未知错误"));
+    }
+
+    #[test]
+    fn service_type_as_key() {
+        let mut map: HashMap<ServiceType, usize> = HashMap::new();
+        *map.entry(ServiceType::SERVICE_WIN32_OWN_PROCESS).or_default() += 1;
+        *map.entry(ServiceType::SERVICE_WIN32_OWN_PROCESS).or_default() += 1;
+        *map.entry(ServiceType::SERVICE_KERNEL_DRIVER).or_default() += 1;
+        assert_eq!(map[&ServiceType::SERVICE_WIN32_OWN_PROCESS], 2);
+        assert_eq!(map.len(), 2);
+    }
+
+    #[test]
+    fn status_transition() {
+        let stopped = ServiceStatus::SERVICE_STOPPED;
+        let running = ServiceStatus::SERVICE_RUNNING;
+        let paused = ServiceStatus::SERVICE_PAUSED;
+        assert!(stopped.can_transition_to(ServiceStatus::SERVICE_START_PENDING));
+        assert!(!stopped.can_transition_to(paused));
+        assert!(!stopped.can_transition_to(running));
+        assert!(!stopped.can_transition_to(ServiceStatus::SERVICE_STOP_PENDING));
+        assert!(stopped.can_transition_to(stopped));
+        assert!(ServiceStatus::SERVICE_START_PENDING.can_transition_to(stopped));
+        assert!(running.can_transition_to(ServiceStatus::SERVICE_PAUSE_PENDING));
+        assert!(!running.can_transition_to(paused));
+        assert!(ServiceStatus::SERVICE_PAUSE_PENDING.can_transition_to(running));
+        assert!(paused.can_transition_to(ServiceStatus::SERVICE_CONTINUE_PENDING));
+        assert!(!paused.can_transition_to(running));
+        assert!(ServiceStatus::SERVICE_STOP_PENDING.can_transition_to(stopped));
+        assert!(!ServiceStatus::SERVICE_STOP_PENDING.can_transition_to(running));
+        let unknown = ServiceStatus::from(windows::Win32::System::Services::SERVICE_STATUS_CURRENT_STATE(42));
+        assert!(!unknown.can_transition_to(unknown));
+        assert!(!running.can_transition_to(unknown));
+    }
+
+    #[test]
+    fn enumerate_access() {
+        assert_eq!(
+            u32::from(ScManagerAccess::ENUMERATE),
+            u32::from(ScManagerAccess::SC_MANAGER_CONNECT | ScManagerAccess::SC_MANAGER_ENUMERATE_SERVICE)
+        );
+    }
+
+    #[test]
+    fn control_accept_flag() {
+        let flag = ServiceControlCode::SERVICE_CONTROL_CONTINUE.required_accept_flag().unwrap();
+        assert!(flag.contains(ControlsAccepted::SERVICE_ACCEPT_PAUSE_CONTINUE));
+        let flag = ServiceControlCode::SERVICE_CONTROL_NETBINDENABLE.required_accept_flag().unwrap();
+        assert!(flag.contains(ControlsAccepted::SERVICE_ACCEPT_NETBINDCHANGE));
+        assert!(ServiceControlCode::SERVICE_CONTROL_INTERROGATE.required_accept_flag().is_none());
+        assert!(ServiceControlCode::from(200u32).required_accept_flag().is_none());
+    }
+
+    #[test]
+    fn control_expected_state() {
+        assert_eq!(ServiceControlCode::SERVICE_CONTROL_STOP.expected_state(), Some(ServiceStatus::SERVICE_STOPPED));
+        assert_eq!(ServiceControlCode::SERVICE_CONTROL_CONTINUE.expected_state(), Some(ServiceStatus::SERVICE_RUNNING));
+        assert_eq!(ServiceControlCode::SERVICE_CONTROL_PARAMCHANGE.expected_state(), None);
+        assert_eq!(ServiceControlCode::from(200u32).expected_state(), None);
+    }
+
+    #[test]
+    fn try_from_dword() {
+        assert_eq!(ServiceStartType::try_from(2), Ok(ServiceStartType::SERVICE_AUTO_START));
+        assert_eq!(ServiceStartType::try_from(5), Err(ServiceError::ERROR_INVALID_PARAMETER));
+        assert_eq!(ServiceErrorControl::try_from(3), Ok(ServiceErrorControl::SERVICE_ERROR_CRITICAL));
+        assert_eq!(ServiceErrorControl::try_from(4), Err(ServiceError::ERROR_INVALID_PARAMETER));
+        assert_eq!(ServiceType::try_from(0x10), Ok(ServiceType::SERVICE_WIN32_OWN_PROCESS));
+        assert!(ServiceType::try_from(0x110).is_ok());
+        assert!(ServiceType::try_from(0x60).is_ok());
+        assert_eq!(ServiceType::try_from(0), Err(ServiceError::ERROR_INVALID_PARAMETER));
+        assert_eq!(ServiceType::try_from(0x100), Err(ServiceError::ERROR_INVALID_PARAMETER));
+        assert_eq!(ServiceType::try_from(0x1010), Err(ServiceError::ERROR_INVALID_PARAMETER));
+    }
+}
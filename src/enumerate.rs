@@ -0,0 +1,894 @@
+//! 枚举与批量查询服务
+//!
+//! 枚举只向SCM请求ScManagerAccess::ENUMERATE(SC_MANAGER_CONNECT|SC_MANAGER_ENUMERATE_SERVICE),
+//! 普通用户也可以得到所有服务的名称和状态。需要逐个打开服务的函数(find_by_binary_path、find_orphaned、
+//! query_configs、query_many_status、for_each_service)还受每个服务自身的安全描述符限制,
+//! 普通用户可能无法查询部分服务的配置,这些服务会被跳过或返回各自的错误
+
+use std::collections::HashMap;
+
+use windows::core::{PCWSTR, PWSTR};
+use windows::Win32::Foundation::{ERROR_INVALID_DATA, ERROR_MORE_DATA, GetLastError};
+use windows::Win32::System::Services::{ENUM_SERVICE_STATUS_PROCESSW, ENUM_SERVICE_STATUSW, ENUM_SERVICE_TYPE, EnumDependentServicesW, EnumServicesStatusExW, SC_ENUM_PROCESS_INFO, SC_HANDLE, SERVICE_DRIVER, SERVICE_STATE_ALL, SERVICE_STATUS_PROCESS, SERVICE_WIN32};
+
+use crate::config::{Dependency, ServiceConfig};
+use crate::dword::{ScManagerAccess, ServiceAccess, ServiceError, ServiceStatus, ServiceType};
+use crate::manager::ServiceManager;
+use crate::status::FullStatus;
+use crate::handle::OwnedScHandle;
+use crate::{multi_sz_to_vec, path, wide_to_string, RawServiceConfig, WindowsService, QUERY_BUFFER_ATTEMPTS};
+
+/// 枚举得到的服务信息,所有枚举函数都返回该类型
+///
+/// 各字段的来源:
+/// - EnumServicesStatusExW(SC_ENUM_PROCESS_INFO): 所有字段都会填充
+/// - EnumDependentServicesW等只返回ENUM_SERVICE_STATUSW的接口:
+///   status中的process_id与service_flags没有对应数据,始终为0
+///
+/// 启用serde特性后可以序列化
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ServiceInfo {
+    /// 服务名称
+    pub service_name: String,
+    /// 显示名称
+    pub display_name: String,
+    /// 扩展状态
+    pub status: FullStatus,
+}
+
+/// # 获取所有服务的状态表
+/// 通过一次EnumServicesStatusExW调用获取所有服务的状态,无需逐个打开服务查询
+/// ## 参数
+/// ### output:
+/// - Result<Vec<(服务名称, 显示名称, 服务状态)>,ServiceError>,按显示名称排序
+/// ## 例子
+/// ```
+/// use windows_service_controller::enumerate::status_table;
+/// for (name, display_name, status) in status_table().unwrap() {
+///     println!("{}\t{}\t{}", name, display_name, status);
+/// }
+/// ```
+pub fn status_table() -> Result<Vec<(String, String, ServiceStatus)>, ServiceError> {
+    let mut table: Vec<(String, String, ServiceStatus)> = enum_services(SERVICE_WIN32)?
+        .into_iter()
+        .map(|info| (info.service_name, info.display_name, info.status.current_state()))
+        .collect();
+    table.sort_by_cached_key(|(_, display_name, _)| display_name.to_lowercase());
+    Ok(table)
+}
+
+/// # 获取所有服务的状态,按服务名称索引
+/// 与status_table相同,通过一次枚举取得所有服务和驱动(包括正在运行和已停止的)的当前状态,
+/// 返回便于按名称反复查找的映射。键为SCM返回的服务名称,保持原有的大小写
+/// ## 参数
+/// ### output:
+/// - Result<HashMap<服务名称, 服务状态>,ServiceError>
+/// ## 例子
+/// ```
+/// use windows_service_controller::enumerate::all_states;
+/// let states = all_states().unwrap();
+/// println!("{:?}", states.get("WSearch"));
+/// ```
+pub fn all_states() -> Result<HashMap<String, ServiceStatus>, ServiceError> {
+    Ok(enum_services(SERVICE_WIN32 | SERVICE_DRIVER)?
+        .into_iter()
+        .map(|info| (info.service_name, info.status.current_state()))
+        .collect())
+}
+
+/// # 按名称查找服务
+/// 枚举所有服务,返回服务名称或显示名称包含指定子串(不区分大小写)的服务。
+/// SCM不支持按名称过滤,过滤在本地完成
+/// ## 参数
+/// ### input:
+/// - substring: 要查找的子串
+/// ### output:
+/// - Result<Vec<ServiceInfo>,ServiceError>
+/// ## 例子
+/// ```
+/// use windows_service_controller::enumerate::find_services;
+/// for info in find_services("search").unwrap() {
+///     println!("{}\t{}", info.service_name, info.display_name);
+/// }
+/// ```
+pub fn find_services(substring: &str) -> Result<Vec<ServiceInfo>, ServiceError> {
+    let substring = substring.to_lowercase();
+    Ok(enum_services(SERVICE_WIN32)?
+        .into_iter()
+        .filter(|info| {
+            info.service_name.to_lowercase().contains(&substring)
+                || info.display_name.to_lowercase().contains(&substring)
+        })
+        .collect())
+}
+
+/// # 按可执行文件路径查找服务
+/// 枚举所有服务和驱动,逐个查询配置,返回lpBinaryPathName指向指定可执行文件的服务名称。
+/// 比较时忽略引号和启动参数,'/'与'\'视为相同,没有权限查询配置的服务会被跳过。
+///
+/// 每个服务都需要打开并查询一次配置,服务较多时耗时可能达到数百毫秒,不适合频繁调用
+/// ## 参数
+/// ### input:
+/// - path: 可执行文件路径,可以带引号和参数
+/// - case_insensitive: 是否忽略大小写(Windows的路径一般不区分大小写)
+/// ### output:
+/// - Result<Vec<String>,ServiceError>
+/// ## 例子
+/// ```
+/// use windows_service_controller::enumerate::find_by_binary_path;
+/// for name in find_by_binary_path("C:\\WINDOWS\\system32\\svchost.exe", true).unwrap() {
+///     println!("{}", name);
+/// }
+/// ```
+pub fn find_by_binary_path(path: &str, case_insensitive: bool) -> Result<Vec<String>, ServiceError> {
+    filter_by_config(|config| path::same_executable(&wide_to_string(config.lpBinaryPathName), path, case_insensitive))
+}
+
+/// # 查找可执行文件已不存在的服务
+/// 枚举所有服务和驱动,逐个查询配置,返回可执行文件在磁盘上不存在的服务名称,适合清理残留的服务。
+/// 检查前去掉引号和启动参数,并按当前进程的环境展开%SystemRoot%等环境变量;
+/// 驱动的\SystemRoot\前缀和相对路径(相对于%SystemRoot%)也会被展开。
+/// 没有配置可执行文件或没有权限查询配置的服务会被跳过
+/// ## 参数
+/// ### output:
+/// - Result<Vec<String>,ServiceError>
+/// ## 例子
+/// ```
+/// use windows_service_controller::enumerate::find_orphaned;
+/// for name in find_orphaned().unwrap() {
+///     println!("{}", name);
+/// }
+/// ```
+pub fn find_orphaned() -> Result<Vec<String>, ServiceError> {
+    filter_by_config(|config| {
+        let binary_path = wide_to_string(config.lpBinaryPathName);
+        if binary_path.trim().is_empty() {
+            return false;
+        }
+        let is_driver = config.dwServiceType.0 & SERVICE_DRIVER.0 != 0;
+        let executable = path::resolve_executable(&binary_path, is_driver, |name| std::env::var(name).ok());
+        !std::path::Path::new(&executable).exists()
+    })
+}
+
+/// 枚举所有服务和驱动并逐个查询配置,返回满足条件的服务名称,无法查询配置的服务会被跳过
+fn filter_by_config<F>(mut predicate: F) -> Result<Vec<String>, ServiceError>
+where
+    F: FnMut(&RawServiceConfig) -> bool,
+{
+    // 句柄由guard持有,predicate panic时也会关闭
+    let sc_manager_handle = OwnedScHandle::new(WindowsService::open_sc_manager(ScManagerAccess::ENUMERATE)?);
+    let services = enum_services_status(sc_manager_handle.get(), SERVICE_WIN32 | SERVICE_DRIVER)?;
+    Ok(services
+        .into_iter()
+        .filter(|info| {
+            let service_handle = match WindowsService::open_service(
+                sc_manager_handle.get(),
+                &info.service_name,
+                ServiceAccess::SERVICE_QUERY_CONFIG,
+            ) {
+                Ok(handle) => OwnedScHandle::new(handle),
+                Err(_) => return false,
+            };
+            match WindowsService::get_config(service_handle.get()) {
+                Ok((config, _buffer)) => predicate(&config),
+                Err(_) => false,
+            }
+        })
+        .map(|info| info.service_name)
+        .collect())
+}
+
+/// 分批枚举时缓冲区的最小字节数,保证每批至少能容纳一个服务(服务名称与显示名称最长256个字符)
+const MIN_BATCH_SIZE: usize = std::mem::size_of::<ENUM_SERVICE_STATUS_PROCESSW>() + 2 * (256 + 1) * 2;
+
+/// # 分批枚举服务
+/// 使用固定大小的缓冲区和恢复句柄分多次调用EnumServicesStatusExW,每取得一批服务就交给回调处理,
+/// 内存占用只与batch_size有关,适合服务和驱动数量很多的系统。
+/// 需要一次性取得所有服务时使用status_table或find_services
+/// ## 参数
+/// ### input:
+/// - service_type: 要枚举的服务类型,如ServiceType::SERVICE_WIN32、ServiceType::SERVICE_DRIVER
+/// - batch_size: 缓冲区大小(字节),小于能容纳一个服务的大小时自动增大
+/// - on_batch: 处理每批服务的回调
+/// ### output:
+/// - Result<(),ServiceError>
+/// ## 例子
+/// ```
+/// use windows_service_controller::dword::ServiceType;
+/// use windows_service_controller::enumerate::for_each_batch;
+/// for_each_batch(ServiceType::SERVICE_DRIVER, 16 * 1024, |batch| {
+///     for info in batch {
+///         println!("{}\t{}", info.service_name, info.status.current_state());
+///     }
+/// })
+/// .unwrap();
+/// ```
+pub fn for_each_batch<F>(service_type: ServiceType, batch_size: usize, on_batch: F) -> Result<(), ServiceError>
+where
+    F: FnMut(Vec<ServiceInfo>),
+{
+    let sc_manager_handle = OwnedScHandle::new(WindowsService::open_sc_manager(ScManagerAccess::ENUMERATE)?);
+    enum_services_batched(
+        sc_manager_handle.get(),
+        service_type.into(),
+        batch_size.max(MIN_BATCH_SIZE),
+        on_batch,
+    )
+}
+
+/// # 批量查询服务状态
+/// 只打开一次SCM,以SERVICE_QUERY_STATUS权限逐个打开服务查询状态后立即关闭。
+/// 每个服务的结果相互独立,某个服务查询失败不会影响其他服务
+/// ## 参数
+/// ### input:
+/// - names: 服务名称列表
+/// ### output:
+/// - Vec<(服务名称, Result<ServiceStatus,ServiceError>)>,顺序与names一致
+/// ## 例子
+/// ```
+/// use windows_service_controller::enumerate::query_many_status;
+/// for (name, status) in query_many_status(&["WSearch", "NotExist"]) {
+///     match status {
+///         Ok(status) => println!("{}: {}", name, status),
+///         Err(e) => println!("{}: {}", name, e),
+///     }
+/// }
+/// ```
+pub fn query_many_status(names: &[&str]) -> Vec<(String, Result<ServiceStatus, ServiceError>)> {
+    let sc_manager_handle = match WindowsService::open_sc_manager(ScManagerAccess::SC_MANAGER_CONNECT) {
+        Ok(handle) => OwnedScHandle::new(handle),
+        Err(e) => return names.iter().map(|name| (name.to_string(), Err(e))).collect(),
+    };
+    names
+        .iter()
+        .map(|name| {
+            let status = WindowsService::open_service(sc_manager_handle.get(), name, ServiceAccess::SERVICE_QUERY_STATUS)
+                .and_then(|service_handle| WindowsService::get_status_ex(OwnedScHandle::new(service_handle).get()))
+                .map(|status| status.current_state());
+            (name.to_string(), status)
+        })
+        .collect()
+}
+
+/// for_each_service的结果:每个选中的服务的名称及其操作结果
+pub type ActionResults = Vec<(String, Result<(), ServiceError>)>;
+
+/// # 对满足条件的服务逐个执行操作
+/// 例如停止显示名称以某个前缀开头的所有服务。只打开一次SCM,枚举所有WIN32服务,对filter返回true的服务以access权限打开,执行action后立即关闭。
+/// 每个服务的结果相互独立,打开服务或action失败不会影响其他服务
+/// ## 参数
+/// ### input:
+/// - filter: 根据枚举得到的信息选择服务
+/// - access: 打开服务时请求的访问权限,需要包含action所需的权限。
+///   打开服务时会查询配置,因此总是额外请求SERVICE_QUERY_CONFIG
+/// - action: 对每个选中的服务执行的操作
+/// ### output:
+/// - Result<ActionResults,ServiceError>: 打开SCM或枚举失败时返回Err,顺序与枚举结果一致
+/// ## 例子
+/// ```
+/// use windows_service_controller::dword::ServiceAccess;
+/// use windows_service_controller::enumerate::for_each_service;
+/// let results = for_each_service(
+///     |info| info.display_name.starts_with("MyApp"),
+///     ServiceAccess::SERVICE_ALL_ACCESS,
+///     |service| service.stop_service(),
+/// )
+/// .unwrap();
+/// for (name, result) in results {
+///     println!("{}: {:?}", name, result);
+/// }
+/// ```
+pub fn for_each_service<F, A>(
+    mut filter: F,
+    access: ServiceAccess,
+    mut action: A,
+) -> Result<ActionResults, ServiceError>
+where
+    F: FnMut(&ServiceInfo) -> bool,
+    A: FnMut(&WindowsService) -> Result<(), ServiceError>,
+{
+    let manager = ServiceManager::open(Some(ScManagerAccess::ENUMERATE))?;
+    Ok(enum_services_status(manager.handle(), SERVICE_WIN32)?
+        .into_iter()
+        .filter(|info| filter(info))
+        .map(|info| {
+            let access = access | ServiceAccess::SERVICE_QUERY_CONFIG;
+            let result = WindowsService::open_with_scm(&manager, &info.service_name, Some(access))
+                .and_then(|service| action(&service));
+            (info.service_name, result)
+        })
+        .collect())
+}
+
+/// 并行查询配置时的最大线程数
+const MAX_QUERY_THREADS: usize = 8;
+
+/// # 批量查询服务配置
+/// 将服务分给多个线程(不超过CPU核数和8个)并行查询,每个线程打开自己的SCM句柄,
+/// 以SERVICE_QUERY_CONFIG权限逐个打开服务查询配置后立即关闭。
+/// 每个服务的结果相互独立,某个服务查询失败不会影响其他服务
+/// ## 参数
+/// ### input:
+/// - names: 服务名称列表
+/// ### output:
+/// - Vec<(服务名称, Result<ServiceConfig,ServiceError>)>,顺序与names一致
+/// ## 例子
+/// ```
+/// use windows_service_controller::enumerate::query_configs;
+/// for (name, config) in query_configs(&["WSearch", "NotExist"]) {
+///     match config {
+///         Ok(config) => println!("{}: {}", name, config.binary_path),
+///         Err(e) => println!("{}: {}", name, e),
+///     }
+/// }
+/// ```
+pub fn query_configs(names: &[&str]) -> Vec<(String, Result<ServiceConfig, ServiceError>)> {
+    if names.is_empty() {
+        return Vec::new();
+    }
+    let threads = std::thread::available_parallelism()
+        .map_or(1, |n| n.get())
+        .min(MAX_QUERY_THREADS)
+        .min(names.len());
+    let chunk_size = names.len().div_ceil(threads);
+    std::thread::scope(|scope| {
+        let workers: Vec<_> = names
+            .chunks(chunk_size)
+            .map(|chunk| scope.spawn(move || query_configs_serial(chunk)))
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().expect("查询服务配置的线程异常退出"))
+            .collect()
+    })
+}
+
+/// # 异步枚举服务
+/// 与for_each_batch相同的EnumServicesStatusExW调用会阻塞当前线程,这里放到tokio的阻塞线程池(spawn_blocking)中执行,
+/// 不会占用异步运行时的工作线程。需要启用tokio特性,且必须在tokio运行时中调用
+/// ## 参数
+/// ### input:
+/// - service_type: 需要枚举的服务类型,例如SERVICE_WIN32、SERVICE_DRIVER
+/// ### output:
+/// - Result<Vec<ServiceInfo>,ServiceError>
+/// ## 例子
+/// ```
+/// use windows_service_controller::dword::ServiceType;
+/// use windows_service_controller::enumerate::{enumerate_async, query_configs_async};
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// let services = enumerate_async(ServiceType::SERVICE_WIN32).await.unwrap();
+/// let names = services.into_iter().map(|info| info.service_name).collect();
+/// for (name, config) in query_configs_async(names).await {
+///     if let Ok(config) = config {
+///         println!("{}: {}", name, config.binary_path);
+///     }
+/// }
+/// # });
+/// ```
+#[cfg(feature = "tokio")]
+pub async fn enumerate_async(service_type: ServiceType) -> Result<Vec<ServiceInfo>, ServiceError> {
+    tokio::task::spawn_blocking(move || enum_services(service_type.into()))
+        .await
+        .expect("枚举服务的任务异常退出")
+}
+
+/// # 异步批量查询服务配置
+/// 与query_configs相同,将服务分成若干份(不超过CPU核数和8份),每份作为一个spawn_blocking任务,
+/// 在阻塞线程池中用自己的SCM句柄依次查询,所有任务完成后按原顺序合并结果。
+/// 需要启用tokio特性,且必须在tokio运行时中调用
+/// ## 参数
+/// ### input:
+/// - names: 服务名称列表
+/// ### output:
+/// - Vec<(服务名称, Result<ServiceConfig,ServiceError>)>,顺序与names一致
+#[cfg(feature = "tokio")]
+pub async fn query_configs_async(names: Vec<String>) -> Vec<(String, Result<ServiceConfig, ServiceError>)> {
+    if names.is_empty() {
+        return Vec::new();
+    }
+    let tasks = std::thread::available_parallelism()
+        .map_or(1, |n| n.get())
+        .min(MAX_QUERY_THREADS)
+        .min(names.len());
+    let chunk_size = names.len().div_ceil(tasks);
+    let workers: Vec<_> = names
+        .chunks(chunk_size)
+        .map(|chunk| {
+            let chunk = chunk.to_vec();
+            tokio::task::spawn_blocking(move || {
+                let names: Vec<&str> = chunk.iter().map(String::as_str).collect();
+                query_configs_serial(&names)
+            })
+        })
+        .collect();
+    let mut result = Vec::with_capacity(names.len());
+    for worker in workers {
+        result.extend(worker.await.expect("查询服务配置的任务异常退出"));
+    }
+    result
+}
+
+/// 在当前线程中依次查询服务配置,SCM句柄只在当前线程中使用
+fn query_configs_serial(names: &[&str]) -> Vec<(String, Result<ServiceConfig, ServiceError>)> {
+    let sc_manager_handle = match WindowsService::open_sc_manager(ScManagerAccess::SC_MANAGER_CONNECT) {
+        Ok(handle) => OwnedScHandle::new(handle),
+        Err(e) => return names.iter().map(|name| (name.to_string(), Err(e))).collect(),
+    };
+    names
+        .iter()
+        .map(|name| {
+            let config = WindowsService::open_service(sc_manager_handle.get(), name, ServiceAccess::SERVICE_QUERY_CONFIG)
+                .and_then(|service_handle| {
+                    let service_handle = OwnedScHandle::new(service_handle);
+                    // 在缓冲区释放前复制出字符串
+                    WindowsService::get_config(service_handle.get())
+                        .map(|(config, _buffer)| ServiceConfig::from_raw(&config))
+                });
+            (name.to_string(), config)
+        })
+        .collect()
+}
+
+/// 打开SCM并枚举指定类型的所有服务
+fn enum_services(service_type: ENUM_SERVICE_TYPE) -> Result<Vec<ServiceInfo>, ServiceError> {
+    let sc_manager_handle = OwnedScHandle::new(WindowsService::open_sc_manager(ScManagerAccess::ENUMERATE)?);
+    enum_services_status(sc_manager_handle.get(), service_type)
+}
+
+/// 枚举指定类型的所有服务
+pub(crate) fn enum_services_status(
+    sc_manager_handle: SC_HANDLE,
+    service_type: ENUM_SERVICE_TYPE,
+) -> Result<Vec<ServiceInfo>, ServiceError> {
+    let mut result = Vec::new();
+    enum_services_batched(sc_manager_handle, service_type, 0, |batch| result.extend(batch))?;
+    Ok(result)
+}
+
+/// 以恢复句柄分批枚举指定类型的服务
+/// - buffer_size: 缓冲区的初始大小(字节),放不下任何一个服务时才会按所需大小扩大,之后一直复用
+fn enum_services_batched<F>(
+    sc_manager_handle: SC_HANDLE,
+    service_type: ENUM_SERVICE_TYPE,
+    buffer_size: usize,
+    mut on_batch: F,
+) -> Result<(), ServiceError>
+where
+    F: FnMut(Vec<ServiceInfo>),
+{
+    // 使用u64作为存储保证结构体的对齐
+    let mut buffer: Vec<u64> = vec![0; buffer_size.div_ceil(8)];
+    let mut resume_handle: u32 = 0;
+    loop {
+        let mut bytes_needed: u32 = 0;
+        let mut services_returned: u32 = 0;
+        let more_data = match unsafe {
+            EnumServicesStatusExW(
+                sc_manager_handle,
+                SC_ENUM_PROCESS_INFO,
+                service_type,
+                SERVICE_STATE_ALL,
+                Some(std::slice::from_raw_parts_mut(
+                    buffer.as_mut_ptr() as *mut u8,
+                    buffer.len() * 8,
+                )),
+                &mut bytes_needed,
+                &mut services_returned,
+                Some(&mut resume_handle),
+                PCWSTR::null(),
+            )
+        } {
+            Ok(_) => false,
+            Err(_) => {
+                let error = unsafe { GetLastError() };
+                if error != ERROR_MORE_DATA {
+                    return Err(error.into());
+                }
+                true
+            }
+        };
+        if services_returned > 0 {
+            // 字符串指向缓冲区内部,需要在下一次调用覆盖缓冲区之前复制出来
+            on_batch(parse_enum_status_process(&buffer, services_returned as usize)?);
+        }
+        if !more_data {
+            return Ok(());
+        }
+        if services_returned == 0 {
+            let needed = (bytes_needed as usize).div_ceil(8);
+            if needed <= buffer.len() {
+                return Err(ERROR_MORE_DATA.into());
+            }
+            buffer.resize(needed, 0);
+        }
+    }
+}
+
+/// 解析EnumServicesStatusExW(SC_ENUM_PROCESS_INFO)写入的缓冲区
+/// 缓冲区开头是count个ENUM_SERVICE_STATUS_PROCESSW,其中的字符串指针指向数组之后的区域。
+/// 解析前检查数组没有超出缓冲区,每个非空字符串指针都指向数组之后、缓冲区之内且按u16对齐,
+/// 并且在缓冲区结束前有NUL结尾,不满足时返回ERROR_INVALID_DATA;空指针解析为空字符串。
+/// 使用u64作为存储保证结构体的对齐
+fn parse_enum_status_process(buffer: &[u64], count: usize) -> Result<Vec<ServiceInfo>, ServiceError> {
+    parse_entries(buffer, count, |entry: &ENUM_SERVICE_STATUS_PROCESSW| {
+        (entry.lpServiceName, entry.lpDisplayName, entry.ServiceStatusProcess.into())
+    })
+}
+
+/// 解析EnumDependentServicesW写入的缓冲区,布局与检查同parse_enum_status_process,
+/// 结构体为ENUM_SERVICE_STATUSW,没有进程ID和服务标志
+fn parse_enum_status(buffer: &[u64], count: usize) -> Result<Vec<ServiceInfo>, ServiceError> {
+    parse_entries(buffer, count, |entry: &ENUM_SERVICE_STATUSW| {
+        let status = entry.ServiceStatus;
+        let status = SERVICE_STATUS_PROCESS {
+            dwServiceType: status.dwServiceType,
+            dwCurrentState: status.dwCurrentState,
+            dwControlsAccepted: status.dwControlsAccepted,
+            dwWin32ExitCode: status.dwWin32ExitCode,
+            dwServiceSpecificExitCode: status.dwServiceSpecificExitCode,
+            dwCheckPoint: status.dwCheckPoint,
+            dwWaitHint: status.dwWaitHint,
+            ..Default::default()
+        };
+        (entry.lpServiceName, entry.lpDisplayName, status.into())
+    })
+}
+
+/// 解析开头是count个T、字符串位于数组之后的枚举缓冲区,entry取出每个结构体的服务名称、显示名称和状态
+fn parse_entries<T, F>(buffer: &[u64], count: usize, entry: F) -> Result<Vec<ServiceInfo>, ServiceError>
+where
+    F: Fn(&T) -> (PWSTR, PWSTR, FullStatus),
+{
+    let entry_size = std::mem::size_of::<T>();
+    let buffer_len = buffer.len() * 8;
+    let entries_len = count.checked_mul(entry_size).filter(|len| *len <= buffer_len).ok_or(ERROR_INVALID_DATA)?;
+    let base = buffer.as_ptr() as usize;
+    let wide = |s: PWSTR| -> Result<String, ServiceError> {
+        if s.is_null() {
+            return Ok(String::new());
+        }
+        let address = s.0 as usize;
+        if address < base + entries_len || address >= base + buffer_len || (address - base) & 1 != 0 {
+            return Err(ERROR_INVALID_DATA.into());
+        }
+        // 指针已确认位于缓冲区内,只读取到缓冲区结尾
+        let chars = unsafe { std::slice::from_raw_parts(s.0 as *const u16, (base + buffer_len - address) / 2) };
+        match chars.iter().position(|c| *c == 0) {
+            Some(len) => Ok(String::from_utf16_lossy(&chars[..len])),
+            None => Err(ERROR_INVALID_DATA.into()),
+        }
+    };
+    let entries = unsafe { std::slice::from_raw_parts(buffer.as_ptr() as *const T, count) };
+    entries
+        .iter()
+        .map(|item| {
+            let (service_name, display_name, status) = entry(item);
+            Ok(ServiceInfo {
+                service_name: wide(service_name)?,
+                display_name: wide(display_name)?,
+                status,
+            })
+        })
+        .collect()
+}
+
+/// 枚举依赖于指定服务的服务(EnumDependentServicesW),包括间接依赖的服务,按启动顺序的逆序排列。
+/// 只包含在依赖项中直接写出服务名称的服务,不包括依赖服务所在服务组('+'开头)的服务。
+/// service_handle需要SERVICE_ENUMERATE_DEPENDENTS权限
+pub(crate) fn enum_dependent_services(service_handle: SC_HANDLE) -> Result<Vec<ServiceInfo>, ServiceError> {
+    // 使用u64作为存储保证结构体的对齐
+    let mut buffer: Vec<u64> = Vec::new();
+    for _ in 0..QUERY_BUFFER_ATTEMPTS {
+        let mut bytes_needed: u32 = 0;
+        let mut services_returned: u32 = 0;
+        let result = unsafe {
+            EnumDependentServicesW(
+                service_handle,
+                SERVICE_STATE_ALL,
+                (!buffer.is_empty()).then_some(buffer.as_mut_ptr() as *mut ENUM_SERVICE_STATUSW),
+                (buffer.len() * 8) as u32,
+                &mut bytes_needed,
+                &mut services_returned,
+            )
+        };
+        if result.is_ok() {
+            return parse_enum_status(&buffer, services_returned as usize);
+        }
+        let error = unsafe { GetLastError() };
+        let needed = (bytes_needed as usize).div_ceil(8);
+        if error != ERROR_MORE_DATA || needed <= buffer.len() {
+            return Err(error.into());
+        }
+        buffer.resize(needed, 0);
+    }
+    Err(ServiceError::BUFFER_STILL_TOO_SMALL)
+}
+
+/// 依赖项中是否包含指定的服务组('+'开头,不区分大小写)
+fn depends_on_group(dependencies: &[String], group: &str) -> bool {
+    let group = group.to_lowercase();
+    dependencies.iter().any(|dependency| match Dependency::parse(dependency) {
+        Dependency::Group(name) => name.to_lowercase() == group,
+        Dependency::Service(_) => false,
+    })
+}
+
+/// 查找依赖于服务组group的服务,以及依赖于这些服务的服务。
+/// 需要逐个查询所有服务和驱动的配置,无法以SERVICE_QUERY_CONFIG|SERVICE_ENUMERATE_DEPENDENTS打开的服务会被跳过
+pub(crate) fn group_dependents(sc_manager_handle: SC_HANDLE, group: &str) -> Result<Vec<ServiceInfo>, ServiceError> {
+    let mut result = Vec::new();
+    for info in enum_services_status(sc_manager_handle, SERVICE_WIN32 | SERVICE_DRIVER)? {
+        let service_handle = match WindowsService::open_service(
+            sc_manager_handle,
+            &info.service_name,
+            ServiceAccess::SERVICE_QUERY_CONFIG | ServiceAccess::SERVICE_ENUMERATE_DEPENDENTS,
+        ) {
+            Ok(handle) => OwnedScHandle::new(handle),
+            Err(_) => continue,
+        };
+        let dependencies = WindowsService::get_config(service_handle.get())
+            .map(|(config, _buffer)| multi_sz_to_vec(config.lpDependencies))
+            .unwrap_or_default();
+        if depends_on_group(&dependencies, group) {
+            let indirect = enum_dependent_services(service_handle.get()).unwrap_or_default();
+            result.push(info);
+            result.extend(indirect);
+        }
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod test {
+    use windows::core::PWSTR;
+    use windows::Win32::System::Services::{ENUM_SERVICE_STATUS_PROCESSW, SERVICE_STATUS_PROCESS};
+
+    use crate::dword::{ServiceAccess, ServiceError, ServiceType};
+    use crate::enumerate::{
+        all_states, depends_on_group, find_by_binary_path, find_orphaned, find_services, for_each_batch,
+        for_each_service, parse_enum_status_process, query_configs, query_many_status, status_table,
+    };
+    #[cfg(feature = "tokio")]
+    use crate::enumerate::{enumerate_async, query_configs_async};
+
+    /// 按EnumServicesStatusExW的布局构造缓冲区:结构体数组在前,字符串依次放在数组之后
+    fn enum_buffer(services: &[(&str, &str, u32)]) -> Vec<u64> {
+        let entries_len = services.len() * std::mem::size_of::<ENUM_SERVICE_STATUS_PROCESSW>();
+        let strings: Vec<Vec<u16>> = services
+            .iter()
+            .flat_map(|(name, display_name, _)| [*name, *display_name])
+            .map(|s| s.encode_utf16().chain(Some(0)).collect())
+            .collect();
+        let strings_len: usize = strings.iter().map(|s| s.len() * 2).sum();
+        let mut buffer: Vec<u64> = vec![0; (entries_len + strings_len).div_ceil(8)];
+        let base = buffer.as_mut_ptr() as *mut u8;
+        let mut offset = entries_len;
+        let mut pointers = Vec::new();
+        for s in &strings {
+            let pointer = unsafe { base.add(offset) } as *mut u16;
+            unsafe { std::ptr::copy_nonoverlapping(s.as_ptr(), pointer, s.len()) };
+            pointers.push(PWSTR(pointer));
+            offset += s.len() * 2;
+        }
+        for (i, (_, _, process_id)) in services.iter().enumerate() {
+            let entry = ENUM_SERVICE_STATUS_PROCESSW {
+                lpServiceName: pointers[i * 2],
+                lpDisplayName: pointers[i * 2 + 1],
+                ServiceStatusProcess: SERVICE_STATUS_PROCESS { dwProcessId: *process_id, ..Default::default() },
+            };
+            unsafe { (base as *mut ENUM_SERVICE_STATUS_PROCESSW).add(i).write(entry) };
+        }
+        buffer
+    }
+
+    #[test]
+    fn parse_enum_buffer() {
+        let buffer = enum_buffer(&[("WSearch", "Windows Search", 1234), ("Lers", "", 0)]);
+        let services = parse_enum_status_process(&buffer, 2).unwrap();
+        assert_eq!(services.len(), 2);
+        assert_eq!(services[0].service_name, "WSearch");
+        assert_eq!(services[0].display_name, "Windows Search");
+        assert_eq!(services[0].status.process_id(), 1234);
+        assert_eq!(services[1].service_name, "Lers");
+        assert_eq!(services[1].display_name, "");
+        assert!(parse_enum_status_process(&buffer, 0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn parse_enum_buffer_invalid() {
+        let invalid = Err(ServiceError::from(windows::Win32::Foundation::ERROR_INVALID_DATA));
+        // 数组超出缓冲区
+        let buffer = enum_buffer(&[("A", "B", 0)]);
+        assert_eq!(parse_enum_status_process(&buffer, 100).map(|s| s.len()), invalid);
+
+        // 字符串没有NUL结尾
+        let mut buffer = enum_buffer(&[("Lers", "Lers", 0)]);
+        let entries_len = std::mem::size_of::<ENUM_SERVICE_STATUS_PROCESSW>();
+        let bytes = unsafe { std::slice::from_raw_parts_mut(buffer.as_mut_ptr() as *mut u8, buffer.len() * 8) };
+        bytes[entries_len..].fill(b'a');
+        assert_eq!(parse_enum_status_process(&buffer, 1).map(|s| s.len()), invalid);
+
+        // 指针指向结构体数组内部或缓冲区之外
+        let mut buffer = enum_buffer(&[("Lers", "Lers", 0)]);
+        let entry = buffer.as_mut_ptr() as *mut ENUM_SERVICE_STATUS_PROCESSW;
+        unsafe { (*entry).lpDisplayName = PWSTR(entry as *mut u16) };
+        assert_eq!(parse_enum_status_process(&buffer, 1).map(|s| s.len()), invalid);
+        unsafe { (*entry).lpDisplayName = PWSTR((buffer.as_ptr() as *mut u16).add(buffer.len() * 4)) };
+        assert_eq!(parse_enum_status_process(&buffer, 1).map(|s| s.len()), invalid);
+
+        // 空指针解析为空字符串
+        unsafe { (*entry).lpDisplayName = PWSTR::null() };
+        assert_eq!(parse_enum_status_process(&buffer, 1).unwrap()[0].display_name, "");
+    }
+
+    #[test]
+    fn list_status_table() {
+        match status_table() {
+            Ok(table) => {
+                for (name, display_name, status) in table {
+                    println!("{}\t{}\t{}", name, display_name, status)
+                }
+            }
+            Err(e) => {
+                println!("{}", e)
+            }
+        }
+    }
+
+    #[test]
+    fn list_all_states() {
+        match all_states() {
+            Ok(states) => {
+                println!("{} services", states.len());
+                println!("{:?}", states.get("WSearch"));
+            }
+            Err(e) => {
+                println!("{}", e)
+            }
+        }
+    }
+
+    #[test]
+    fn for_each_matching_service() {
+        let result = for_each_service(
+            |info| info.service_name.eq_ignore_ascii_case("WSearch"),
+            ServiceAccess::SERVICE_QUERY_STATUS,
+            |service| service.query_service_status().map(|status| println!("{}", status)),
+        );
+        match result {
+            Ok(results) => {
+                assert!(results.len() <= 1);
+                for (name, result) in results {
+                    println!("{}: {:?}", name, result);
+                    // 只请求了SERVICE_QUERY_STATUS,SERVICE_QUERY_CONFIG由for_each_service补上
+                    assert_eq!(result, Ok(()));
+                }
+            }
+            Err(e) => {
+                println!("{}", e)
+            }
+        }
+    }
+
+    #[test]
+    fn find_services_by_substring() {
+        match find_services("SEARCH") {
+            Ok(services) => {
+                for info in services {
+                    println!("{}\t{}", info.service_name, info.display_name)
+                }
+            }
+            Err(e) => {
+                println!("{}", e)
+            }
+        }
+    }
+
+    #[test]
+    fn find_services_by_binary_path() {
+        match find_by_binary_path("\"C:\\WINDOWS\\system32\\svchost.exe\" -k netsvcs", true) {
+            Ok(services) => {
+                for name in services {
+                    println!("{}", name)
+                }
+            }
+            Err(e) => {
+                println!("{}", e)
+            }
+        }
+    }
+
+    #[test]
+    fn find_orphaned_services() {
+        match find_orphaned() {
+            Ok(services) => {
+                for name in services {
+                    println!("{}", name)
+                }
+            }
+            Err(e) => {
+                println!("{}", e)
+            }
+        }
+    }
+
+    #[test]
+    fn enumerate_in_batches() {
+        let mut batches = 0;
+        let mut services = 0;
+        let result = for_each_batch(ServiceType::SERVICE_WIN32, 4096, |batch| {
+            assert!(!batch.is_empty());
+            batches += 1;
+            services += batch.len();
+        });
+        match result {
+            Ok(_) => {
+                println!("{} services in {} batches", services, batches)
+            }
+            Err(e) => {
+                println!("{}", e)
+            }
+        }
+    }
+
+    #[test]
+    fn query_many_configs() {
+        let result = query_configs(&["WSearch", "Lers/NotExist", "Spooler"]);
+        assert_eq!(result.len(), 3);
+        assert_eq!(result[1].0, "Lers/NotExist");
+        assert!(result[1].1.is_err());
+        for (name, config) in result {
+            match config {
+                Ok(config) => println!("{}: {}", name, config.binary_path),
+                Err(e) => println!("{}: {}", name, e),
+            }
+        }
+    }
+
+    #[test]
+    fn query_many() {
+        let result = query_many_status(&["WSearch", "Lers/NotExist"]);
+        assert_eq!(result.len(), 2);
+        assert!(result[1].1.is_err());
+        for (name, status) in result {
+            match status {
+                Ok(status) => println!("{}: {}", name, status),
+                Err(e) => println!("{}: {}", name, e),
+            }
+        }
+    }
+
+    #[test]
+    fn group_dependency() {
+        let dependencies = vec!["RpcSs".to_string(), "+NetworkProvider".to_string()];
+        assert!(depends_on_group(&dependencies, "NetworkProvider"));
+        assert!(depends_on_group(&dependencies, "networkprovider"));
+        assert!(!depends_on_group(&dependencies, "RpcSs"));
+        assert!(!depends_on_group(&[], "NetworkProvider"));
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn enumerate_without_blocking() {
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        runtime.block_on(async {
+            match enumerate_async(ServiceType::SERVICE_WIN32).await {
+                Ok(services) => {
+                    let names: Vec<String> = services.into_iter().take(16).map(|info| info.service_name).collect();
+                    let configs = query_configs_async(names.clone()).await;
+                    assert_eq!(configs.iter().map(|(name, _)| name).collect::<Vec<_>>(), names.iter().collect::<Vec<_>>());
+                    for (name, config) in configs {
+                        match config {
+                            Ok(config) => println!("{}: {}", name, config.binary_path),
+                            Err(e) => println!("{}: {}", name, e),
+                        }
+                    }
+                }
+                Err(e) => println!("{}", e),
+            }
+            assert!(query_configs_async(Vec::new()).await.is_empty());
+        });
+    }
+}
@@ -0,0 +1,348 @@
+//! 从系统事件日志读取服务的失败记录(需要启用eventlog特性)
+//!
+//! 服务意外终止或以错误退出时,SCM会在System日志中以"Service Control Manager"为来源写入事件:
+//! - 7031: 服务意外终止,并记录已经终止的次数和将要执行的恢复操作
+//! - 7034: 服务意外终止,没有配置恢复操作
+//! - 7023: 服务以Win32错误代码终止
+//! - 7024: 服务以服务特定错误代码终止
+//!
+//! 事件中记录的是服务的显示名称,同时在二进制数据中记录服务名称(UTF-16),两者之一相同即视为该服务的事件
+
+use std::time::{Duration, SystemTime};
+
+use windows::core::PCWSTR;
+use windows::Win32::Foundation::{ERROR_NO_MORE_ITEMS, GetLastError};
+use windows::Win32::System::EventLog::{
+    EvtClose, EvtNext, EvtQuery, EvtQueryChannelPath, EvtQueryReverseDirection, EvtRender, EvtRenderEventXml, EVT_HANDLE,
+};
+
+use crate::dword::ServiceError;
+use crate::failure::FailureAction;
+use crate::query_with_buffer;
+
+/// 服务意外终止,并执行恢复操作
+const EVENT_CRASHED_WITH_ACTION: u32 = 7031;
+/// 服务意外终止
+const EVENT_CRASHED: u32 = 7034;
+/// 服务以Win32错误代码终止
+const EVENT_TERMINATED_WITH_ERROR: u32 = 7023;
+/// 服务以服务特定错误代码终止
+const EVENT_TERMINATED_WITH_SERVICE_ERROR: u32 = 7024;
+/// 只查询SCM写入的服务失败事件
+const FAILURE_QUERY: &str = "*[System[Provider[@Name='Service Control Manager'] and \
+    (EventID=7023 or EventID=7024 or EventID=7031 or EventID=7034)]]";
+/// 每次EvtNext取出的事件数
+const EVENT_BATCH: usize = 16;
+/// EvtNext的超时,INFINITE表示等待结果集就绪
+const INFINITE: u32 = 0xFFFF_FFFF;
+
+/// 一次服务失败的记录
+/// 启用serde特性后可以序列化
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ServiceFailure {
+    /// 事件的记录时间
+    pub time: SystemTime,
+    /// 事件ID(7023、7024、7031或7034)
+    pub event_id: u32,
+    /// 退出代码,只有7023(Win32错误代码)和7024(服务特定错误代码)事件包含
+    pub exit_code: Option<u32>,
+    /// 服务到目前为止意外终止的次数,只有7031和7034事件包含
+    pub failure_count: Option<u32>,
+    /// SCM执行的恢复操作及其延迟,只有7031事件包含
+    pub recovery_action: Option<FailureAction>,
+}
+
+/// 按时间从新到旧读取服务最近的失败记录,最多count条
+/// - service_name: 服务名称,与事件的二进制数据比较(不区分大小写)
+/// - display_name: 显示名称,与事件的param1比较
+pub(crate) fn recent_failures(service_name: &str, display_name: &str, count: usize) -> Result<Vec<ServiceFailure>, ServiceError> {
+    let mut result = Vec::new();
+    if count == 0 {
+        return Ok(result);
+    }
+    let channel: Vec<u16> = "System".encode_utf16().chain(Some(0)).collect();
+    let query: Vec<u16> = FAILURE_QUERY.encode_utf16().chain(Some(0)).collect();
+    let results = unsafe {
+        EvtQuery(
+            EVT_HANDLE::default(),
+            PCWSTR(channel.as_ptr()),
+            PCWSTR(query.as_ptr()),
+            EvtQueryChannelPath.0 | EvtQueryReverseDirection.0,
+        )
+    }
+    .map_err(|_| ServiceError::from_last_error())?;
+    let mut events = [0isize; EVENT_BATCH];
+    let outcome = loop {
+        if result.len() >= count {
+            break Ok(());
+        }
+        let mut returned: u32 = 0;
+        if unsafe { EvtNext(results, &mut events, INFINITE, 0, &mut returned) }.is_err() {
+            let error = unsafe { GetLastError() };
+            break if error == ERROR_NO_MORE_ITEMS { Ok(()) } else { Err(error.into()) };
+        }
+        // 取出的事件句柄都需要关闭,已经取够count条后剩余的事件不再渲染
+        for &event in &events[..returned as usize] {
+            let event = EVT_HANDLE(event);
+            if result.len() < count {
+                if let Some(failure) = render_xml(event)
+                    .ok()
+                    .and_then(|xml| parse_failure_event(&xml, service_name, display_name))
+                {
+                    result.push(failure);
+                }
+            }
+            let _ = unsafe { EvtClose(event) };
+        }
+    };
+    let _ = unsafe { EvtClose(results) };
+    outcome.map(|_| result)
+}
+
+/// 将事件渲染为XML
+fn render_xml(event: EVT_HANDLE) -> Result<String, ServiceError> {
+    let buffer = query_with_buffer(|buffer, bytes_needed| {
+        let mut property_count: u32 = 0;
+        unsafe {
+            EvtRender(
+                EVT_HANDLE::default(),
+                event,
+                EvtRenderEventXml.0,
+                buffer.len() as u32,
+                Some(buffer.as_mut_ptr() as *mut _),
+                bytes_needed,
+                &mut property_count,
+            )
+        }
+        .map_err(|_| unsafe { GetLastError() })
+    })?;
+    // 缓冲区中是以NUL结尾的UTF-16字符串
+    let chars: Vec<u16> = buffer
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .take_while(|c| *c != 0)
+        .collect();
+    Ok(String::from_utf16_lossy(&chars))
+}
+
+/// 解析EvtRender(EvtRenderEventXml)得到的事件,不是该服务的失败事件时返回None
+fn parse_failure_event(xml: &str, service_name: &str, display_name: &str) -> Option<ServiceFailure> {
+    let event_id: u32 = element_text(xml, "EventID")?.trim().parse().ok()?;
+    if ![EVENT_CRASHED_WITH_ACTION, EVENT_CRASHED, EVENT_TERMINATED_WITH_ERROR, EVENT_TERMINATED_WITH_SERVICE_ERROR]
+        .contains(&event_id)
+    {
+        return None;
+    }
+    let by_name = element_text(xml, "Binary")
+        .and_then(decode_binary_name)
+        .is_some_and(|name| name.eq_ignore_ascii_case(service_name));
+    let by_display_name = data(xml, "param1").is_some_and(|name| name == display_name);
+    if !by_name && !by_display_name {
+        return None;
+    }
+    let time = attribute(xml, "TimeCreated", "SystemTime").and_then(|time| parse_system_time(&time))?;
+    let number = |name: &str| data(xml, name).and_then(|value| value.trim().parse::<u32>().ok());
+    let mut failure = ServiceFailure { time, event_id, exit_code: None, failure_count: None, recovery_action: None };
+    match event_id {
+        EVENT_TERMINATED_WITH_ERROR | EVENT_TERMINATED_WITH_SERVICE_ERROR => {
+            failure.exit_code = data(xml, "param2").and_then(|code| parse_exit_code(&code));
+        }
+        EVENT_CRASHED_WITH_ACTION => {
+            failure.failure_count = number("param2");
+            let delay = Duration::from_millis(u64::from(number("param3").unwrap_or(0)));
+            // param4为SC_ACTION_TYPE,param5是本地化的操作说明(消息中的%5)
+            failure.recovery_action = number("param4").map(|action| match action {
+                1 => FailureAction::Restart { delay },
+                2 => FailureAction::Reboot { delay },
+                3 => FailureAction::RunCommand { delay },
+                _ => FailureAction::None { delay },
+            });
+        }
+        _ => failure.failure_count = number("param2"),
+    }
+    Some(failure)
+}
+
+/// 退出代码以十进制记录,可能带有"%%"前缀(引用系统消息),服务特定错误代码可能是负数
+fn parse_exit_code(text: &str) -> Option<u32> {
+    let text = text.trim();
+    let text = text.strip_prefix("%%").unwrap_or(text);
+    text.parse::<u32>().ok().or_else(|| text.parse::<i32>().ok().map(|code| code as u32))
+}
+
+/// 第一个名为name的元素的文本,元素可以带属性
+fn element_text(xml: &str, name: &str) -> Option<String> {
+    let open = format!("<{}", name);
+    let mut rest = xml;
+    while let Some(start) = rest.find(&open) {
+        rest = &rest[start + open.len()..];
+        // 排除名称以name开头的其他元素,例如查找Event时的EventID
+        if rest.starts_with(['>', ' ']) {
+            let content = &rest[rest.find('>')? + 1..];
+            let end = content.find(&format!("</{}>", name))?;
+            return Some(unescape(&content[..end]));
+        }
+    }
+    None
+}
+
+/// 第一个名为element的元素的attribute属性
+fn attribute(xml: &str, element: &str, attribute: &str) -> Option<String> {
+    let start = xml.find(&format!("<{} ", element))?;
+    let tag = &xml[start..start + xml[start..].find('>')?];
+    let key = format!("{}=", attribute);
+    let value = &tag[tag.find(&key)? + key.len()..];
+    let quote = value.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+    let value = &value[1..];
+    Some(unescape(&value[..value.find(quote)?]))
+}
+
+/// EventData中Name为name的Data元素的文本
+fn data(xml: &str, name: &str) -> Option<String> {
+    ["\"", "'"].iter().find_map(|quote| {
+        let open = format!("<Data Name={}{}{}>", quote, name, quote);
+        let content = &xml[xml.find(&open)? + open.len()..];
+        Some(unescape(&content[..content.find("</Data>")?]))
+    })
+}
+
+/// 二进制数据是十六进制表示的UTF-16LE服务名称,可能以NUL结尾
+fn decode_binary_name(hex: String) -> Option<String> {
+    let hex = hex.trim();
+    if hex.len() & 3 != 0 || !hex.is_ascii() {
+        return None;
+    }
+    let units = (0..hex.len())
+        .step_by(4)
+        .map(|i| {
+            let low = u8::from_str_radix(&hex[i..i + 2], 16).ok()?;
+            let high = u8::from_str_radix(&hex[i + 2..i + 4], 16).ok()?;
+            Some(u16::from_le_bytes([low, high]))
+        })
+        .collect::<Option<Vec<u16>>>()?;
+    let len = units.iter().position(|c| *c == 0).unwrap_or(units.len());
+    Some(String::from_utf16_lossy(&units[..len]))
+}
+
+/// 还原XML中的字符实体
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// 解析"2024-05-01T10:11:12.1234567Z"形式的UTC时间
+fn parse_system_time(text: &str) -> Option<SystemTime> {
+    let text = text.trim().strip_suffix('Z')?;
+    let (date, time) = text.split_once('T')?;
+    let mut date = date.splitn(3, '-').map(|part| part.parse::<i64>().ok());
+    let (year, month, day) = (date.next()??, date.next()??, date.next()??);
+    let (time, fraction) = time.split_once('.').unwrap_or((time, ""));
+    let mut time = time.splitn(3, ':').map(|part| part.parse::<u64>().ok());
+    let (hour, minute, second) = (time.next()??, time.next()??, time.next()??);
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) || hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+    if !fraction.bytes().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    // 小数部分最多取到纳秒
+    let nanos = format!("{:0<9}", &fraction[..fraction.len().min(9)]).parse::<u32>().ok()?;
+    // 公历日期到1970-01-01的天数
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let year_of_era = y - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = u64::try_from(era * 146_097 + day_of_era - 719_468).ok()?;
+    let seconds = days * 86_400 + hour * 3_600 + minute * 60 + second;
+    Some(SystemTime::UNIX_EPOCH + Duration::new(seconds, nanos))
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, SystemTime};
+
+    use crate::eventlog::{parse_exit_code, parse_failure_event, parse_system_time, ServiceFailure};
+    use crate::failure::FailureAction;
+
+    /// 按EvtRenderEventXml的格式构造事件
+    fn event(event_id: u32, data: &[(&str, &str)], binary: &str) -> String {
+        let data: String = data
+            .iter()
+            .map(|(name, value)| format!("<Data Name=\"{}\">{}</Data>", name, value))
+            .collect();
+        format!(
+            "<Event xmlns='http://schemas.microsoft.com/win/2004/08/events/event'><System>\
+             <Provider Name='Service Control Manager'/><EventID Qualifiers='49152'>{}</EventID>\
+             <TimeCreated SystemTime='2024-05-01T10:11:12.5000000Z'/><Channel>System</Channel></System>\
+             <EventData>{}<Binary>{}</Binary></EventData></Event>",
+            event_id, data, binary
+        )
+    }
+
+    #[test]
+    fn system_time() {
+        assert_eq!(parse_system_time("1970-01-01T00:00:00Z"), Some(SystemTime::UNIX_EPOCH));
+        assert_eq!(
+            parse_system_time("2024-05-01T10:11:12.5000000Z"),
+            Some(SystemTime::UNIX_EPOCH + Duration::new(1_714_558_272, 500_000_000))
+        );
+        assert_eq!(
+            parse_system_time("2000-03-01T00:00:00.123456789123Z"),
+            Some(SystemTime::UNIX_EPOCH + Duration::new(951_868_800, 123_456_789))
+        );
+        assert!(parse_system_time("2024-13-01T00:00:00Z").is_none());
+        assert!(parse_system_time("2024-05-01 00:00:00").is_none());
+        assert!(parse_system_time("1969-12-31T23:59:59Z").is_none());
+    }
+
+    #[test]
+    fn exit_code() {
+        assert_eq!(parse_exit_code("%%1067"), Some(1067));
+        assert_eq!(parse_exit_code("5"), Some(5));
+        assert_eq!(parse_exit_code("-2147024891"), Some(0x8007_0005));
+        assert_eq!(parse_exit_code("%%abc"), None);
+    }
+
+    #[test]
+    fn failure_event() {
+        // "Lers"的UTF-16LE
+        let binary = "4C00650072007300";
+        let time = SystemTime::UNIX_EPOCH + Duration::new(1_714_558_272, 500_000_000);
+        let crashed = event(
+            7031,
+            &[
+                ("param1", "Lers &amp; Co"),
+                ("param2", "3"),
+                ("param3", "60000"),
+                ("param4", "1"),
+                ("param5", "Restart the service"),
+            ],
+            binary,
+        );
+        assert_eq!(
+            parse_failure_event(&crashed, "lers", "Other"),
+            Some(ServiceFailure {
+                time,
+                event_id: 7031,
+                exit_code: None,
+                failure_count: Some(3),
+                recovery_action: Some(FailureAction::Restart { delay: Duration::from_secs(60) }),
+            })
+        );
+        // 没有二进制数据时按显示名称匹配
+        let crashed = event(7034, &[("param1", "Lers &amp; Co"), ("param2", "2")], "");
+        let failure = parse_failure_event(&crashed, "Lers", "Lers & Co").unwrap();
+        assert_eq!((failure.event_id, failure.failure_count, failure.recovery_action), (7034, Some(2), None));
+        assert!(parse_failure_event(&crashed, "Lers", "Lers").is_none());
+
+        let terminated = event(7023, &[("param1", "Lers"), ("param2", "%%1067")], binary);
+        let failure = parse_failure_event(&terminated, "Lers", "Lers").unwrap();
+        assert_eq!((failure.exit_code, failure.failure_count), (Some(1067), None));
+        assert!(parse_failure_event(&event(7036, &[("param1", "Lers")], binary), "Lers", "Lers").is_none());
+    }
+}
@@ -0,0 +1,401 @@
+use std::time::Duration;
+
+use windows::Win32::System::Services::{
+    SC_ACTION, SC_ACTION_NONE, SC_ACTION_REBOOT, SC_ACTION_RESTART, SC_ACTION_RUN_COMMAND, SERVICE_FAILURE_ACTIONSW,
+};
+
+use crate::dword::ServiceError;
+use crate::wide_to_string;
+
+/// dwResetPeriod为INFINITE时表示永不重置失败计数
+const INFINITE: u32 = 0xFFFF_FFFF;
+
+/// 恢复策略中最多的操作数量,SCM只按失败次数依次取用,超出的操作没有实际意义
+pub const MAX_FAILURE_ACTIONS: usize = 64;
+
+/// 服务失败时执行的操作
+/// 启用serde特性后序列化为`{ "action": "restart", "delay": "30s" }`的形式
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "action", rename_all = "snake_case"))]
+pub enum FailureAction {
+    /// 不执行任何操作
+    None {
+        #[cfg_attr(feature = "serde", serde(with = "duration_text", default))]
+        delay: Duration,
+    },
+    /// 重新启动服务(设置时需要SERVICE_START权限)
+    Restart {
+        #[cfg_attr(feature = "serde", serde(with = "duration_text"))]
+        delay: Duration,
+    },
+    /// 重新启动计算机(设置时调用者需要SE_SHUTDOWN_NAME特权)
+    Reboot {
+        #[cfg_attr(feature = "serde", serde(with = "duration_text"))]
+        delay: Duration,
+    },
+    /// 运行FailureActions::command指定的命令
+    RunCommand {
+        #[cfg_attr(feature = "serde", serde(with = "duration_text"))]
+        delay: Duration,
+    },
+}
+
+impl FailureAction {
+    /// # 执行操作前等待的时间
+    pub fn delay(&self) -> Duration {
+        match self {
+            FailureAction::None { delay }
+            | FailureAction::Restart { delay }
+            | FailureAction::Reboot { delay }
+            | FailureAction::RunCommand { delay } => *delay,
+        }
+    }
+
+    fn to_raw(&self) -> SC_ACTION {
+        SC_ACTION {
+            Type: match self {
+                FailureAction::None { .. } => SC_ACTION_NONE,
+                FailureAction::Restart { .. } => SC_ACTION_RESTART,
+                FailureAction::Reboot { .. } => SC_ACTION_REBOOT,
+                FailureAction::RunCommand { .. } => SC_ACTION_RUN_COMMAND,
+            },
+            Delay: self.delay().as_millis() as u32,
+        }
+    }
+
+    fn from_raw(action: &SC_ACTION) -> FailureAction {
+        let delay = Duration::from_millis(u64::from(action.Delay));
+        match action.Type {
+            SC_ACTION_RESTART => FailureAction::Restart { delay },
+            SC_ACTION_REBOOT => FailureAction::Reboot { delay },
+            SC_ACTION_RUN_COMMAND => FailureAction::RunCommand { delay },
+            _ => FailureAction::None { delay },
+        }
+    }
+}
+
+/// 服务的失败恢复策略(SERVICE_FAILURE_ACTIONSW)
+/// 第n次失败执行actions中的第n个操作,超过长度时重复最后一个操作
+/// ## 例子
+/// 启用serde特性后可以从配置文件读取,时长使用"30s"、"1m30s"、"500ms"等形式:
+/// ```json
+/// {
+///     "reset_period": "1d",
+///     "command": "C:\\Lers\\notify.exe",
+///     "actions": [
+///         { "action": "restart", "delay": "30s" },
+///         { "action": "restart", "delay": "2m" },
+///         { "action": "run_command", "delay": "0s" }
+///     ]
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FailureActions {
+    /// 没有失败多长时间后将失败计数重置为0,精确到秒,None表示永不重置
+    #[cfg_attr(feature = "serde", serde(with = "reset_period_text", default))]
+    pub reset_period: Option<Duration>,
+    /// 执行Reboot操作前广播的消息,为空时使用默认消息
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub reboot_message: String,
+    /// RunCommand操作运行的命令行
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub command: String,
+    /// 依次执行的操作,为空时删除恢复策略
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub actions: Vec<FailureAction>,
+}
+
+impl FailureActions {
+    /// # 失败后自动重新启动服务的恢复策略
+    /// 最常见的配置:每次失败后等待delay重新启动服务,reset_period内没有失败时重置失败计数。
+    /// - max_restarts为None时一直重新启动(只有一个Restart操作,之后的失败重复该操作)
+    /// - max_restarts为Some(n)时前n次失败重新启动,之后不再执行任何操作;Some(0)等同于不重新启动。
+    ///   n加上最后的None操作不能超过MAX_FAILURE_ACTIONS,否则返回ERROR_INVALID_PARAMETER
+    /// ## 参数
+    /// ### input:
+    /// - delay: 每次重新启动前等待的时间
+    /// - reset_period: 没有失败多长时间后重置失败计数,精确到秒
+    /// - max_restarts: 最多重新启动的次数
+    /// ### output:
+    /// - Result<FailureActions,ServiceError>
+    /// ## 例子
+    /// ```
+    /// use std::time::Duration;
+    /// use windows_service_controller::failure::{FailureAction, FailureActions};
+    /// let actions = FailureActions::auto_restart(Duration::from_secs(5), Duration::from_secs(86400), Some(2)).unwrap();
+    /// assert_eq!(actions.actions.len(), 3);
+    /// assert_eq!(actions.actions[2], FailureAction::None { delay: Duration::ZERO });
+    /// ```
+    pub fn auto_restart(
+        delay: Duration,
+        reset_period: Duration,
+        max_restarts: Option<u32>,
+    ) -> Result<FailureActions, ServiceError> {
+        let restart = FailureAction::Restart { delay };
+        let actions = match max_restarts {
+            None => vec![restart],
+            // 先检查数量再分配,避免很大的n耗尽内存
+            Some(n) if n as usize >= MAX_FAILURE_ACTIONS => return Err(ServiceError::ERROR_INVALID_PARAMETER),
+            Some(n) => std::iter::repeat_n(restart, n as usize)
+                .chain(Some(FailureAction::None { delay: Duration::ZERO }))
+                .collect(),
+        };
+        Ok(FailureActions { reset_period: Some(reset_period), actions, ..Default::default() })
+    }
+
+    /// # 检查恢复策略是否可以设置
+    /// - 操作数量不能超过MAX_FAILURE_ACTIONS
+    /// - 包含RunCommand操作时command不能为空
+    /// - 每个操作的delay不能超过u32::MAX毫秒
+    /// - reset_period不能超过u32::MAX - 1秒
+    /// ## 参数
+    /// ### output:
+    /// - Result<(),ServiceError>: 不满足时返回ERROR_INVALID_PARAMETER
+    pub fn validate(&self) -> Result<(), ServiceError> {
+        if self.actions.len() > MAX_FAILURE_ACTIONS {
+            return Err(ServiceError::ERROR_INVALID_PARAMETER);
+        }
+        let has_command = self.actions.iter().any(|a| matches!(a, FailureAction::RunCommand { .. }));
+        if has_command && self.command.trim().is_empty() {
+            return Err(ServiceError::ERROR_INVALID_PARAMETER);
+        }
+        if self.actions.iter().any(|a| a.delay().as_millis() > u128::from(u32::MAX)) {
+            return Err(ServiceError::ERROR_INVALID_PARAMETER);
+        }
+        if self.reset_period.is_some_and(|p| p.as_secs() >= u64::from(INFINITE)) {
+            return Err(ServiceError::ERROR_INVALID_PARAMETER);
+        }
+        Ok(())
+    }
+
+    /// # 是否包含Restart操作
+    /// 设置包含Restart操作的策略时,服务句柄需要SERVICE_START权限
+    pub fn has_restart(&self) -> bool {
+        self.actions.iter().any(|a| matches!(a, FailureAction::Restart { .. }))
+    }
+
+    /// # 是否包含Reboot操作
+    /// 服务意外终止时会重新启动计算机
+    pub fn has_reboot(&self) -> bool {
+        self.actions.iter().any(|a| matches!(a, FailureAction::Reboot { .. }))
+    }
+
+    /// 转换为SC_ACTION列表,需要先通过validate检查
+    pub(crate) fn raw_actions(&self) -> Vec<SC_ACTION> {
+        self.actions.iter().map(FailureAction::to_raw).collect()
+    }
+
+    /// 转换为dwResetPeriod
+    pub(crate) fn raw_reset_period(&self) -> u32 {
+        match self.reset_period {
+            None => INFINITE,
+            Some(period) => period.as_secs() as u32,
+        }
+    }
+
+    /// 从QueryServiceConfig2W返回的结构体复制出恢复策略
+    /// ## Safety
+    /// raw中的指针必须有效,lpsaActions指向cActions个SC_ACTION
+    pub(crate) unsafe fn from_raw(raw: &SERVICE_FAILURE_ACTIONSW) -> FailureActions {
+        let actions = if raw.lpsaActions.is_null() {
+            Vec::new()
+        } else {
+            (0..raw.cActions as usize)
+                .map(|i| FailureAction::from_raw(&std::ptr::read_unaligned(raw.lpsaActions.add(i))))
+                .collect()
+        };
+        FailureActions {
+            reset_period: match raw.dwResetPeriod {
+                INFINITE => None,
+                seconds => Some(Duration::from_secs(u64::from(seconds))),
+            },
+            reboot_message: wide_to_string(raw.lpRebootMsg),
+            command: wide_to_string(raw.lpCommand),
+            actions,
+        }
+    }
+}
+
+/// # 将时长格式化为"1h30m"、"30s"、"500ms"等形式
+/// 依次使用d、h、m、s、ms单位,省略为0的部分,零时长为"0s"
+pub fn format_duration(duration: Duration) -> String {
+    if duration.is_zero() {
+        return "0s".to_string();
+    }
+    let mut millis = duration.as_millis();
+    let mut result = String::new();
+    for (unit, size) in [("d", 86_400_000), ("h", 3_600_000), ("m", 60_000), ("s", 1000), ("ms", 1)] {
+        if millis >= size {
+            result.push_str(&format!("{}{}", millis / size, unit));
+            millis %= size;
+        }
+    }
+    result
+}
+
+/// # 解析format_duration格式的时长
+/// 由一个或多个"数字+单位"组成,单位为d、h、m、s、ms,如"30s"、"1m30s"、"1d"。
+/// 单独的"0"表示零时长
+/// ## 参数
+/// ### input:
+/// - text: 时长文本
+/// ### output:
+/// - Result<Duration,String>: 格式错误时返回错误描述
+pub fn parse_duration(text: &str) -> Result<Duration, String> {
+    let text = text.trim();
+    if text == "0" {
+        return Ok(Duration::ZERO);
+    }
+    if text.is_empty() {
+        return Err("时长不能为空".to_string());
+    }
+    let mut total = Duration::ZERO;
+    let mut rest = text;
+    while !rest.is_empty() {
+        let digits = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+        if digits == 0 {
+            return Err(format!("时长\"{}\"缺少数字", text));
+        }
+        let value: u64 = rest[..digits].parse().map_err(|_| format!("时长\"{}\"的数字过大", text))?;
+        rest = &rest[digits..];
+        let unit_len = rest.find(|c: char| c.is_ascii_digit()).unwrap_or(rest.len());
+        let part = match &rest[..unit_len] {
+            "ms" => Some(Duration::from_millis(value)),
+            "s" => Some(Duration::from_secs(value)),
+            "m" => value.checked_mul(60).map(Duration::from_secs),
+            "h" => value.checked_mul(3600).map(Duration::from_secs),
+            "d" => value.checked_mul(86_400).map(Duration::from_secs),
+            "" => return Err(format!("时长\"{}\"缺少单位", text)),
+            unit => return Err(format!("时长\"{}\"的单位\"{}\"无效,可用的单位为d、h、m、s、ms", text, unit)),
+        };
+        total = part
+            .and_then(|part| total.checked_add(part))
+            .ok_or_else(|| format!("时长\"{}\"过大", text))?;
+        rest = &rest[unit_len..];
+    }
+    Ok(total)
+}
+
+/// 以format_duration/parse_duration的格式(反)序列化Duration
+#[cfg(feature = "serde")]
+mod duration_text {
+    use std::time::Duration;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&super::format_duration(*duration))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        super::parse_duration(&text).map_err(serde::de::Error::custom)
+    }
+}
+
+/// 与duration_text相同,None表示为"infinite"
+#[cfg(feature = "serde")]
+mod reset_period_text {
+    use std::time::Duration;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(period: &Option<Duration>, serializer: S) -> Result<S::Ok, S::Error> {
+        match period {
+            None => serializer.serialize_str("infinite"),
+            Some(period) => serializer.serialize_str(&super::format_duration(*period)),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Duration>, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        if text.trim().eq_ignore_ascii_case("infinite") {
+            return Ok(None);
+        }
+        super::parse_duration(&text).map(Some).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use crate::dword::ServiceError;
+    use crate::failure::{format_duration, parse_duration, FailureAction, FailureActions, MAX_FAILURE_ACTIONS};
+
+    #[test]
+    fn duration_text() {
+        assert_eq!(format_duration(Duration::ZERO), "0s");
+        assert_eq!(format_duration(Duration::from_secs(30)), "30s");
+        assert_eq!(format_duration(Duration::from_secs(90)), "1m30s");
+        assert_eq!(format_duration(Duration::from_millis(86_400_500)), "1d500ms");
+        assert_eq!(parse_duration("0"), Ok(Duration::ZERO));
+        assert_eq!(parse_duration("30s"), Ok(Duration::from_secs(30)));
+        assert_eq!(parse_duration("1m30s"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_duration(" 2h "), Ok(Duration::from_secs(7200)));
+        assert_eq!(parse_duration("1500ms"), Ok(Duration::from_millis(1500)));
+        for duration in [Duration::from_millis(1), Duration::from_secs(3661), Duration::from_millis(90_061_001)] {
+            assert_eq!(parse_duration(&format_duration(duration)), Ok(duration));
+        }
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("30").is_err());
+        assert!(parse_duration("s").is_err());
+        assert!(parse_duration("5w").is_err());
+    }
+
+    #[test]
+    fn auto_restart() {
+        let delay = Duration::from_secs(5);
+        let forever = FailureActions::auto_restart(delay, Duration::from_secs(60), None).unwrap();
+        assert_eq!(forever.actions, vec![FailureAction::Restart { delay }]);
+        assert_eq!(forever.raw_reset_period(), 60);
+        assert_eq!(forever.validate(), Ok(()));
+        let limited = FailureActions::auto_restart(delay, Duration::from_secs(60), Some(2)).unwrap();
+        assert_eq!(
+            limited.actions,
+            vec![
+                FailureAction::Restart { delay },
+                FailureAction::Restart { delay },
+                FailureAction::None { delay: Duration::ZERO },
+            ]
+        );
+        let never = FailureActions::auto_restart(delay, Duration::from_secs(60), Some(0)).unwrap();
+        assert!(!never.has_restart());
+        // 加上最后的None操作正好是上限
+        let most = FailureActions::auto_restart(delay, Duration::from_secs(60), Some(MAX_FAILURE_ACTIONS as u32 - 1)).unwrap();
+        assert_eq!(most.actions.len(), MAX_FAILURE_ACTIONS);
+        assert_eq!(most.validate(), Ok(()));
+        for n in [MAX_FAILURE_ACTIONS as u32, u32::MAX] {
+            assert_eq!(
+                FailureActions::auto_restart(delay, Duration::from_secs(60), Some(n)),
+                Err(ServiceError::ERROR_INVALID_PARAMETER)
+            );
+        }
+        let too_many = FailureActions { actions: vec![FailureAction::Restart { delay }; MAX_FAILURE_ACTIONS + 1], ..Default::default() };
+        assert_eq!(too_many.validate(), Err(ServiceError::ERROR_INVALID_PARAMETER));
+    }
+
+    #[test]
+    fn validate_actions() {
+        let mut actions = FailureActions {
+            reset_period: Some(Duration::from_secs(86_400)),
+            actions: vec![
+                FailureAction::Restart { delay: Duration::from_secs(30) },
+                FailureAction::RunCommand { delay: Duration::ZERO },
+            ],
+            ..Default::default()
+        };
+        assert_eq!(actions.validate(), Err(ServiceError::ERROR_INVALID_PARAMETER));
+        actions.command = "C:\\Lers\\notify.exe".to_string();
+        assert_eq!(actions.validate(), Ok(()));
+        assert!(actions.has_restart());
+        assert!(!actions.has_reboot());
+        assert_eq!(actions.raw_reset_period(), 86_400);
+        actions.actions.push(FailureAction::Reboot { delay: Duration::from_millis(u32::MAX as u64 + 1) });
+        assert!(actions.has_reboot());
+        assert_eq!(actions.validate(), Err(ServiceError::ERROR_INVALID_PARAMETER));
+        assert_eq!(FailureActions::default().raw_reset_period(), 0xFFFF_FFFF);
+    }
+}
@@ -0,0 +1,226 @@
+use windows::Win32::System::Services::{ControlService, StartServiceW, SC_HANDLE, SERVICE_STATUS};
+
+use crate::dword::{ScManagerAccess, ServiceAccess, ServiceControlCode, ServiceError};
+use crate::status::FullStatus;
+use crate::{close_service_handle, ServiceName, WindowsService};
+
+/// 可以在线程间转移的服务句柄
+/// WindowsService持有原始句柄,不实现Send;需要把服务控制交给其他线程(如线程池)时,
+/// 可以将其转换为ServiceHandle后再移动。ServiceHandle只提供启动、停止、发送控制和查询状态等最少的操作。
+/// ## Safety
+/// ServiceHandle通过`unsafe impl Send`声明可以转移到其他线程,由使用者保证:
+/// - 同一时刻只有一个线程通过该句柄执行操作(ServiceHandle不实现Sync,需要共享时请自行加锁);
+/// - 不通过其他途径(例如保存的原始句柄)关闭或复用其中的句柄。
+///
+/// SCM句柄本身可以在任意线程中使用,但对同一服务的并发控制(例如一个线程停止、另一个线程启动)
+/// 的结果取决于执行顺序,本库不做同步
+/// ## 例子
+/// ```
+/// use windows_service_controller::handle::ServiceHandle;
+/// use windows_service_controller::WindowsService;
+/// let service = WindowsService::open("Lers", None, None).unwrap();
+/// let handle = ServiceHandle::from(service);
+/// std::thread::spawn(move || {
+///     handle.stop().unwrap();
+/// })
+/// .join()
+/// .unwrap();
+/// ```
+pub struct ServiceHandle {
+    name: ServiceName,
+    service_handle: OwnedScHandle,
+    /// 只为在drop时关闭而持有
+    _sc_manager_handle: OwnedScHandle,
+    access: ServiceAccess,
+}
+
+// 见ServiceHandle的Safety说明:句柄由ServiceHandle独占,转移后只由一个线程使用
+unsafe impl Send for ServiceHandle {}
+
+/// 持有所有权的SCM或服务句柄,drop时通过close_service_handle关闭
+/// 空句柄表示不持有任何句柄,drop时什么也不做。drop时忽略关闭失败,不会panic;
+/// 需要知道关闭是否成功时使用close。可以用into_raw交出所有权,由调用者负责关闭
+#[derive(Debug, Default)]
+pub(crate) struct OwnedScHandle(SC_HANDLE);
+
+impl OwnedScHandle {
+    /// 接管句柄的所有权
+    pub(crate) fn new(handle: SC_HANDLE) -> OwnedScHandle {
+        OwnedScHandle(handle)
+    }
+
+    /// 原始句柄,所有权仍由OwnedScHandle持有
+    pub(crate) fn get(&self) -> SC_HANDLE {
+        self.0
+    }
+
+    pub(crate) fn is_invalid(&self) -> bool {
+        self.0.is_invalid()
+    }
+
+    /// 交出句柄的所有权,之后由调用者负责关闭
+    pub(crate) fn into_raw(mut self) -> SC_HANDLE {
+        std::mem::take(&mut self.0)
+    }
+
+    /// 立即关闭句柄,无论成功与否之后都持有空句柄;空句柄直接返回Ok
+    pub(crate) fn close(&mut self) -> Result<(), ServiceError> {
+        let handle = std::mem::take(&mut self.0);
+        if handle.is_invalid() || close_service_handle(handle).is_ok() {
+            Ok(())
+        } else {
+            Err(ServiceError::from_last_error())
+        }
+    }
+}
+
+impl Drop for OwnedScHandle {
+    fn drop(&mut self) {
+        let _ = self.close();
+    }
+}
+
+impl From<WindowsService> for ServiceHandle {
+    /// 取出WindowsService的句柄,WindowsService不再关闭它们
+    fn from(mut service: WindowsService) -> Self {
+        ServiceHandle {
+            name: ServiceName(std::mem::take(&mut service.name.0)),
+            service_handle: std::mem::take(&mut service.service_handle),
+            _sc_manager_handle: std::mem::take(&mut service.sc_manager_handle),
+            access: service.access,
+        }
+    }
+}
+
+impl ServiceHandle {
+    /// # 通过服务名打开服务句柄
+    /// 与WindowsService::open相同,但不读取服务配置
+    /// ## 参数
+    /// ### input:
+    /// - name: 服务名称(不是显示名称)
+    /// - service_access: 默认为SERVICE_ALL_ACCESS
+    /// ### output:
+    /// - Result<ServiceHandle,ServiceError>
+    pub fn open(name: &str, service_access: Option<ServiceAccess>) -> Result<ServiceHandle, ServiceError> {
+        let service_name = ServiceName::try_new(name)?;
+        let sc_manager_handle =
+            OwnedScHandle::new(WindowsService::open_sc_manager(ScManagerAccess::SC_MANAGER_CONNECT)?);
+        let access = service_access.unwrap_or(ServiceAccess::SERVICE_ALL_ACCESS);
+        let service_handle = WindowsService::open_service(sc_manager_handle.get(), name, access)?;
+        Ok(ServiceHandle {
+            name: service_name,
+            service_handle: OwnedScHandle::new(service_handle),
+            _sc_manager_handle: sc_manager_handle,
+            access,
+        })
+    }
+
+    /// # 服务名称
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// # 打开服务时请求的访问权限
+    pub fn access(&self) -> ServiceAccess {
+        self.access
+    }
+
+    /// # 查询服务的扩展状态
+    pub fn query_status_ex(&self) -> Result<FullStatus, ServiceError> {
+        WindowsService::get_status_ex(self.handle_for(ServiceAccess::SERVICE_QUERY_STATUS)?)
+    }
+
+    /// # 启动服务
+    #[must_use = "启动服务可能失败,应当检查返回的结果"]
+    pub fn start(&self) -> Result<(), ServiceError> {
+        let service_handle = self.handle_for(ServiceAccess::SERVICE_START)?;
+        match unsafe { StartServiceW(service_handle, None) } {
+            Ok(_) => Ok(()),
+            Err(_) => Err(ServiceError::from_last_error()),
+        }
+    }
+
+    /// # 停止服务
+    /// 只发送SERVICE_CONTROL_STOP,不等待服务停止
+    #[must_use = "停止服务可能失败,应当检查返回的结果"]
+    pub fn stop(&self) -> Result<(), ServiceError> {
+        self.control(ServiceControlCode::SERVICE_CONTROL_STOP)
+    }
+
+    /// # 向服务发送控制代码
+    #[must_use = "控制请求可能失败,应当检查返回的结果"]
+    pub fn control(&self, code: ServiceControlCode) -> Result<(), ServiceError> {
+        let code: u32 = code.into();
+        let service_handle = self.handle_for(WindowsService::control_access(code))?;
+        let mut service_status = SERVICE_STATUS::default();
+        match unsafe { ControlService(service_handle, code, &mut service_status) } {
+            Ok(_) => Ok(()),
+            Err(_) => Err(ServiceError::from_last_error()),
+        }
+    }
+
+    /// 获取服务句柄,并检查打开服务时是否请求了操作所需的访问权限
+    fn handle_for(&self, required: ServiceAccess) -> Result<SC_HANDLE, ServiceError> {
+        if self.service_handle.is_invalid() {
+            return Err(ServiceError::ERROR_INVALID_HANDLE);
+        }
+        match ServiceError::missing_access(self.access, required) {
+            None => Ok(self.service_handle.get()),
+            Some(e) => Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::dword::{ScManagerAccess, ServiceAccess, ServiceError};
+    use crate::handle::{OwnedScHandle, ServiceHandle};
+    use crate::{close_service_handle, WindowsService};
+
+    fn assert_send<T: Send>() {}
+
+    #[test]
+    fn handle_is_send() {
+        assert_send::<ServiceHandle>();
+        match ServiceHandle::open("WSearch", Some(ServiceAccess::GENERIC_READ)) {
+            Ok(handle) => {
+                let status = std::thread::spawn(move || handle.query_status_ex()).join().unwrap();
+                match status {
+                    Ok(status) => println!("{:?}", status.current_state()),
+                    Err(e) => println!("{}", e),
+                }
+            }
+            Err(e) => {
+                println!("{}", e);
+            }
+        }
+    }
+
+    #[test]
+    fn null_guard() {
+        let mut guard = OwnedScHandle::default();
+        assert!(guard.is_invalid());
+        assert_eq!(guard.close(), Ok(()));
+        assert!(OwnedScHandle::default().into_raw().is_invalid());
+    }
+
+    #[test]
+    fn guard_into_raw() {
+        match WindowsService::open_sc_manager(ScManagerAccess::SC_MANAGER_CONNECT) {
+            Ok(handle) => {
+                let raw = OwnedScHandle::new(handle).into_raw();
+                assert_eq!(raw, handle);
+                // into_raw交出所有权后guard不会关闭句柄,由调用者关闭
+                assert!(close_service_handle(raw).is_ok());
+                let mut guard = OwnedScHandle::new(WindowsService::open_sc_manager(ScManagerAccess::SC_MANAGER_CONNECT).unwrap());
+                assert_eq!(guard.close(), Ok(()));
+                assert!(guard.is_invalid());
+                assert_eq!(guard.close(), Ok(()));
+            }
+            Err(e) => {
+                assert_ne!(e, ServiceError::ERROR_INVALID_HANDLE);
+                println!("{}", e);
+            }
+        }
+    }
+}
@@ -1,30 +1,381 @@
+use std::collections::HashSet;
+use std::ffi::{c_void, OsStr};
+use std::os::windows::ffi::OsStrExt;
+#[cfg(debug_assertions)]
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
 use lers_windows_macro::PCWSTR;
-use windows::core::PCWSTR;
-use windows::Win32::Foundation::GetLastError;
-use windows::Win32::System::Services::{ChangeServiceConfigW, CloseServiceHandle, ControlService, CreateServiceW, DeleteService, OpenSCManagerW, OpenServiceW, QUERY_SERVICE_CONFIGW, QueryServiceConfigW, QueryServiceStatus, SC_HANDLE, SERVICE_STATUS, StartServiceW};
+use windows::core::{PCWSTR, PWSTR};
+use windows::Win32::Foundation::{CloseHandle, ERROR_INSUFFICIENT_BUFFER, GetLastError, HANDLE, WIN32_ERROR};
+use windows::Win32::Security::{GetTokenInformation, TokenElevation, TOKEN_ELEVATION, TOKEN_QUERY};
+use windows::Win32::System::Services;
+use windows::Win32::System::Services::{ChangeServiceConfig2W, NotifyBootConfigStatus, ChangeServiceConfigW, CloseServiceHandle, ControlService, DeleteService, ENUM_SERVICE_TYPE, OpenSCManagerW, OpenServiceW, QUERY_SERVICE_CONFIGW, QueryServiceConfig2W, SERVICE_FAILURE_ACTIONSW, SERVICE_LAUNCH_PROTECTED_INFO, QueryServiceConfigW, QueryServiceStatus, QueryServiceStatusEx, SC_HANDLE, SC_STATUS_PROCESS_INFO, SERVICE_DELAYED_AUTO_START_INFO, SERVICE_ERROR, SERVICE_START_TYPE, SERVICE_STATUS, SERVICE_STATUS_PROCESS, SERVICE_TRIGGER_INFO, StartServiceW};
+use windows::Win32::System::Threading::{GetCurrentProcess, OpenProcessToken};
 
-use crate::dword::{ScManagerAccess, ServiceAccess, ServiceControlCode, ServiceError, ServiceErrorControl, ServiceStartType, ServiceStatus, ServiceType};
+use crate::builder::ServiceBuilder;
+use crate::config::{ConfigChange, ConfigDiff, Dependency, ServiceConfig, StartBehavior};
+use crate::dword::{ControlsAccepted, LaunchProtected, ScManagerAccess, ServiceAccess, ServiceConfigInfoLevel, ServiceControlCode, ServiceError, ServiceErrorControl, ServiceStartType, ServiceStatus, ServiceType};
+use crate::enumerate::ServiceInfo;
+use crate::failure::FailureActions;
+use crate::handle::OwnedScHandle;
+use crate::manager::ServiceManager;
+use crate::status::{FullStatus, ProgressTracker, StartProgress};
+use crate::watch::{StatusReceiver, WatchHandle, WatchHandles};
 
+pub mod builder;
+pub mod config;
+pub mod control_event;
 pub mod dword;
+pub mod enumerate;
+#[cfg(feature = "eventlog")]
+pub mod eventlog;
+pub mod failure;
+pub mod handle;
+pub mod manager;
+pub mod order;
+pub mod process;
+pub mod path;
+mod registry;
+mod sc;
+#[cfg(feature = "signature")]
+mod signature;
+pub mod status;
+pub mod watch;
+
+/// 服务名称的最大长度(按UTF-16编码单元计算)
+const MAX_SERVICE_NAME_LEN: usize = 256;
+
+/// 发送停止、暂停控制前等待服务离开挂起状态的默认超时时间
+pub(crate) const DEFAULT_PENDING_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// control_and_wait、wait_for_ready在服务还没有到达预期状态时重新查询的间隔
+const CONTROL_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// 查询变长数据时的最大尝试次数
+const QUERY_BUFFER_ATTEMPTS: usize = 4;
+
+/// 调试构建中本库打开且尚未关闭的SCM和服务句柄数量
+#[cfg(debug_assertions)]
+static OPEN_HANDLES: AtomicUsize = AtomicUsize::new(0);
+
+#[cfg(debug_assertions)]
+thread_local! {
+    /// 调试构建中当前线程累计打开过的句柄数量(包括已经关闭的)
+    static OPENED_ON_THREAD: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+/// 记录打开了一个句柄,所有OpenSCManagerW、OpenServiceW、CreateServiceW成功后调用
+pub(crate) fn handle_opened() {
+    #[cfg(debug_assertions)]
+    {
+        OPEN_HANDLES.fetch_add(1, Ordering::Relaxed);
+        OPENED_ON_THREAD.with(|opened| opened.set(opened.get() + 1));
+    }
+}
+
+/// 调试构建中当前线程累计打开过的句柄数量,发布构建返回None。
+/// 比较操作前后的值可以检查操作是否复用了已打开的句柄,不受其他线程的影响
+#[cfg_attr(not(test), allow(dead_code))]
+pub(crate) fn handles_opened_on_thread() -> Option<usize> {
+    #[cfg(debug_assertions)]
+    return Some(OPENED_ON_THREAD.with(|opened| opened.get()));
+    #[cfg(not(debug_assertions))]
+    None
+}
+
+/// 关闭SCM或服务句柄并更新调试构建中的句柄计数,本库打开的句柄都应当通过它关闭
+pub(crate) fn close_service_handle(handle: SC_HANDLE) -> windows::core::Result<()> {
+    unsafe { CloseServiceHandle(handle) }?;
+    #[cfg(debug_assertions)]
+    {
+        let previous = OPEN_HANDLES.fetch_sub(1, Ordering::Relaxed);
+        debug_assert!(previous > 0, "关闭的句柄数量多于打开的句柄数量");
+    }
+    Ok(())
+}
+
+/// # 当前打开的句柄数量(仅调试构建)
+/// 统计本库打开且尚未关闭的SCM和服务句柄,包括WindowsService、ServiceManager、ServiceHandle和监听线程持有的句柄。
+/// 在测试中比较操作前后的数量可以发现句柄泄漏(例如忘记drop或者mem::forget了WindowsService)。
+/// 发布构建不统计,返回None
+/// ## 参数
+/// ### output:
+/// - Option<usize>
+/// ## 例子
+/// ```
+/// use windows_service_controller::{open_handle_count, WindowsService};
+/// let before = open_handle_count();
+/// drop(WindowsService::open("WSearch", None, None));
+/// assert_eq!(open_handle_count(), before);
+/// ```
+pub fn open_handle_count() -> Option<usize> {
+    #[cfg(debug_assertions)]
+    return Some(OPEN_HANDLES.load(Ordering::Relaxed));
+    #[cfg(not(debug_assertions))]
+    None
+}
+
+/// 以按需增长的缓冲区调用查询函数
+/// query返回ERROR_INSUFFICIENT_BUFFER并给出更大的所需字节数时扩大缓冲区重试。
+/// 数据可能在两次调用之间被其他进程修改而变大,尝试QUERY_BUFFER_ATTEMPTS次后仍然不足时返回BUFFER_STILL_TOO_SMALL
+fn query_with_buffer<F>(mut query: F) -> Result<Vec<u8>, ServiceError>
+where
+    F: FnMut(&mut [u8], &mut u32) -> Result<(), WIN32_ERROR>,
+{
+    let mut buffer: Vec<u8> = Vec::new();
+    let mut bytes_needed: u32 = 0;
+    for _ in 0..QUERY_BUFFER_ATTEMPTS {
+        match query(&mut buffer, &mut bytes_needed) {
+            Ok(_) => return Ok(buffer),
+            Err(error) if error == ERROR_INSUFFICIENT_BUFFER && bytes_needed as usize > buffer.len() => {
+                buffer.resize(bytes_needed as usize, 0);
+            }
+            Err(error) => return Err(error.into()),
+        }
+    }
+    Err(ServiceError::BUFFER_STILL_TOO_SMALL)
+}
+
+/// 将以NUL结尾的宽字符串转换为String,空指针返回空字符串
+fn wide_to_string(s: PWSTR) -> String {
+    if s.is_null() {
+        String::new()
+    } else {
+        unsafe { String::from_utf16_lossy(s.as_wide()) }
+    }
+}
+
+/// 将以两个NUL结尾的宽字符串列表转换为Vec<String>,空指针返回空列表
+fn multi_sz_to_vec(s: PWSTR) -> Vec<String> {
+    let mut result = Vec::new();
+    if s.is_null() {
+        return result;
+    }
+    let mut p = s.0 as *const u16;
+    unsafe {
+        loop {
+            let item = PCWSTR(p);
+            let item = item.as_wide();
+            if item.is_empty() {
+                break;
+            }
+            result.push(String::from_utf16_lossy(item));
+            p = p.add(item.len() + 1);
+        }
+    }
+    result
+}
+
+/// # 校验服务名称
+/// 服务名称不能为空,不能包含'/'、'\\'或NUL字符,且最长256个UTF-16字符
+/// ## 参数
+/// ### input:
+/// - name: 服务名称
+/// ### output:
+/// - Result<(),ServiceError>: 名称无效时返回ERROR_INVALID_NAME
+/// ## 例子
+/// ```
+/// use windows_service_controller::validate_service_name;
+/// assert!(validate_service_name("Lers").is_ok());
+/// assert!(validate_service_name("Lers/1").is_err());
+/// ```
+pub fn validate_service_name(name: &str) -> Result<(), ServiceError> {
+    // NUL会在转换为PCWSTR时截断名称,实际打开的会是另一个服务
+    if name.is_empty() || name.contains(['/', '\\', '\0']) {
+        return Err(ServiceError::ERROR_INVALID_NAME);
+    }
+    // 长度限制针对的是UTF-16编码单元,超出BMP的字符会占用两个单元
+    if name.encode_utf16().count() > MAX_SERVICE_NAME_LEN {
+        return Err(ServiceError::ERROR_INVALID_NAME);
+    }
+    Ok(())
+}
+
+/// 校验显示名称:不能为空或包含NUL,长度不超过256个UTF-16编码单元,否则返回ERROR_INVALID_NAME
+/// 不需要显示名称与服务名称不同时应当不设置(None),SCM会使用服务名称
+pub(crate) fn validate_display_name(display_name: &str) -> Result<(), ServiceError> {
+    if display_name.is_empty()
+        || display_name.contains('\0')
+        || display_name.encode_utf16().count() > MAX_SERVICE_NAME_LEN
+    {
+        return Err(ServiceError::ERROR_INVALID_NAME);
+    }
+    Ok(())
+}
+
+/// # 经过校验的服务名称
+/// 只能通过try_new(或TryFrom)构造,构造时按validate_service_name校验,持有ServiceName即说明名称有效,
+/// 不会在调用Win32 API时才得到ERROR_INVALID_NAME。
+/// 可以解引用为&str,接受&str的接口(如WindowsService::open)可以直接传入&ServiceName。
+/// 比较区分大小写,SCM本身对服务名称不区分大小写
+/// ## 例子
+/// ```
+/// use windows_service_controller::{ServiceName, WindowsService};
+/// let name = ServiceName::try_new("Dnscache").unwrap();
+/// let service = WindowsService::open(&name, None, None);
+/// assert!(ServiceName::try_new("Lers/1").is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ServiceName(String);
+
+impl ServiceName {
+    /// # 校验并构造服务名称
+    /// ## 参数
+    /// ### input:
+    /// - name: 服务名称(不是显示名称)
+    /// ### output:
+    /// - Result<ServiceName,ServiceError>: 名称为空、包含'/'、'\\'或NUL、超过256个UTF-16字符时返回ERROR_INVALID_NAME
+    pub fn try_new(name: &str) -> Result<ServiceName, ServiceError> {
+        validate_service_name(name)?;
+        Ok(ServiceName(name.to_string()))
+    }
+
+    /// # 名称字符串
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// # 转换为String
+    pub fn into_string(self) -> String {
+        self.0
+    }
+}
+
+impl std::ops::Deref for ServiceName {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for ServiceName {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for ServiceName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl TryFrom<&str> for ServiceName {
+    type Error = ServiceError;
+
+    fn try_from(name: &str) -> Result<Self, Self::Error> {
+        ServiceName::try_new(name)
+    }
+}
+
+impl TryFrom<String> for ServiceName {
+    type Error = ServiceError;
+
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        validate_service_name(&name)?;
+        Ok(ServiceName(name))
+    }
+}
+
+impl From<ServiceName> for String {
+    fn from(name: ServiceName) -> Self {
+        name.0
+    }
+}
+
+/// # 接受本次启动的配置为"最近一次的正确配置"(Last Known Good)
+/// 调用NotifyBootConfigStatus通知SCM本次启动成功,SCM会把当前的控制集保存为最近一次的正确配置,
+/// 下次启动失败时可以回退到该配置。
+/// - 默认情况下Winlogon在第一个用户成功登录后自动接受,此时再调用会返回ERROR_BOOT_ALREADY_ACCEPTED
+/// - 只有在HKLM\SOFTWARE\Microsoft\Windows NT\CurrentVersion\Winlogon\ReportBootOk为"0"(关闭自动接受)时,
+///   才需要由引导验证程序(或安装引导关键驱动的程序)在确认系统正常后手动调用
+/// - 必须在本次启动期间、自动接受之前调用,每次启动只能接受一次;调用者需要是LocalSystem或管理员
+/// ## 参数
+/// ### output:
+/// - Result<(),ServiceError>
+/// ## 例子
+/// ```
+/// use windows_service_controller::accept_current_boot_config;
+/// use windows_service_controller::dword::ServiceError;
+/// match accept_current_boot_config() {
+///     Ok(_) => println!("accepted"),
+///     Err(e) if e == ServiceError::ERROR_BOOT_ALREADY_ACCEPTED => println!("already accepted"),
+///     Err(e) => println!("{}", e),
+/// }
+/// ```
+pub fn accept_current_boot_config() -> Result<(), ServiceError> {
+    match unsafe { NotifyBootConfigStatus(true) } {
+        Ok(_) => Ok(()),
+        Err(_) => Err(ServiceError::from_last_error()),
+    }
+}
+
+/// # 当前进程是否以管理员身份(提升的令牌)运行
+/// 通过GetTokenInformation(TokenElevation)查询当前进程的令牌。开启UAC时,管理员账户启动的普通进程也返回false;
+/// 创建、删除服务和修改大多数服务的配置都需要提升的令牌
+/// ## 参数
+/// ### output:
+/// - Result<bool,ServiceError>
+/// ## 例子
+/// ```
+/// use windows_service_controller::is_elevated;
+/// if !is_elevated().unwrap() {
+///     println!("请以管理员身份运行");
+/// }
+/// ```
+pub fn is_elevated() -> Result<bool, ServiceError> {
+    let mut token = HANDLE::default();
+    if unsafe { OpenProcessToken(GetCurrentProcess(), TOKEN_QUERY, &mut token) }.is_err() {
+        return Err(ServiceError::from_last_error());
+    }
+    let mut elevation = TOKEN_ELEVATION::default();
+    let mut returned: u32 = 0;
+    let result = unsafe {
+        GetTokenInformation(
+            token,
+            TokenElevation,
+            Some(&mut elevation as *mut TOKEN_ELEVATION as *mut c_void),
+            std::mem::size_of::<TOKEN_ELEVATION>() as u32,
+            &mut returned,
+        )
+    };
+    let error = result.is_err().then(ServiceError::from_last_error);
+    let _ = unsafe { CloseHandle(token) };
+    match error {
+        None => Ok(elevation.TokenIsElevated != 0),
+        Some(e) => Err(e),
+    }
+}
 
 /// windows服务类
 pub struct WindowsService {
-    sc_manager_handle: SC_HANDLE,
-    service_handle: SC_HANDLE,
-    pub config: ServiceConfig,
+    name: ServiceName,
+    /// 服务句柄,字段按声明顺序drop,先于SCM句柄关闭
+    service_handle: OwnedScHandle,
+    /// 由WindowsService打开并持有的SCM句柄;使用他人的SCM句柄(open_with_scm、ServiceManager)时为空句柄,不会被关闭
+    sc_manager_handle: OwnedScHandle,
+    /// 打开服务时请求的访问权限,句柄本身无法查询
+    access: ServiceAccess,
+    config: RawServiceConfig,
+    /// QueryServiceConfigW填充的缓冲区,config中的字符串指针指向这里,必须与config一起替换
+    _config_buffer: Vec<u8>,
 }
 
-type ServiceConfig = QUERY_SERVICE_CONFIGW;
+type RawServiceConfig = QUERY_SERVICE_CONFIGW;
 
-impl Drop for WindowsService {
-    fn drop(&mut self) {
-        unsafe {
-            CloseServiceHandle(self.service_handle).expect("关闭服务对象句柄失败");
-            CloseServiceHandle(self.sc_manager_handle).expect("关闭服务管理器句柄失败");
-        }
+/// 按服务名称比较是否为同一个服务,不区分大小写(与SCM一致)
+/// 比较的是身份而不是句柄、访问权限、当前状态或配置:分别打开的两个实例只要名称相同就相等,
+/// 可以用来对打开的服务去重
+impl PartialEq for WindowsService {
+    fn eq(&self, other: &WindowsService) -> bool {
+        self.name.to_lowercase() == other.name.to_lowercase()
     }
 }
 
+impl Eq for WindowsService {}
+
 impl WindowsService {
     /// # 通过服务名打开一个服务实例
     /// ## 参数
@@ -45,306 +396,2044 @@ impl WindowsService {
         service_access: Option<ServiceAccess>,
         sc_manager_access: Option<ScManagerAccess>,
     ) -> Result<WindowsService, ServiceError> {
-        let sc_manager_handle = Self::open_sc_manager(
+        let service_name = ServiceName::try_new(name)?;
+        let sc_manager_handle = OwnedScHandle::new(Self::open_sc_manager(
             sc_manager_access.unwrap_or_else(|| ScManagerAccess::SC_MANAGER_CONNECT),
-        )?;
-        let service_handle = Self::open_service(
-            sc_manager_handle,
-            name,
-            service_access.unwrap_or_else(|| ServiceAccess::SERVICE_ALL_ACCESS),
-        )?;
+        )?);
+        let access = service_access.unwrap_or_else(|| ServiceAccess::SERVICE_ALL_ACCESS);
+        let service_handle = OwnedScHandle::new(Self::open_service(sc_manager_handle.get(), name, access)?);
+        let (config, config_buffer) = Self::get_config(service_handle.get())?;
         Ok(WindowsService {
-            sc_manager_handle,
+            name: service_name,
             service_handle,
-            config: Self::get_config(service_handle)?,
+            sc_manager_handle,
+            access,
+            config,
+            _config_buffer: config_buffer,
         })
     }
 
-    /// # 请求当前服务状态
-    pub fn query_service_status(&self) -> Result<ServiceStatus, ServiceError> {
-        let mut status = SERVICE_STATUS::default();
-        let result = unsafe { QueryServiceStatus(self.service_handle, &mut status) };
-        if result.is_ok() {
-            Ok(status.dwCurrentState.into())
-        } else {
-            unsafe { Err(GetLastError().into()) }
-        }
-    }
-
-    /// # 新建一个服务
+    /// # 使用已打开的服务控制管理器打开服务
+    /// 与open相同,但不再为每个服务打开新的SCM句柄,适合需要依次打开多个服务的场景。
+    /// 返回的服务不持有SCM句柄,释放时不会关闭它;服务句柄独立于SCM句柄,manager释放后仍然可以使用
     /// ## 参数
     /// ### input:
-    /// - name: 服务名称(最长256字符,斜杠无效)
-    /// - display_name: 服务显示名称,不写与name一致
-    /// - sc_manager_access: SCM的访问权限,默认SC_MANAGER_ALL_ACCESS
-    /// - service_access: 对服务的访问权限,默认SERVICE_ALL_ACCESS
-    /// - service_type: 服务类型,常量在 service_type::
-    /// - service_start_type: 服务启动选项
-    /// - error_control: 错误控制
-    /// - binary_path: 需要启动的文件路径,路径可以包含启动的参数
-    /// - dependencies: 服务的依赖项
+    /// - scm: 已打开的服务控制管理器
+    /// - name: 服务名称(不是显示名称)
+    /// - service_access: 默认为SERVICE_ALL_ACCESS
     /// ### output:
     /// - Result<WindowsService,ServiceError>
     /// ## 例子
     /// ```
-    /// use windows_service_controller::dword::{ServiceError, ServiceStartType, ServiceType};
+    /// use windows_service_controller::dword::{ScManagerAccess, ServiceAccess};
+    /// use windows_service_controller::manager::ServiceManager;
     /// use windows_service_controller::WindowsService;
-    /// let service = WindowsService::new(
-    ///     "Lers",
-    ///     None,
-    ///     None,
-    ///     None,
-    ///     ServiceType::SERVICE_WIN32_OWN_PROCESS,
-    ///     ServiceStartType::SERVICE_DEMAND_START,
-    ///     ServiceError::SERVICE_ERROR_NORMAL,
-    ///     "D:\\ENGLISH\\Rust\\hot_update\\target\\debug\\hot_update.exe",
-    ///     None,
-    ///  );
-    ///
-
-    pub fn new(
+    /// let manager = ServiceManager::open(Some(ScManagerAccess::SC_MANAGER_CONNECT)).unwrap();
+    /// for name in ["WSearch", "Dnscache"] {
+    ///     let service = WindowsService::open_with_scm(&manager, name, Some(ServiceAccess::GENERIC_READ)).unwrap();
+    ///     println!("{}", service.config().display_name);
+    /// }
+    /// ```
+    pub fn open_with_scm(
+        scm: &ServiceManager,
         name: &str,
-        display_name: Option<&str>,
-        sc_manager_access: Option<ScManagerAccess>,
         service_access: Option<ServiceAccess>,
-        service_type: ServiceType,
-        service_start_type: ServiceStartType,
-        error_control: ServiceErrorControl,
-        binary_path: &str,
-        dependencies: Option<Vec<&str>>,
     ) -> Result<WindowsService, ServiceError> {
-        let sc_manager_handle = Self::open_sc_manager(
-            sc_manager_access.unwrap_or_else(|| ScManagerAccess::SC_MANAGER_ALL_ACCESS),
-        )?;
-        let display_name = display_name.unwrap_or_else(|| name);
-        let service_handle = unsafe {
-            CreateServiceW(
-                sc_manager_handle,
-                PCWSTR!(name),
-                PCWSTR!(display_name),
-                service_access.unwrap_or_else(|| ServiceAccess::SERVICE_ALL_ACCESS).into(),
-                service_type.into(),
-                service_start_type.into(),
-                error_control.into(),
-                PCWSTR!(binary_path),
-                PCWSTR::null(),
-                None,
-                match dependencies {
-                    None => PCWSTR::null(),
-                    Some(v) => {
-                        let mut result: Vec<u16> = Vec::new();
-                        for str in v {
-                            result.push(str.parse::<u16>().unwrap())
-                        }
-                        PCWSTR!(vec result)
-                    }
-                },
-                PCWSTR::null(),
-                PCWSTR::null(),
-            )
+        let service_name = ServiceName::try_new(name)?;
+        let access = service_access.unwrap_or(ServiceAccess::SERVICE_ALL_ACCESS);
+        let mut service = WindowsService {
+            name: service_name,
+            service_handle: OwnedScHandle::new(Self::open_service(scm.handle(), name, access)?),
+            sc_manager_handle: OwnedScHandle::default(),
+            access,
+            config: RawServiceConfig::default(),
+            _config_buffer: Vec::new(),
         };
-        match service_handle {
-            Ok(handle) => Ok(WindowsService {
-                sc_manager_handle,
-                service_handle: handle,
-                config: Self::get_config(handle)?,
-            }),
-            Err(_) => unsafe { Err(GetLastError().into()) },
-        }
+        (service.config, service._config_buffer) = Self::get_config(service.service_handle.get())?;
+        Ok(service)
     }
 
-    /// # 删除该服务
+    /// # 取出服务持有的SCM句柄,转换为ServiceManager
+    /// 句柄的所有权转移给返回的ServiceManager,由它负责关闭,服务本身不再持有SCM句柄(不会重复关闭)。
+    /// 服务句柄独立于SCM句柄,取出后服务的各项操作不受影响。
+    /// 可以配合open_with_scm打开同一台计算机上的其他服务,而不必再次打开SCM;
+    /// SCM句柄的访问权限与打开该服务时的sc_manager_access相同(默认SC_MANAGER_CONNECT)
     /// ## 参数
     /// ### output:
-    /// - Result<(),ServiceError>
-    pub fn delete_service(&self) -> Result<(), ServiceError> {
-        let result = unsafe { DeleteService(self.service_handle) };
-        if result.is_ok() {
-            Ok(())
-        } else {
-            unsafe { Err(GetLastError().into()) }
-        }
+    /// - Option<ServiceManager>: 服务没有持有SCM句柄(例如由open_with_scm打开或已经取出)时为None
+    /// ## 例子
+    /// ```
+    /// use windows_service_controller::dword::ServiceAccess;
+    /// use windows_service_controller::WindowsService;
+    /// let mut service = WindowsService::open("WSearch", Some(ServiceAccess::GENERIC_READ), None).unwrap();
+    /// let manager = service.take_manager().unwrap();
+    /// let sibling = WindowsService::open_with_scm(&manager, "Dnscache", Some(ServiceAccess::GENERIC_READ)).unwrap();
+    /// println!("{:?} {:?}", service.query_service_status(), sibling.query_service_status());
+    /// ```
+    pub fn take_manager(&mut self) -> Option<ServiceManager> {
+        let handle = std::mem::take(&mut self.sc_manager_handle);
+        (!handle.is_invalid()).then(|| ServiceManager::from_handle(handle.into_raw()))
     }
 
-    /// # 更新服务配置
+    /// # 以尽可能多的访问权限打开服务
+    /// 先以preferred打开服务,遇到ERROR_ACCESS_DENIED时依次降级为
+    /// GENERIC_READ|GENERIC_EXECUTE(读取与控制)、GENERIC_READ(只读)重试,只尝试比preferred小的权限。
+    /// 适合以普通用户运行、不需要全部权限也能工作的工具,实际得到的权限可以通过access()获取
     /// ## 参数
     /// ### input:
-    /// - passwd: 修改服务密码,不修改请传入None
+    /// - name: 服务名称(不是显示名称)
+    /// - preferred: 优先请求的访问权限,一般为SERVICE_ALL_ACCESS
+    /// - sc_manager_access: 默认为SC_MANAGER_CONNECT
     /// ### output:
-    /// - Result<(),ServiceError>
+    /// - Result<WindowsService,ServiceError>: 所有权限都被拒绝时返回ERROR_ACCESS_DENIED
     /// ## 例子
     /// ```
+    /// use windows_service_controller::dword::ServiceAccess;
     /// use windows_service_controller::WindowsService;
-    /// let mut service = WindowsService::open("Lers", None, None).unwrap();
-    /// use lers_windows_macro::PWSTR;
-    ///
-    /// service.config.lpDisplayName = PWSTR!("lers233");
-    /// service.update_service_config(None).unwrap()
-    ///```
-    /// ## BUG
-    /// 似乎无法修改lpServiceStartName字段
-    pub fn update_service_config(&self, passwd: Option<&str>) -> Result<(), ServiceError> {
-        match unsafe {
-            ChangeServiceConfigW(
-                self.service_handle,
-                self.config.dwServiceType,
-                self.config.dwStartType,
-                self.config.dwErrorControl,
-                PCWSTR(self.config.lpBinaryPathName.as_ptr()),
-                PCWSTR(self.config.lpLoadOrderGroup.as_ptr()),
-                None,
-                PCWSTR(self.config.lpDependencies.as_ptr()),
-                PCWSTR::null(),
-                match passwd {
-                    None => PCWSTR::null(),
-                    Some(s) => PCWSTR!(s),
-                },
-                PCWSTR(self.config.lpDisplayName.as_ptr()),
-            )
-        } {
-            Ok(_) => Ok(()),
-            Err(_) => unsafe { Err(GetLastError().into()) },
+    /// let service = WindowsService::try_open_with_fallback_access("WSearch", ServiceAccess::SERVICE_ALL_ACCESS, None).unwrap();
+    /// if service.access().contains(ServiceAccess::SERVICE_STOP) {
+    ///     service.stop_service().unwrap();
+    /// }
+    /// ```
+    pub fn try_open_with_fallback_access(
+        name: &str,
+        preferred: ServiceAccess,
+        sc_manager_access: Option<ScManagerAccess>,
+    ) -> Result<WindowsService, ServiceError> {
+        let service_name = ServiceName::try_new(name)?;
+        // 句柄为空时drop不会关闭,出错返回时由drop关闭已打开的句柄
+        let mut service = WindowsService {
+            name: service_name,
+            service_handle: OwnedScHandle::default(),
+            sc_manager_handle: OwnedScHandle::new(Self::open_sc_manager(
+                sc_manager_access.unwrap_or_else(|| ScManagerAccess::SC_MANAGER_CONNECT),
+            )?),
+            access: preferred,
+            config: RawServiceConfig::default(),
+            _config_buffer: Vec::new(),
+        };
+        let fallbacks = [
+            ServiceAccess::GENERIC_READ | ServiceAccess::GENERIC_EXECUTE,
+            ServiceAccess::GENERIC_READ,
+        ];
+        let candidates = std::iter::once(preferred)
+            .chain(fallbacks.into_iter().filter(|access| preferred.contains(*access) && !access.contains(preferred)));
+        for access in candidates {
+            match Self::open_service(service.sc_manager_handle.get(), name, access) {
+                Ok(service_handle) => {
+                    service.service_handle = OwnedScHandle::new(service_handle);
+                    service.access = access;
+                    (service.config, service._config_buffer) = Self::get_config(service_handle)?;
+                    return Ok(service);
+                }
+                Err(e) if e == ServiceError::ERROR_ACCESS_DENIED => continue,
+                Err(e) => return Err(e),
+            }
         }
+        Err(ServiceError::ERROR_ACCESS_DENIED)
     }
 
-    /// # 发送控制代码到服务
-    /// ## 参数：
+    /// # 只以DELETE权限打开服务,用于卸载
+    /// 卸载程序只需要删除服务,按最小权限原则只请求DELETE,调用者可以删除但没有完全控制权限时也能打开。
+    /// 该权限无法读取配置,打开时不查询配置,config()返回空的配置;除delete_service/delete_service_ex外,
+    /// 其他操作会返回INSUFFICIENT_ACCESS_*
+    /// ## 参数
     /// ### input:
-    /// - code: 控制代码
+    /// - name: 服务名称(不是显示名称)
     /// ### output:
-    /// - Result<(),ServiceError>
+    /// - Result<WindowsService,ServiceError>
     /// ## 例子
     /// ```
-    /// use windows_service_controller::dword::ServiceControlCode;
     /// use windows_service_controller::WindowsService;
-    /// let mut service = WindowsService::open("Lers", None, None).unwrap();
-    /// match service.control_service(ServiceControlCode::SERVICE_CONTROL_STOP){
-    ///     Ok(_) => {
-    ///         println!("succeed")
-    ///     }
-    ///     Err(e) => {
-    ///         println!("{}", e)
-    ///     }
-    /// }
+    /// let service = WindowsService::open_for_delete("Lers").unwrap();
+    /// service.delete_service().unwrap();
     /// ```
-    pub fn control_service(&self, code: ServiceControlCode) -> Result<(), ServiceError> {
-        let mut service_status = SERVICE_STATUS::default();
-        unsafe {
-            match ControlService(
-                self.service_handle,
-                code.into(),
-                &mut service_status,
-            )
-            {
-                Ok(_) => { Ok(()) }
-                Err(_) => { Err(GetLastError().into()) }
-            }
-        }
+    pub fn open_for_delete(name: &str) -> Result<WindowsService, ServiceError> {
+        let service_name = ServiceName::try_new(name)?;
+        // 句柄为空时drop不会关闭,出错返回时由drop关闭已打开的句柄
+        let mut service = WindowsService {
+            name: service_name,
+            service_handle: OwnedScHandle::default(),
+            sc_manager_handle: OwnedScHandle::new(Self::open_sc_manager(ScManagerAccess::SC_MANAGER_CONNECT)?),
+            access: ServiceAccess::DELETE,
+            config: RawServiceConfig::default(),
+            _config_buffer: Vec::new(),
+        };
+        service.service_handle =
+            OwnedScHandle::new(Self::open_service(service.sc_manager_handle.get(), name, ServiceAccess::DELETE)?);
+        Ok(service)
     }
 
-    /// # 开启服务
-    /// ## 参数：
+    /// # 获取打开服务时请求的访问权限
+    /// 句柄本身无法查询访问权限,这里返回的是open或new时传入的值
+    /// ## 参数
     /// ### output:
-    /// - Result<(),ServiceError>
+    /// - ServiceAccess
     /// ## 例子
     /// ```
+    /// use windows_service_controller::dword::ServiceAccess;
     /// use windows_service_controller::WindowsService;
-    /// let mut service = WindowsService::open("Lers", None, None).unwrap();
-    ///
-    /// match service.start_service(){
-    ///     Ok(_) => {
-    ///         println!("succeed")
-    ///     }
-    ///     Err(e) => {
-    ///         println!("{}", e)
-    ///     }
-    /// }
+    /// let service = WindowsService::open("WSearch", Some(ServiceAccess::GENERIC_READ), None).unwrap();
+    /// assert!(!service.access().contains(ServiceAccess::SERVICE_STOP));
     /// ```
-    pub fn start_service(&self) -> Result<(), ServiceError> {
-        unsafe {
-            match StartServiceW(
-                self.service_handle,
-                None,
-            ) {
-                Ok(_) => { Ok(()) }
-                Err(_) => Err(GetLastError().into()),
-            }
-        }
+    pub fn access(&self) -> ServiceAccess {
+        self.access
     }
 
-    /// # 停止服务
-    /// ## 参数:
+    /// # 服务名称
+    /// 打开或创建服务时使用的服务名称(不是显示名称),与传入时的大小写一致
+    /// ## 参数
     /// ### output:
-    /// - Result<(),ServiceError>
+    /// - &str
     /// ## 例子
     /// ```
+    /// use windows_service_controller::dword::ServiceAccess;
     /// use windows_service_controller::WindowsService;
-    /// let mut service = WindowsService::open("Lers", None, None).unwrap();
-    ///
-    /// match service.stop_service(){
-    ///     Ok(_) => {
-    ///         println!("succeed")
-    ///     }
-    ///     Err(e) => {
-    ///         println!("{}", e)
-    ///     }
-    /// }
+    /// let service = WindowsService::open("WSearch", Some(ServiceAccess::GENERIC_READ), None).unwrap();
+    /// assert_eq!(service.name(), "WSearch");
     /// ```
-    pub fn stop_service(&self) -> Result<(), ServiceError> {
-        self.control_service(ServiceControlCode::SERVICE_CONTROL_STOP)
-    }
-
-    fn open_service(
-        sc_manager_handle: SC_HANDLE,
-        name: &str,
-        access: ServiceAccess,
-    ) -> Result<SC_HANDLE, ServiceError> {
-        let service_handle = unsafe { OpenServiceW(sc_manager_handle, PCWSTR!(name), access.into()) };
-        match service_handle {
-            Ok(handle) => Ok(handle),
-            Err(_) => unsafe { Err(GetLastError().into()) },
-        }
+    pub fn name(&self) -> &str {
+        &self.name
     }
 
-    fn open_sc_manager(access: ScManagerAccess) -> Result<SC_HANDLE, ServiceError> {
-        let sc_manager_handle = unsafe { OpenSCManagerW(PCWSTR::null(), PCWSTR::null(), access.into()) };
-        match sc_manager_handle {
-            Ok(handle) => Ok(handle),
-            Err(_) => unsafe { Err(GetLastError().into()) },
-        }
+    /// # 经过校验的服务名称
+    /// 与name相同,返回ServiceName,可以直接传给其他需要服务名称的接口
+    pub fn service_name(&self) -> &ServiceName {
+        &self.name
     }
 
-    fn get_config(service_handle: SC_HANDLE) -> Result<ServiceConfig, ServiceError> {
-        let mut config = ServiceConfig::default();
-        let mut cap: u32 = Default::default();
-        match unsafe { QueryServiceConfigW(service_handle, Some(&mut config), 370, &mut cap) } {
-            Ok(_) => Ok(config),
-            Err(_) => {
-                match unsafe {
-                    QueryServiceConfigW(service_handle, Some(&mut config), cap, &mut cap)
-                } {
-                    Ok(_) => Ok(config),
-                    Err(_) => unsafe { Err(GetLastError().into()) },
+    /// # 获取服务配置
+    /// 返回打开服务时(或最近一次修改配置后)读取的配置
+    /// ## 参数
+    /// ### output:
+    /// - ServiceConfig
+    /// ## 例子
+    /// ```
+    /// use windows_service_controller::dword::ServiceAccess;
+    /// use windows_service_controller::WindowsService;
+    /// let service = WindowsService::open("WSearch", Some(ServiceAccess::GENERIC_READ), None).unwrap();
+    /// println!("{}", service.config().binary_path);
+    /// ```
+    pub fn config(&self) -> ServiceConfig {
+        ServiceConfig::from_raw(&self.config)
+    }
+
+    /// # 枚举依赖于该服务的服务
+    /// 停止服务前可以用来评估影响范围。依赖关系有两种:
+    /// - 直接依赖: 其他服务的依赖项中写出了该服务的名称。通过EnumDependentServicesW获取,
+    ///   包括间接依赖的服务,按启动顺序的逆序排列(即可以依次停止的顺序)
+    /// - 服务组依赖: 其他服务的依赖项中写出了该服务所在的服务组('+'加组名)。
+    ///   SCM启动这类服务前只要求组内至少有一个服务已经启动,EnumDependentServicesW不会返回它们
+    ///
+    /// include_groups为true且服务属于某个服务组时,还会逐个查询所有服务和驱动的配置,
+    /// 把依赖该服务组的服务以及依赖它们的服务追加在直接依赖之后(已经出现过的服务不会重复),
+    /// 耗时与服务数量成正比;无法查询配置的服务会被跳过
+    ///
+    /// 需要SERVICE_ENUMERATE_DEPENDENTS权限
+    /// ## 参数
+    /// ### input:
+    /// - include_groups: 是否包括服务组依赖
+    /// ### output:
+    /// - Result<Vec<ServiceInfo>,ServiceError>: 其中的status没有进程ID和服务标志,始终为0
+    /// ## 例子
+    /// ```
+    /// use windows_service_controller::dword::ServiceAccess;
+    /// use windows_service_controller::WindowsService;
+    /// let service = WindowsService::open("RpcSs", Some(ServiceAccess::GENERIC_READ), None).unwrap();
+    /// for info in service.enumerate_dependents(true).unwrap() {
+    ///     println!("{}\t{}", info.service_name, info.status.current_state());
+    /// }
+    /// ```
+    pub fn enumerate_dependents(&self, include_groups: bool) -> Result<Vec<ServiceInfo>, ServiceError> {
+        let service_handle = self.handle_for(ServiceAccess::SERVICE_ENUMERATE_DEPENDENTS)?;
+        let mut dependents = enumerate::enum_dependent_services(service_handle)?;
+        let group = wide_to_string(self.config.lpLoadOrderGroup);
+        if include_groups && !group.is_empty() {
+            let sc_manager_handle = OwnedScHandle::new(Self::open_sc_manager(ScManagerAccess::ENUMERATE)?);
+            let group_dependents = enumerate::group_dependents(sc_manager_handle.get(), &group);
+            let mut seen: HashSet<String> = dependents.iter().map(|info| info.service_name.to_lowercase()).collect();
+            seen.insert(self.name.to_lowercase());
+            dependents.extend(group_dependents?.into_iter().filter(|info| seen.insert(info.service_name.to_lowercase())));
+        }
+        Ok(dependents)
+    }
+
+    /// # 服务是否运行在共享进程中
+    /// 根据配置中的服务类型是否包含SERVICE_WIN32_SHARE_PROCESS判断(包括每用户服务的共享进程类型)
+    /// ## 参数
+    /// ### output:
+    /// - bool
+    /// ## 例子
+    /// ```
+    /// use windows_service_controller::dword::ServiceAccess;
+    /// use windows_service_controller::WindowsService;
+    /// let service = WindowsService::open("Dnscache", Some(ServiceAccess::GENERIC_READ), None).unwrap();
+    /// println!("{}", service.is_shared_process());
+    /// ```
+    pub fn is_shared_process(&self) -> bool {
+        self.config.dwServiceType.0 & Services::SERVICE_WIN32_SHARE_PROCESS.0 != 0
+    }
+
+    /// # 承载服务的svchost组名称
+    /// 可执行文件为svchost.exe时返回`-k`参数指定的组(如netsvcs),同一组的服务运行在同一个svchost进程中。
+    /// 可执行文件不是svchost.exe或无法确定组时返回None
+    /// ## 参数
+    /// ### output:
+    /// - Option<String>
+    /// ## 例子
+    /// ```
+    /// use windows_service_controller::dword::ServiceAccess;
+    /// use windows_service_controller::WindowsService;
+    /// let service = WindowsService::open("Dnscache", Some(ServiceAccess::GENERIC_READ), None).unwrap();
+    /// println!("{:?}", service.host_process_name());
+    /// ```
+    pub fn host_process_name(&self) -> Option<String> {
+        path::svchost_group(&wide_to_string(self.config.lpBinaryPathName))
+    }
+
+    /// # 请求当前服务状态
+    pub fn query_service_status(&self) -> Result<ServiceStatus, ServiceError> {
+        let mut status = SERVICE_STATUS::default();
+        let result = unsafe { QueryServiceStatus(self.handle_for(ServiceAccess::SERVICE_QUERY_STATUS)?, &mut status) };
+        if result.is_ok() {
+            Ok(status.dwCurrentState.into())
+        } else {
+            Err(ServiceError::from_last_error())
+        }
+    }
+
+    /// # 请求当前服务的扩展状态
+    /// 包含进程ID、接受的控制代码、服务进程标志等信息
+    /// ## 例子
+    /// ```
+    /// use windows_service_controller::dword::{ServiceAccess, ServiceFlags};
+    /// use windows_service_controller::WindowsService;
+    /// // open会查询配置,因此还需要SERVICE_QUERY_CONFIG权限
+    /// let service = WindowsService::open("WSearch", Some(ServiceAccess::GENERIC_READ), None).unwrap();
+    /// let status = service.query_status_ex().unwrap();
+    /// assert!(!status.service_flags().contains(ServiceFlags::SERVICE_RUNS_IN_SYSTEM_PROCESS));
+    /// ```
+    pub fn query_status_ex(&self) -> Result<FullStatus, ServiceError> {
+        Self::get_status_ex(self.handle_for(ServiceAccess::SERVICE_QUERY_STATUS)?)
+    }
+
+    /// # 同时查询服务的最新配置和扩展状态
+    /// 两次查询都使用已经打开的服务句柄,不会重新打开SCM或服务。
+    /// config()返回的是打开服务时读取的配置,需要最新配置和状态(例如显示服务详情)时使用该方法
+    /// ## 参数
+    /// ### output:
+    /// - Result<(ServiceConfig,FullStatus),ServiceError>: 需要SERVICE_QUERY_CONFIG和SERVICE_QUERY_STATUS权限
+    /// ## 例子
+    /// ```
+    /// use windows_service_controller::dword::ServiceAccess;
+    /// use windows_service_controller::WindowsService;
+    /// let service = WindowsService::open("WSearch", Some(ServiceAccess::GENERIC_READ), None).unwrap();
+    /// let (config, status) = service.query_config_and_status().unwrap();
+    /// println!("{} {}", config.binary_path, status.current_state());
+    /// ```
+    pub fn query_config_and_status(&self) -> Result<(ServiceConfig, FullStatus), ServiceError> {
+        // 分别检查两个权限,缺少时返回对应的INSUFFICIENT_ACCESS_*
+        self.handle_for(ServiceAccess::SERVICE_QUERY_CONFIG)?;
+        let service_handle = self.handle_for(ServiceAccess::SERVICE_QUERY_STATUS)?;
+        let (config, _buffer) = Self::get_config(service_handle)?;
+        Ok((ServiceConfig::from_raw(&config), Self::get_status_ex(service_handle)?))
+    }
+
+    /// # 监听服务状态变化
+    /// 在后台线程中轮询服务状态,开始监听时以及每次状态变化时调用on_change,
+    /// 直到返回的WatchHandle被stop或drop
+    /// ## 参数
+    /// ### input:
+    /// - on_change: 状态变化时的回调
+    /// ### output:
+    /// - Result<WatchHandle,ServiceError>
+    /// ## 例子
+    /// ```
+    /// use windows_service_controller::WindowsService;
+    /// let service = WindowsService::open("WSearch", None, None).unwrap();
+    /// let handle = service.watch(|status| println!("{}", status.current_state())).unwrap();
+    /// std::thread::sleep(std::time::Duration::from_secs(10));
+    /// handle.stop();
+    /// ```
+    pub fn watch<F>(&self, on_change: F) -> Result<WatchHandle, ServiceError>
+    where
+        F: FnMut(FullStatus) + Send + 'static,
+    {
+        Ok(WatchHandle::spawn(self.open_watch_handles()?, on_change))
+    }
+
+    /// # 以通道的形式监听服务状态变化
+    /// 在后台线程中每隔poll_interval轮询一次服务状态,开始监听时以及每次状态变化时发送到通道。
+    /// 这是NotifyServiceStatusChangeW回调方式的简单替代,适合在循环中消费状态(如刷新界面)。
+    /// 接收端被drop、服务被删除时轮询线程退出,通道随之关闭
+    /// ## 参数
+    /// ### input:
+    /// - poll_interval: 轮询间隔
+    /// ### output:
+    /// - Result<StatusReceiver,ServiceError>
+    /// ## 例子
+    /// ```
+    /// use std::time::Duration;
+    /// use windows_service_controller::WindowsService;
+    /// let service = WindowsService::open("WSearch", None, None).unwrap();
+    /// let receiver = service.watch_channel(Duration::from_millis(500)).unwrap();
+    /// for status in receiver.iter().take(3) {
+    ///     println!("{}", status.current_state());
+    /// }
+    /// ```
+    pub fn watch_channel(&self, poll_interval: Duration) -> Result<StatusReceiver, ServiceError> {
+        Ok(StatusReceiver::spawn(self.open_watch_handles()?, poll_interval))
+    }
+
+    /// 为监听线程打开独立的句柄,不受当前实例生命周期的影响
+    fn open_watch_handles(&self) -> Result<WatchHandles, ServiceError> {
+        self.handle()?;
+        let sc_manager_handle = OwnedScHandle::new(Self::open_sc_manager(ScManagerAccess::SC_MANAGER_CONNECT)?);
+        let service_handle = Self::open_service(sc_manager_handle.get(), &self.name, ServiceAccess::SERVICE_QUERY_STATUS)?;
+        Ok(WatchHandles {
+            service_handle: OwnedScHandle::new(service_handle),
+            _sc_manager_handle: sc_manager_handle,
+        })
+    }
+
+    /// # 新建一个服务
+    /// 参数较多时推荐使用[ServiceBuilder]。
+    /// 创建后查询配置失败时会删除刚创建的服务,删除失败时返回SERVICE_CREATED_NOT_QUERIED。
+    /// 可执行文件不存在时返回ERROR_PATH_NOT_FOUND,需要先注册服务再部署文件时请使用ServiceBuilder::allow_missing_binary
+    /// ## 参数
+    /// ### input:
+    /// - name: 服务名称(最长256个UTF-16字符,斜杠无效)
+    /// - display_name: 服务显示名称。None时与name一致(SCM总会为服务保存显示名称,无法创建没有显示名称的服务);
+    ///   Some("")、包含NUL或超过256个UTF-16字符时返回ERROR_INVALID_NAME,不会把空字符串当作None处理
+    /// - sc_manager_access: SCM的访问权限,默认SC_MANAGER_CREATE_SERVICE
+    /// - service_access: 对服务的访问权限,默认SERVICE_ALL_ACCESS
+    /// - service_type: 服务类型,常量在 service_type::
+    /// - service_start_type: 服务启动选项
+    /// - error_control: 错误控制
+    /// - binary_path: 需要启动的文件路径,路径可以包含启动的参数
+    /// - dependencies: 服务的依赖项
+    /// ### output:
+    /// - Result<WindowsService,ServiceError>
+    /// ## 例子
+    /// ```
+    /// use windows_service_controller::dword::{ServiceErrorControl, ServiceStartType, ServiceType};
+    /// use windows_service_controller::WindowsService;
+    /// let service = WindowsService::new(
+    ///     "Lers",
+    ///     None,
+    ///     None,
+    ///     None,
+    ///     ServiceType::SERVICE_WIN32_OWN_PROCESS,
+    ///     ServiceStartType::SERVICE_DEMAND_START,
+    ///     ServiceErrorControl::SERVICE_ERROR_NORMAL,
+    ///     "D:\\ENGLISH\\Rust\\hot_update\\target\\debug\\hot_update.exe",
+    ///     None,
+    /// );
+    /// ```
+    pub fn new(
+        name: &str,
+        display_name: Option<&str>,
+        sc_manager_access: Option<ScManagerAccess>,
+        service_access: Option<ServiceAccess>,
+        service_type: ServiceType,
+        service_start_type: ServiceStartType,
+        error_control: ServiceErrorControl,
+        binary_path: &str,
+        dependencies: Option<Vec<&str>>,
+    ) -> Result<WindowsService, ServiceError> {
+        let mut builder = ServiceBuilder::new(name, binary_path)
+            .service_type(service_type)
+            .start_type(service_start_type)
+            .error_control(error_control);
+        if let Some(display_name) = display_name {
+            builder = builder.display_name(display_name);
+        }
+        if let Some(access) = sc_manager_access {
+            builder = builder.sc_manager_access(access);
+        }
+        if let Some(access) = service_access {
+            builder = builder.service_access(access);
+        }
+        if let Some(dependencies) = dependencies {
+            builder = builder.dependencies(&dependencies);
+        }
+        builder.create()
+    }
+
+    /// # 按信息级别查询扩展配置的原始数据
+    /// 用于本库还没有提供类型化接口的信息级别,返回QueryServiceConfig2W写入的缓冲区。
+    /// 缓冲区以对应的结构体(如SERVICE_DESCRIPTIONW)开头,其中的指针指向缓冲区内部
+    /// 当前系统不支持该信息级别时返回ERROR_INVALID_LEVEL(见ServiceError::is_unsupported_level),
+    /// 基于它的类型化接口(failure_actions、launch_protected等)同样如此
+    /// ## 参数
+    /// ### input:
+    /// - level: 信息级别
+    /// ### output:
+    /// - Result<Vec<u8>,ServiceError>
+    /// ## 例子
+    /// ```
+    /// use windows_service_controller::dword::ServiceConfigInfoLevel;
+    /// use windows_service_controller::WindowsService;
+    /// let service = WindowsService::open("WSearch", None, None).unwrap();
+    /// let data = service.query_config2_raw(ServiceConfigInfoLevel::SERVICE_CONFIG_PRESHUTDOWN_INFO).unwrap();
+    /// println!("{:?}", data);
+    /// ```
+    pub fn query_config2_raw(&self, level: ServiceConfigInfoLevel) -> Result<Vec<u8>, ServiceError> {
+        let service_handle = self.handle_for(ServiceAccess::SERVICE_QUERY_CONFIG)?;
+        query_with_buffer(|buffer, bytes_needed| {
+            unsafe { QueryServiceConfig2W(service_handle, level.into(), Some(buffer), bytes_needed) }
+                .map_err(|_| unsafe { GetLastError() })
+        })
+    }
+
+    /// # 按信息级别修改扩展配置
+    /// 用于本库还没有提供类型化接口的信息级别,data直接作为lpInfo传给ChangeServiceConfig2W。
+    /// 有类型化接口的信息级别请优先使用类型化接口
+    /// ## 参数
+    /// ### input:
+    /// - level: 信息级别
+    /// - data: 信息级别对应的结构体(如SERVICE_PRESHUTDOWN_INFO)的字节
+    /// ### output:
+    /// - Result<(),ServiceError>
+    /// ## Safety
+    /// - data必须是level对应结构体的完整内存布局,并按该结构体的要求对齐
+    /// - 结构体中的指针(如字符串)必须在调用期间有效,并指向符合Win32要求的数据
+    /// ## 例子
+    /// ```
+    /// use windows_service_controller::dword::ServiceConfigInfoLevel;
+    /// use windows_service_controller::WindowsService;
+    /// let service = WindowsService::open("Lers", None, None).unwrap();
+    /// // SERVICE_PRESHUTDOWN_INFO { dwPreshutdownTimeout: u32 }
+    /// let timeout: u32 = 10_000;
+    /// unsafe {
+    ///     service
+    ///         .change_config2_raw(ServiceConfigInfoLevel::SERVICE_CONFIG_PRESHUTDOWN_INFO, &timeout.to_ne_bytes())
+    ///         .unwrap();
+    /// }
+    /// ```
+    pub unsafe fn change_config2_raw(&self, level: ServiceConfigInfoLevel, data: &[u8]) -> Result<(), ServiceError> {
+        let service_handle = self.handle_for(ServiceAccess::SERVICE_CHANGE_CONFIG)?;
+        match ChangeServiceConfig2W(service_handle, level.into(), Some(data.as_ptr() as *const c_void)) {
+            Ok(_) => Ok(()),
+            Err(_) => Err(ServiceError::from_last_error()),
+        }
+    }
+
+    /// # 查询服务的失败恢复策略
+    /// ## 参数
+    /// ### output:
+    /// - Result<FailureActions,ServiceError>
+    /// ## 例子
+    /// ```
+    /// use windows_service_controller::WindowsService;
+    /// let service = WindowsService::open("WSearch", None, None).unwrap();
+    /// println!("{:?}", service.failure_actions().unwrap());
+    /// ```
+    pub fn failure_actions(&self) -> Result<FailureActions, ServiceError> {
+        let buffer = self.query_config2_raw(ServiceConfigInfoLevel::SERVICE_CONFIG_FAILURE_ACTIONS)?;
+        if buffer.len() < std::mem::size_of::<SERVICE_FAILURE_ACTIONSW>() {
+            return Ok(FailureActions::default());
+        }
+        // 结构体中的指针指向buffer,buffer在复制完成前一直存活
+        unsafe {
+            let raw = std::ptr::read_unaligned(buffer.as_ptr() as *const SERVICE_FAILURE_ACTIONSW);
+            Ok(FailureActions::from_raw(&raw))
+        }
+    }
+
+    /// # 查询服务实际的启动方式
+    /// 由触发器启动的服务在配置中显示为按需启动,这里同时读取触发器配置(SERVICE_CONFIG_TRIGGER_INFO):
+    /// 按需启动且至少有一个触发器时返回StartBehavior::Triggered。
+    /// 只有按需启动的服务需要查询触发器,需要SERVICE_QUERY_CONFIG权限;系统不支持触发器时按没有触发器处理
+    /// ## 参数
+    /// ### output:
+    /// - Result<StartBehavior,ServiceError>
+    /// ## 例子
+    /// ```
+    /// use windows_service_controller::dword::ServiceAccess;
+    /// use windows_service_controller::WindowsService;
+    /// let service = WindowsService::open("W32Time", Some(ServiceAccess::GENERIC_READ), None).unwrap();
+    /// println!("{:?}", service.effective_start_behavior().unwrap());
+    /// ```
+    pub fn effective_start_behavior(&self) -> Result<StartBehavior, ServiceError> {
+        let start_type: ServiceStartType = self.config.dwStartType.into();
+        if start_type != ServiceStartType::SERVICE_DEMAND_START {
+            return Ok(StartBehavior::from_start_type(start_type, 0));
+        }
+        // 不支持触发器的系统上服务不会由触发器启动
+        let buffer = match self.query_config2_raw(ServiceConfigInfoLevel::SERVICE_CONFIG_TRIGGER_INFO) {
+            Err(e) if e.is_unsupported_level() => Vec::new(),
+            result => result?,
+        };
+        let trigger_count = if buffer.len() < std::mem::size_of::<SERVICE_TRIGGER_INFO>() {
+            0
+        } else {
+            // 只读取cTriggers,不访问pTriggers指向的数据
+            unsafe { std::ptr::read_unaligned(buffer.as_ptr() as *const SERVICE_TRIGGER_INFO) }.cTriggers
+        };
+        Ok(StartBehavior::from_start_type(start_type, trigger_count))
+    }
+
+    /// # 查询服务的启动保护级别
+    /// 读取QueryServiceConfig2W(SERVICE_CONFIG_LAUNCH_PROTECTED),需要SERVICE_QUERY_CONFIG权限
+    /// ## 参数
+    /// ### output:
+    /// - Result<LaunchProtected,ServiceError>
+    /// ## 例子
+    /// ```
+    /// use windows_service_controller::dword::ServiceAccess;
+    /// use windows_service_controller::WindowsService;
+    /// let service = WindowsService::open("WinDefend", Some(ServiceAccess::GENERIC_READ), None).unwrap();
+    /// println!("{:?}", service.launch_protected().unwrap());
+    /// ```
+    pub fn launch_protected(&self) -> Result<LaunchProtected, ServiceError> {
+        let buffer = self.query_config2_raw(ServiceConfigInfoLevel::SERVICE_CONFIG_LAUNCH_PROTECTED)?;
+        if buffer.len() < std::mem::size_of::<SERVICE_LAUNCH_PROTECTED_INFO>() {
+            return Ok(LaunchProtected::SERVICE_LAUNCH_PROTECTED_NONE);
+        }
+        let info = unsafe { std::ptr::read_unaligned(buffer.as_ptr() as *const SERVICE_LAUNCH_PROTECTED_INFO) };
+        Ok(LaunchProtected::from(info.dwLaunchProtected))
+    }
+
+    /// # 服务是否以受保护进程运行
+    /// 启动保护级别不是SERVICE_LAUNCH_PROTECTED_NONE时返回true,详细级别见launch_protected
+    /// ## 参数
+    /// ### output:
+    /// - Result<bool,ServiceError>
+    /// ## 例子
+    /// ```
+    /// use windows_service_controller::dword::ServiceAccess;
+    /// use windows_service_controller::WindowsService;
+    /// let service = WindowsService::open("WinDefend", Some(ServiceAccess::GENERIC_READ), None).unwrap();
+    /// println!("{}", service.is_protected().unwrap());
+    /// ```
+    pub fn is_protected(&self) -> Result<bool, ServiceError> {
+        Ok(self.launch_protected()? != LaunchProtected::SERVICE_LAUNCH_PROTECTED_NONE)
+    }
+
+    /// # 服务是否是关键的系统服务
+    /// 在允许用户停止或删除服务前用于给出警告,满足任意一项时返回true:
+    /// - ServiceConfig::is_boot_critical: 错误控制为SERVICE_ERROR_CRITICAL,或是BOOT_START/SYSTEM_START的驱动
+    /// - 以受保护进程运行(launch_protected不为NONE): 反恶意软件等服务,一般无法停止,系统也依赖它们
+    /// - 失败恢复策略包含Reboot: 系统为RpcSs、DcomLaunch等服务配置了终止后重新启动计算机,停止它们会导致重启
+    ///
+    /// 这是根据配置的推断,不能覆盖所有情况:例如进程被标记为关键进程(终止时蓝屏)无法通过服务配置得知。
+    /// 系统不支持启动保护(ERROR_INVALID_LEVEL)时该项视为不满足。需要SERVICE_QUERY_CONFIG权限
+    /// ## 参数
+    /// ### output:
+    /// - Result<bool,ServiceError>
+    /// ## 例子
+    /// ```
+    /// use windows_service_controller::dword::ServiceAccess;
+    /// use windows_service_controller::WindowsService;
+    /// let service = WindowsService::open("RpcSs", Some(ServiceAccess::GENERIC_READ), None).unwrap();
+    /// if service.is_critical().unwrap() {
+    ///     println!("停止该服务可能导致系统重启或无法启动");
+    /// }
+    /// ```
+    pub fn is_critical(&self) -> Result<bool, ServiceError> {
+        self.handle_for(ServiceAccess::SERVICE_QUERY_CONFIG)?;
+        if self.config().is_boot_critical() {
+            return Ok(true);
+        }
+        match self.is_protected() {
+            Ok(true) => return Ok(true),
+            Ok(false) => {}
+            Err(e) if e.is_unsupported_level() => {}
+            Err(e) => return Err(e),
+        }
+        Ok(self.failure_actions()?.has_reboot())
+    }
+
+    /// # 设置服务失败后自动重新启动
+    /// 按FailureActions::auto_restart构造恢复策略后调用set_failure_actions,需要完全控制时直接使用set_failure_actions。
+    /// 需要SERVICE_CHANGE_CONFIG和SERVICE_START权限
+    /// ## 参数
+    /// ### input:
+    /// - delay: 每次重新启动前等待的时间
+    /// - reset_period: 没有失败多长时间后重置失败计数,精确到秒
+    /// - max_restarts: 最多重新启动的次数,None表示一直重新启动;不能达到failure::MAX_FAILURE_ACTIONS,否则返回ERROR_INVALID_PARAMETER
+    /// ### output:
+    /// - Result<(),ServiceError>
+    /// ## 例子
+    /// ```
+    /// use std::time::Duration;
+    /// use windows_service_controller::WindowsService;
+    /// let service = WindowsService::open("Lers", None, None).unwrap();
+    /// service.set_auto_restart(Duration::from_secs(10), Duration::from_secs(86400), Some(3)).unwrap();
+    /// ```
+    pub fn set_auto_restart(&self, delay: Duration, reset_period: Duration, max_restarts: Option<u32>) -> Result<(), ServiceError> {
+        self.set_failure_actions(&FailureActions::auto_restart(delay, reset_period, max_restarts)?)
+    }
+
+    /// # 读取服务最近的失败记录
+    /// 从System事件日志中按时间从新到旧查找SCM记录的该服务的失败事件(7023、7024、7031、7034),
+    /// 用于判断服务是否在反复崩溃。需要启用eventlog特性;读取System日志一般不需要管理员权限
+    /// ## 参数
+    /// ### input:
+    /// - count: 最多返回的记录数
+    /// ### output:
+    /// - Result<Vec<ServiceFailure>,ServiceError>
+    /// ## 例子
+    /// ```
+    /// use windows_service_controller::dword::ServiceAccess;
+    /// use windows_service_controller::WindowsService;
+    /// let service = WindowsService::open("WSearch", Some(ServiceAccess::GENERIC_READ), None).unwrap();
+    /// for failure in service.recent_failures(10).unwrap() {
+    ///     println!("{:?} {} {:?}", failure.time, failure.event_id, failure.recovery_action);
+    /// }
+    /// ```
+    #[cfg(feature = "eventlog")]
+    pub fn recent_failures(&self, count: usize) -> Result<Vec<eventlog::ServiceFailure>, ServiceError> {
+        eventlog::recent_failures(&self.name, &wide_to_string(self.config.lpDisplayName), count)
+    }
+
+    /// # 设置服务的失败恢复策略
+    /// 设置前通过FailureActions::validate检查。包含Restart操作时需要SERVICE_START权限,
+    /// 包含Reboot操作时调用者需要SE_SHUTDOWN_NAME特权
+    /// ## 参数
+    /// ### input:
+    /// - actions: 恢复策略,actions为空时删除恢复策略
+    /// ### output:
+    /// - Result<(),ServiceError>
+    /// ## 例子
+    /// ```
+    /// use std::time::Duration;
+    /// use windows_service_controller::failure::{FailureAction, FailureActions};
+    /// use windows_service_controller::WindowsService;
+    /// let service = WindowsService::open("Lers", None, None).unwrap();
+    /// let actions = FailureActions {
+    ///     reset_period: Some(Duration::from_secs(86400)),
+    ///     actions: vec![
+    ///         FailureAction::Restart { delay: Duration::from_secs(30) },
+    ///         FailureAction::Restart { delay: Duration::from_secs(120) },
+    ///     ],
+    ///     ..Default::default()
+    /// };
+    /// service.set_failure_actions(&actions).unwrap();
+    /// ```
+    pub fn set_failure_actions(&self, actions: &FailureActions) -> Result<(), ServiceError> {
+        actions.validate()?;
+        if actions.has_restart() {
+            self.handle_for(ServiceAccess::SERVICE_START)?;
+        }
+        let mut raw_actions = actions.raw_actions();
+        let mut reboot_message: Vec<u16> = actions.reboot_message.encode_utf16().chain(Some(0)).collect();
+        let mut command: Vec<u16> = actions.command.encode_utf16().chain(Some(0)).collect();
+        let info = SERVICE_FAILURE_ACTIONSW {
+            dwResetPeriod: actions.raw_reset_period(),
+            // 空字符串表示删除原有的消息和命令
+            lpRebootMsg: PWSTR(reboot_message.as_mut_ptr()),
+            lpCommand: PWSTR(command.as_mut_ptr()),
+            cActions: raw_actions.len() as u32,
+            // lpsaActions为NULL时Windows忽略cActions和dwResetPeriod,不会删除恢复策略;
+            // 空列表也传入非空指针(cActions为0,不会被读取)
+            lpsaActions: raw_actions.as_mut_ptr(),
+        };
+        unsafe {
+            let data = std::slice::from_raw_parts(
+                &info as *const SERVICE_FAILURE_ACTIONSW as *const u8,
+                std::mem::size_of::<SERVICE_FAILURE_ACTIONSW>(),
+            );
+            self.change_config2_raw(ServiceConfigInfoLevel::SERVICE_CONFIG_FAILURE_ACTIONS, data)
+        }
+    }
+
+    /// # 查询失败恢复策略中的重启消息
+    /// ## 参数
+    /// ### output:
+    /// - Result<Option<String>,ServiceError>: 没有设置消息时为None
+    /// ## 例子
+    /// ```
+    /// use windows_service_controller::WindowsService;
+    /// let service = WindowsService::open("WSearch", None, None).unwrap();
+    /// println!("{:?}", service.failure_reboot_message().unwrap());
+    /// ```
+    pub fn failure_reboot_message(&self) -> Result<Option<String>, ServiceError> {
+        let message = self.failure_actions()?.reboot_message;
+        Ok(if message.is_empty() { None } else { Some(message) })
+    }
+
+    /// # 设置或清除失败恢复策略中的重启消息
+    /// 只修改消息,不改变恢复操作、重置周期和命令行
+    /// ## 参数
+    /// ### input:
+    /// - msg: 重启前广播的消息,None清除原有的消息
+    /// ### output:
+    /// - Result<(),ServiceError>
+    /// ## 例子
+    /// ```
+    /// use windows_service_controller::WindowsService;
+    /// let service = WindowsService::open("Lers", None, None).unwrap();
+    /// service.set_failure_reboot_message(Some("Lers服务失败,计算机即将重启")).unwrap();
+    /// service.set_failure_reboot_message(None).unwrap();
+    /// ```
+    pub fn set_failure_reboot_message(&self, msg: Option<&str>) -> Result<(), ServiceError> {
+        self.change_failure_strings(Some(msg.unwrap_or("")), None)
+    }
+
+    /// # 查询失败恢复策略中RunCommand操作执行的命令行
+    /// 读取QueryServiceConfig2W(SERVICE_CONFIG_FAILURE_ACTIONS)结果中的lpCommand,只需要SERVICE_QUERY_CONFIG权限,
+    /// 以只读权限打开的服务也可以查询,适合审计服务失败时会执行的命令
+    /// ## 参数
+    /// ### output:
+    /// - Result<Option<String>,ServiceError>: 没有设置命令行时为None
+    /// ## 例子
+    /// ```
+    /// use windows_service_controller::WindowsService;
+    /// let service = WindowsService::open("WSearch", None, None).unwrap();
+    /// println!("{:?}", service.failure_command().unwrap());
+    /// ```
+    pub fn failure_command(&self) -> Result<Option<String>, ServiceError> {
+        let command = self.failure_actions()?.command;
+        Ok(if command.is_empty() { None } else { Some(command) })
+    }
+
+    /// # 设置或清除失败恢复策略中RunCommand操作执行的命令行
+    /// 只修改命令行,不改变恢复操作、重置周期和重启消息
+    /// ## 参数
+    /// ### input:
+    /// - command: 命令行,None清除原有的命令行
+    /// ### output:
+    /// - Result<(),ServiceError>
+    /// ## 例子
+    /// ```
+    /// use windows_service_controller::WindowsService;
+    /// let service = WindowsService::open("Lers", None, None).unwrap();
+    /// service.set_failure_command(Some("C:\\Lers\\notify.exe --failed")).unwrap();
+    /// service.set_failure_command(None).unwrap();
+    /// ```
+    pub fn set_failure_command(&self, command: Option<&str>) -> Result<(), ServiceError> {
+        self.change_failure_strings(None, Some(command.unwrap_or("")))
+    }
+
+    /// 只修改SERVICE_FAILURE_ACTIONSW中的字符串,lpsaActions为NULL时恢复操作和重置周期保持不变
+    /// - None: 传NULL,保持原值
+    /// - Some(""): 传空字符串,删除原值
+    fn change_failure_strings(&self, reboot_message: Option<&str>, command: Option<&str>) -> Result<(), ServiceError> {
+        let wide = |s: Option<&str>| s.map(|s| s.encode_utf16().chain(Some(0)).collect::<Vec<u16>>());
+        let mut reboot_message = wide(reboot_message);
+        let mut command = wide(command);
+        let info = SERVICE_FAILURE_ACTIONSW {
+            lpRebootMsg: match &mut reboot_message {
+                None => PWSTR::null(),
+                Some(v) => PWSTR(v.as_mut_ptr()),
+            },
+            lpCommand: match &mut command {
+                None => PWSTR::null(),
+                Some(v) => PWSTR(v.as_mut_ptr()),
+            },
+            ..Default::default()
+        };
+        unsafe {
+            let data = std::slice::from_raw_parts(
+                &info as *const SERVICE_FAILURE_ACTIONSW as *const u8,
+                std::mem::size_of::<SERVICE_FAILURE_ACTIONSW>(),
+            );
+            self.change_config2_raw(ServiceConfigInfoLevel::SERVICE_CONFIG_FAILURE_ACTIONS, data)
+        }
+    }
+
+    /// # 查询服务配置并按`sc qc`的格式输出
+    /// 与`sc qc <name>`的输出一致:以`[SC] QueryServiceConfig SUCCESS`和空行开头,字段名称和对齐方式相同,
+    /// 延迟自动启动的服务在启动类型后显示`(DELAYED)`,交互式服务在服务类型后显示`(interactive)`。
+    /// 每次调用都重新查询配置
+    /// ## 参数
+    /// ### output:
+    /// - Result<String,ServiceError>
+    /// ## 例子
+    /// ```
+    /// use windows_service_controller::WindowsService;
+    /// let service = WindowsService::open("WSearch", None, None).unwrap();
+    /// println!("{}", service.query_config_text().unwrap());
+    /// ```
+    pub fn query_config_text(&self) -> Result<String, ServiceError> {
+        let (config, _buffer) = Self::get_config(self.handle_for(ServiceAccess::SERVICE_QUERY_CONFIG)?)?;
+        let delayed = config.dwStartType == Services::SERVICE_AUTO_START && {
+            let buffer = self.query_config2_raw(ServiceConfigInfoLevel::SERVICE_CONFIG_DELAYED_AUTO_START_INFO)?;
+            buffer.len() >= std::mem::size_of::<SERVICE_DELAYED_AUTO_START_INFO>()
+                && unsafe { std::ptr::read_unaligned(buffer.as_ptr() as *const SERVICE_DELAYED_AUTO_START_INFO) }
+                    .fDelayedAutostart
+                    .as_bool()
+        };
+        Ok(sc::format_qc(&self.name, &config, delayed))
+    }
+
+    /// # 删除该服务
+    /// ## 参数
+    /// ### output:
+    /// - Result<(),ServiceError>
+    #[must_use = "删除服务可能失败,应当检查返回的结果"]
+    pub fn delete_service(&self) -> Result<(), ServiceError> {
+        self.delete_service_ex(false)
+    }
+
+    /// # 删除该服务,可选择同时删除事件源
+    /// 服务常在HKLM\SYSTEM\CurrentControlSet\Services\EventLog\Application\<服务名>下注册事件源,
+    /// DeleteService不会删除该键。remove_eventlog_source为true时,在服务删除成功后删除该键,键不存在时忽略。
+    /// 删除事件源需要管理员权限,失败时服务已经被标记为删除,返回删除事件源的错误
+    /// ## 参数
+    /// ### input:
+    /// - remove_eventlog_source: 是否删除以服务名注册的应用程序事件源
+    /// ### output:
+    /// - Result<(),ServiceError>
+    /// ## 例子
+    /// ```
+    /// use windows_service_controller::WindowsService;
+    /// let service = WindowsService::open("Lers", None, None).unwrap();
+    /// service.delete_service_ex(true).unwrap();
+    /// ```
+    #[must_use = "删除服务可能失败,应当检查返回的结果"]
+    pub fn delete_service_ex(&self, remove_eventlog_source: bool) -> Result<(), ServiceError> {
+        let result = unsafe { DeleteService(self.handle_for(ServiceAccess::DELETE)?) };
+        if result.is_err() {
+            return Err(ServiceError::from_last_error());
+        }
+        if remove_eventlog_source {
+            registry::delete_tree(&registry::eventlog_source_key(&self.name))?;
+        }
+        Ok(())
+    }
+
+    /// # 更新服务配置
+    /// ## 参数
+    /// ### input:
+    /// - config: 新的服务配置,一般由config()获取后修改
+    /// - passwd: 修改服务密码,不修改请传入None
+    /// ### output:
+    /// - Result<(),ServiceError>
+    /// ## 例子
+    /// ```
+    /// use windows_service_controller::WindowsService;
+    /// let mut service = WindowsService::open("Lers", None, None).unwrap();
+    /// let mut config = service.config();
+    /// config.display_name = "lers233".to_string();
+    /// service.update_service_config(&config, None).unwrap()
+    ///```
+    /// 修改后不一定立即生效,可以在修改前用ServiceConfig::change_effect判断是否需要重新启动服务或系统。
+    /// 会把config的所有字段写回,只需要修改部分字段时使用apply_config_change。
+    /// 修改后会重新读取配置,因此需要SERVICE_CHANGE_CONFIG和SERVICE_QUERY_CONFIG权限,缺少时不做任何修改
+    /// ## BUG
+    /// 似乎无法修改service_start_name字段
+    pub fn update_service_config(&mut self, config: &ServiceConfig, passwd: Option<&str>) -> Result<(), ServiceError> {
+        // 修改前检查重新读取配置所需的权限,避免修改成功后读取失败而返回Err
+        let service_handle = self.handle_for(ServiceAccess::SERVICE_CHANGE_CONFIG | ServiceAccess::SERVICE_QUERY_CONFIG)?;
+        let binary_path: Vec<u16> = config.binary_path.encode_utf16().chain(Some(0)).collect();
+        let load_order_group: Vec<u16> = config.load_order_group.encode_utf16().chain(Some(0)).collect();
+        let dependencies: Vec<u16> = builder::to_multi_sz(&config.dependencies).unwrap_or_else(|| vec![0, 0]);
+        let display_name: Vec<u16> = config.display_name.encode_utf16().chain(Some(0)).collect();
+        match unsafe {
+            ChangeServiceConfigW(
+                service_handle,
+                config.service_type.into(),
+                config.start_type.into(),
+                config.error_control.into(),
+                PCWSTR(binary_path.as_ptr()),
+                PCWSTR(load_order_group.as_ptr()),
+                None,
+                PCWSTR(dependencies.as_ptr()),
+                PCWSTR::null(),
+                match passwd {
+                    None => PCWSTR::null(),
+                    Some(s) => PCWSTR!(s),
+                },
+                PCWSTR(display_name.as_ptr()),
+            )
+        } {
+            Ok(_) => {
+                (self.config, self._config_buffer) = Self::get_config(service_handle)?;
+                Ok(())
+            }
+            Err(_) => Err(ServiceError::from_last_error()),
+        }
+    }
+
+    /// 只修改diffs中列出的字段,其余字段传SERVICE_NO_CHANGE或NULL保持不变。
+    /// 标记(tag_id)无法通过ChangeServiceConfigW修改,忽略该项差异
+    pub(crate) fn apply_config_diff(
+        &mut self,
+        diffs: &[ConfigDiff],
+        desired: &ServiceConfig,
+        passwd: Option<&str>,
+    ) -> Result<(), ServiceError> {
+        self.apply_config_change(ConfigChange::from_diffs(diffs, desired, passwd))
+    }
+
+    /// # 按字段修改服务配置
+    /// 调用ChangeServiceConfigW,change中为None的字段传SERVICE_NO_CHANGE或NULL保持不变,只修改为Some的字段。
+    /// 修改成功后重新读取配置,config()返回修改后的配置。
+    /// 需要SERVICE_CHANGE_CONFIG和SERVICE_QUERY_CONFIG权限,缺少时不做任何修改
+    /// ## 参数
+    /// ### input:
+    /// - change: 需要修改的字段
+    /// ### output:
+    /// - Result<(),ServiceError>
+    /// ## 例子
+    /// ```
+    /// use windows_service_controller::config::ConfigChange;
+    /// use windows_service_controller::WindowsService;
+    /// let mut service = WindowsService::open("Lers", None, None).unwrap();
+    /// service
+    ///     .apply_config_change(ConfigChange {
+    ///         binary_path: Some("C:\\Lers\\lers.exe --v2".to_string()),
+    ///         ..Default::default()
+    ///     })
+    ///     .unwrap();
+    /// ```
+    #[must_use = "修改配置可能失败,应当检查返回的结果"]
+    pub fn apply_config_change(&mut self, change: ConfigChange) -> Result<(), ServiceError> {
+        // 与update_service_config相同,修改前检查重新读取配置所需的权限
+        let service_handle = self.handle_for(ServiceAccess::SERVICE_CHANGE_CONFIG | ServiceAccess::SERVICE_QUERY_CONFIG)?;
+        let wide = |s: &Option<String>| s.as_ref().map(|s| s.encode_utf16().chain(Some(0)).collect::<Vec<u16>>());
+        let binary_path = wide(&change.binary_path);
+        let load_order_group = wide(&change.load_order_group);
+        let service_start_name = wide(&change.service_start_name);
+        let password = wide(&change.password);
+        let display_name = wide(&change.display_name);
+        let dependencies = change
+            .dependencies
+            .as_ref()
+            .map(|dependencies| builder::to_multi_sz(dependencies).unwrap_or_else(|| vec![0, 0]));
+        let pointer = |v: &Option<Vec<u16>>| match v {
+            None => PCWSTR::null(),
+            Some(v) => PCWSTR(v.as_ptr()),
+        };
+        match unsafe {
+            ChangeServiceConfigW(
+                service_handle,
+                change.service_type.map_or(ENUM_SERVICE_TYPE(Services::SERVICE_NO_CHANGE), Into::into),
+                change.start_type.map_or(SERVICE_START_TYPE(Services::SERVICE_NO_CHANGE), Into::into),
+                change.error_control.map_or(SERVICE_ERROR(Services::SERVICE_NO_CHANGE), Into::into),
+                pointer(&binary_path),
+                pointer(&load_order_group),
+                None,
+                pointer(&dependencies),
+                pointer(&service_start_name),
+                pointer(&password),
+                pointer(&display_name),
+            )
+        } {
+            Ok(_) => {
+                (self.config, self._config_buffer) = Self::get_config(service_handle)?;
+                Ok(())
+            }
+            Err(_) => Err(ServiceError::from_last_error()),
+        }
+    }
+
+    /// # 获取服务的依赖项
+    /// 从打开服务时(或最近一次修改配置后)读取的配置中解析,'+'开头的项解析为服务组
+    /// ## 参数
+    /// ### output:
+    /// - Vec<Dependency>
+    /// ## 例子
+    /// ```
+    /// use windows_service_controller::config::Dependency;
+    /// use windows_service_controller::WindowsService;
+    /// let service = WindowsService::open("Lers", None, None).unwrap();
+    /// for dependency in service.dependencies() {
+    ///     match dependency {
+    ///         Dependency::Service(name) => println!("service {}", name),
+    ///         Dependency::Group(group) => println!("group {}", group),
+    ///     }
+    /// }
+    /// ```
+    pub fn dependencies(&self) -> Vec<Dependency> {
+        self.config().dependency_list()
+    }
+
+    /// # 修改服务的依赖项
+    /// 服务组写入时加上'+'前缀,传入空列表时移除所有依赖项。只修改依赖项,其他配置保持不变。
+    /// 名称为空或包含NUL、服务名称以'+'开头(会被SCM当作服务组)时返回ERROR_INVALID_PARAMETER。
+    /// 需要SERVICE_CHANGE_CONFIG和SERVICE_QUERY_CONFIG权限,修改在服务下次启动时生效
+    /// ## 参数
+    /// ### input:
+    /// - dependencies: 新的依赖项
+    /// ### output:
+    /// - Result<(),ServiceError>
+    /// ## 例子
+    /// ```
+    /// use windows_service_controller::config::Dependency;
+    /// use windows_service_controller::WindowsService;
+    /// let mut service = WindowsService::open("Lers", None, None).unwrap();
+    /// service
+    ///     .set_dependencies(&[Dependency::Service("RpcSs".to_string()), Dependency::Group("NetworkProvider".to_string())])
+    ///     .unwrap();
+    /// ```
+    #[must_use = "修改配置可能失败,应当检查返回的结果"]
+    pub fn set_dependencies(&mut self, dependencies: &[Dependency]) -> Result<(), ServiceError> {
+        for dependency in dependencies {
+            dependency.validate()?;
+        }
+        self.apply_config_change(ConfigChange {
+            dependencies: Some(dependencies.iter().map(Dependency::to_raw).collect()),
+            ..Default::default()
+        })
+    }
+
+    /// # 发送控制代码到服务
+    /// 句柄有SERVICE_QUERY_STATUS权限时,发送前检查服务是否接受该控制(见ServiceControlCode::required_accept_flag):
+    /// 服务正在运行但dwControlsAccepted不包含所需的标志时,停止控制返回SERVICE_NOT_STOPPABLE,
+    /// 其他控制返回ERROR_SERVICE_CANNOT_ACCEPT_CTRL,不会发送控制
+    /// ## 参数：
+    /// ### input:
+    /// - code: 控制代码
+    /// ### output:
+    /// - Result<(),ServiceError>
+    /// ## 例子
+    /// ```
+    /// use windows_service_controller::dword::ServiceControlCode;
+    /// use windows_service_controller::WindowsService;
+    /// let mut service = WindowsService::open("Lers", None, None).unwrap();
+    /// match service.control_service(ServiceControlCode::SERVICE_CONTROL_STOP){
+    ///     Ok(_) => {
+    ///         println!("succeed")
+    ///     }
+    ///     Err(e) => {
+    ///         println!("{}", e)
+    ///     }
+    /// }
+    /// ```
+    #[must_use = "控制请求可能失败,应当检查返回的结果"]
+    pub fn control_service(&self, code: ServiceControlCode) -> Result<(), ServiceError> {
+        self.send_control(code, true)
+    }
+
+    /// # 通知服务添加了新的网络绑定组件
+    /// 网络绑定发生变化时由网络配置程序发送,服务应当重新枚举绑定。
+    /// 需要SERVICE_PAUSE_CONTINUE权限,服务不接受SERVICE_ACCEPT_NETBINDCHANGE时返回ERROR_SERVICE_CANNOT_ACCEPT_CTRL
+    /// ## 参数：
+    /// ### output:
+    /// - Result<(),ServiceError>
+    /// ## 例子
+    /// ```
+    /// use windows_service_controller::dword::ServiceError;
+    /// use windows_service_controller::WindowsService;
+    /// let service = WindowsService::open("Lers", None, None).unwrap();
+    /// match service.netbind_add() {
+    ///     Err(e) if e == ServiceError::ERROR_SERVICE_CANNOT_ACCEPT_CTRL => println!("服务不处理网络绑定变化"),
+    ///     result => result.unwrap(),
+    /// }
+    /// ```
+    #[must_use = "控制请求可能失败,应当检查返回的结果"]
+    pub fn netbind_add(&self) -> Result<(), ServiceError> {
+        self.control_service(ServiceControlCode::SERVICE_CONTROL_NETBINDADD)
+    }
+
+    /// # 通知服务删除了网络绑定组件
+    /// 服务应当重新枚举绑定并释放被删除组件的绑定,其余同netbind_add
+    #[must_use = "控制请求可能失败,应当检查返回的结果"]
+    pub fn netbind_remove(&self) -> Result<(), ServiceError> {
+        self.control_service(ServiceControlCode::SERVICE_CONTROL_NETBINDREMOVE)
+    }
+
+    /// # 通知服务启用了之前禁用的网络绑定
+    /// 服务应当重新枚举绑定并使用新启用的绑定,其余同netbind_add
+    #[must_use = "控制请求可能失败,应当检查返回的结果"]
+    pub fn netbind_enable(&self) -> Result<(), ServiceError> {
+        self.control_service(ServiceControlCode::SERVICE_CONTROL_NETBINDENABLE)
+    }
+
+    /// # 通知服务禁用了网络绑定
+    /// 服务应当重新枚举绑定并停止使用被禁用的绑定,其余同netbind_add
+    #[must_use = "控制请求可能失败,应当检查返回的结果"]
+    pub fn netbind_disable(&self) -> Result<(), ServiceError> {
+        self.control_service(ServiceControlCode::SERVICE_CONTROL_NETBINDDISABLE)
+    }
+
+    /// 发送控制代码,check_accepted为true且句柄可以查询状态时先等待服务离开挂起状态(仅停止、暂停控制),
+    /// 再检查服务是否接受该控制;为false时不等待也不检查,立即发送
+    fn send_control(&self, code: ServiceControlCode, check_accepted: bool) -> Result<(), ServiceError> {
+        let required = code.required_accept_flag();
+        let code: u32 = code.into();
+        let service_handle = self.handle_for(Self::control_access(code))?;
+        let can_query = self.access.contains(ServiceAccess::SERVICE_QUERY_STATUS);
+        let waits = code == Services::SERVICE_CONTROL_STOP || code == Services::SERVICE_CONTROL_PAUSE;
+        if waits && check_accepted && can_query {
+            // 服务处于挂起状态时无法接受控制,先等待状态稳定;句柄没有查询权限时直接发送
+            match self.wait_while_pending(DEFAULT_PENDING_TIMEOUT) {
+                Err(e) if e != ServiceError::ERROR_ACCESS_DENIED => return Err(e),
+                _ => {}
+            }
+        }
+        if let Some(required) = required.filter(|_| check_accepted && can_query) {
+            // 已停止的服务不接受任何控制,交给ControlService返回ERROR_SERVICE_NOT_ACTIVE
+            if let Ok(status) = self.query_status_ex() {
+                if status.current_state() != ServiceStatus::SERVICE_STOPPED && !status.controls_accepted().contains(required) {
+                    return Err(if code == Services::SERVICE_CONTROL_STOP {
+                        ServiceError::SERVICE_NOT_STOPPABLE
+                    } else {
+                        ServiceError::ERROR_SERVICE_CANNOT_ACCEPT_CTRL
+                    });
+                }
+            }
+        }
+        let mut service_status = SERVICE_STATUS::default();
+        unsafe {
+            match ControlService(
+                service_handle,
+                code,
+                &mut service_status,
+            )
+            {
+                Ok(_) => { Ok(()) }
+                Err(_) => { Err(ServiceError::from_last_error()) }
+            }
+        }
+    }
+
+    /// # 发送控制代码并等待服务到达预期的状态
+    /// 发送控制(与control_service相同,会先检查服务是否接受该控制)后轮询服务状态,直到服务离开挂起状态并处于expected。
+    /// 标准控制的预期状态可以由ServiceControlCode::expected_state得到;用户自定义控制由调用者指定服务处理后的状态。
+    /// - 服务在途中停止而expected不是SERVICE_STOPPED时返回ERROR_SERVICE_NOT_ACTIVE
+    /// - 超时前没有到达expected(例如暂停失败后回到运行)时返回ERROR_SERVICE_REQUEST_TIMEOUT
+    ///
+    /// 需要控制所需的权限以及SERVICE_QUERY_STATUS权限
+    /// ## 参数：
+    /// ### input:
+    /// - code: 控制代码
+    /// - expected: 预期的稳定状态
+    /// - timeout: 发送控制后最长的等待时间
+    /// ### output:
+    /// - Result<ServiceStatus,ServiceError>: 到达的状态(即expected)
+    /// ## 例子
+    /// ```
+    /// use std::time::Duration;
+    /// use windows_service_controller::dword::ServiceControlCode;
+    /// use windows_service_controller::WindowsService;
+    /// let service = WindowsService::open("Lers", None, None).unwrap();
+    /// let code = ServiceControlCode::SERVICE_CONTROL_PAUSE;
+    /// let expected = code.expected_state().unwrap();
+    /// service.control_and_wait(code, expected, Duration::from_secs(30)).unwrap();
+    /// ```
+    #[must_use = "控制请求可能失败或超时,应当检查返回的结果"]
+    pub fn control_and_wait(
+        &self,
+        code: ServiceControlCode,
+        expected: ServiceStatus,
+        timeout: Duration,
+    ) -> Result<ServiceStatus, ServiceError> {
+        self.handle_for(ServiceAccess::SERVICE_QUERY_STATUS)?;
+        self.control_service(code)?;
+        let start = Instant::now();
+        loop {
+            let remaining = timeout.saturating_sub(start.elapsed());
+            let state = self.wait_while_pending(remaining)?;
+            if state == expected {
+                return Ok(state);
+            }
+            if state == ServiceStatus::SERVICE_STOPPED {
+                return Err(ServiceError::ERROR_SERVICE_NOT_ACTIVE);
+            }
+            let remaining = timeout.saturating_sub(start.elapsed());
+            if remaining.is_zero() {
+                return Err(ServiceError::ERROR_SERVICE_REQUEST_TIMEOUT);
+            }
+            // 服务可能还没有开始处理控制,稍后再查询
+            std::thread::sleep(remaining.min(CONTROL_POLL_INTERVAL));
+        }
+    }
+
+    /// # 等待服务启动完成并能够响应控制
+    /// 服务报告SERVICE_RUNNING只说明它调用了SetServiceStatus,不代表已经可以提供服务;
+    /// 运行在共享进程(svchost)中的服务尤其如此,进程中的其他服务可能已经让进程就绪。
+    /// 这里在服务进入运行状态后再发送SERVICE_CONTROL_INTERROGATE,只有服务的控制处理程序实际响应了这次往返,
+    /// 才视为就绪;服务暂时无法接受控制(ERROR_SERVICE_CANNOT_ACCEPT_CTRL)时在超时前重试。
+    /// 这仍然只是近似:控制处理程序能响应不代表服务的业务逻辑已经初始化完成,需要精确判断时应当由服务自身提供就绪信号
+    /// - 服务离开挂起状态后不是SERVICE_RUNNING时返回ERROR_SERVICE_NOT_ACTIVE
+    /// - 超时返回ERROR_SERVICE_REQUEST_TIMEOUT
+    ///
+    /// 需要SERVICE_QUERY_STATUS和SERVICE_INTERROGATE权限
+    /// ## 参数：
+    /// ### input:
+    /// - timeout: 最长等待时间
+    /// ### output:
+    /// - Result<FullStatus,ServiceError>: 就绪时的扩展状态,可以通过runs_in_system_process等方法查看服务标志
+    /// ## 例子
+    /// ```
+    /// use std::time::Duration;
+    /// use windows_service_controller::WindowsService;
+    /// let service = WindowsService::open("Dnscache", None, None).unwrap();
+    /// service.start_service().ok();
+    /// let status = service.wait_for_ready(Duration::from_secs(30)).unwrap();
+    /// println!("pid {}", status.process_id());
+    /// ```
+    #[must_use = "等待可能超时,应当检查返回的状态"]
+    pub fn wait_for_ready(&self, timeout: Duration) -> Result<FullStatus, ServiceError> {
+        self.handle_for(ServiceAccess::SERVICE_INTERROGATE)?;
+        let start = Instant::now();
+        if self.wait_while_pending(timeout)? != ServiceStatus::SERVICE_RUNNING {
+            return Err(ServiceError::ERROR_SERVICE_NOT_ACTIVE);
+        }
+        loop {
+            match self.send_control(ServiceControlCode::SERVICE_CONTROL_INTERROGATE, false) {
+                Ok(_) => return self.query_status_ex(),
+                Err(e) if e == ServiceError::ERROR_SERVICE_CANNOT_ACCEPT_CTRL => {}
+                Err(e) => return Err(e),
+            }
+            let remaining = timeout.saturating_sub(start.elapsed());
+            if remaining.is_zero() {
+                return Err(ServiceError::ERROR_SERVICE_REQUEST_TIMEOUT);
+            }
+            std::thread::sleep(remaining.min(CONTROL_POLL_INTERVAL));
+        }
+    }
+
+    /// # 等待服务离开挂起状态
+    /// 轮询服务状态直到不再处于*_PENDING状态。轮询间隔为dwWaitHint的十分之一(1秒到10秒之间),
+    /// 如果在dwWaitHint内dwCheckPoint没有增加,视为服务没有响应(判断方式见StartProgress::is_stalled,dwWaitHint为0时按30秒计算)
+    /// ## 参数：
+    /// ### input:
+    /// - timeout: 最长等待时间
+    /// ### output:
+    /// - Result<ServiceStatus,ServiceError>: 服务稳定后的状态,超时返回ERROR_SERVICE_REQUEST_TIMEOUT
+    /// ## 例子
+    /// ```
+    /// use std::time::Duration;
+    /// use windows_service_controller::WindowsService;
+    /// let service = WindowsService::open("WSearch", None, None).unwrap();
+    /// println!("{}", service.wait_while_pending(Duration::from_secs(30)).unwrap());
+    /// ```
+    #[must_use = "等待可能超时,应当检查返回的状态"]
+    pub fn wait_while_pending(&self, timeout: Duration) -> Result<ServiceStatus, ServiceError> {
+        self.wait_while_pending_with_progress(timeout, |_| {})
+    }
+
+    /// # 等待服务离开挂起状态并报告进度
+    /// 与wait_while_pending相同,每次轮询到挂起状态时调用on_progress,可用于显示进度
+    /// ## 参数：
+    /// ### input:
+    /// - timeout: 最长等待时间
+    /// - on_progress: 进度回调
+    /// ### output:
+    /// - Result<ServiceStatus,ServiceError>: 服务稳定后的状态,超时或停滞返回ERROR_SERVICE_REQUEST_TIMEOUT
+    /// ## 例子
+    /// ```
+    /// use std::time::Duration;
+    /// use windows_service_controller::WindowsService;
+    /// let service = WindowsService::open("WSearch", None, None).unwrap();
+    /// service.start_service().unwrap();
+    /// service
+    ///     .wait_while_pending_with_progress(Duration::from_secs(30), |progress| {
+    ///         println!("checkpoint {} ({:?})", progress.checkpoint, progress.elapsed)
+    ///     })
+    ///     .unwrap();
+    /// ```
+    #[must_use = "等待可能超时,应当检查返回的状态"]
+    pub fn wait_while_pending_with_progress<F>(&self, timeout: Duration, mut on_progress: F) -> Result<ServiceStatus, ServiceError>
+    where
+        F: FnMut(StartProgress),
+    {
+        let mut status = self.query_status_ex()?;
+        let mut tracker = ProgressTracker::new(&status, Instant::now());
+        loop {
+            if !status.current_state().is_pending() {
+                return Ok(status.current_state());
+            }
+            let progress = tracker.update(&status, Instant::now());
+            on_progress(progress);
+            if progress.is_stalled() || progress.elapsed >= timeout {
+                return Err(ServiceError::ERROR_SERVICE_REQUEST_TIMEOUT);
+            }
+            let interval = (progress.wait_hint / 10).clamp(Duration::from_secs(1), Duration::from_secs(10));
+            std::thread::sleep(interval.min(timeout - progress.elapsed));
+            status = self.query_status_ex()?;
+        }
+    }
+
+    /// # 开启服务
+    /// ## 参数：
+    /// ### output:
+    /// - Result<(),ServiceError>
+    /// ## 例子
+    /// ```
+    /// use windows_service_controller::WindowsService;
+    /// let mut service = WindowsService::open("Lers", None, None).unwrap();
+    ///
+    /// match service.start_service(){
+    ///     Ok(_) => {
+    ///         println!("succeed")
+    ///     }
+    ///     Err(e) => {
+    ///         println!("{}", e)
+    ///     }
+    /// }
+    /// ```
+    #[must_use = "启动服务可能失败,应当检查返回的结果"]
+    pub fn start_service(&self) -> Result<(), ServiceError> {
+        let service_handle = self.handle_for(ServiceAccess::SERVICE_START)?;
+        unsafe {
+            match StartServiceW(
+                service_handle,
+                None,
+            ) {
+                Ok(_) => { Ok(()) }
+                Err(_) => Err(ServiceError::from_last_error()),
+            }
+        }
+    }
+
+    /// # 带参数开启服务
+    /// 参数只传给本次启动的ServiceMain,不会保存。需要每次启动都生效的参数请使用set_start_arguments
+    /// ## 参数：
+    /// ### input:
+    /// - args: 传给ServiceMain的参数
+    /// ### output:
+    /// - Result<(),ServiceError>
+    /// ## 例子
+    /// ```
+    /// use windows_service_controller::WindowsService;
+    /// let service = WindowsService::open("Lers", None, None).unwrap();
+    /// service.start_service_with_args(&["--verbose"]).unwrap();
+    /// ```
+    #[must_use = "启动服务可能失败,应当检查返回的结果"]
+    pub fn start_service_with_args(&self, args: &[&str]) -> Result<(), ServiceError> {
+        self.start_with_wide_args(args.iter().map(|arg| arg.encode_utf16().chain(Some(0)).collect()).collect())
+    }
+
+    /// # 以OsStr参数开启服务
+    /// 与start_service_with_args相同,但参数直接以UTF-16编码传递,不经过UTF-8转换,
+    /// 适合传递可能包含非Unicode内容的文件路径
+    /// ## 参数：
+    /// ### input:
+    /// - args: 传给ServiceMain的参数
+    /// ### output:
+    /// - Result<(),ServiceError>
+    /// ## 例子
+    /// ```
+    /// use std::ffi::OsStr;
+    /// use std::path::Path;
+    /// use windows_service_controller::WindowsService;
+    /// let service = WindowsService::open("Lers", None, None).unwrap();
+    /// let config = Path::new("C:\\Lers\\lers.toml");
+    /// service.start_os(&[OsStr::new("--config"), config.as_os_str()]).unwrap();
+    /// ```
+    #[must_use = "启动服务可能失败,应当检查返回的结果"]
+    pub fn start_os(&self, args: &[&OsStr]) -> Result<(), ServiceError> {
+        self.start_with_wide_args(args.iter().map(|arg| arg.encode_wide().chain(Some(0)).collect()).collect())
+    }
+
+    /// 以NUL结尾的UTF-16参数调用StartServiceW
+    fn start_with_wide_args(&self, args: Vec<Vec<u16>>) -> Result<(), ServiceError> {
+        let service_handle = self.handle_for(ServiceAccess::SERVICE_START)?;
+        let argv: Vec<PCWSTR> = args.iter().map(|arg| PCWSTR(arg.as_ptr())).collect();
+        unsafe {
+            match StartServiceW(service_handle, Some(&argv)) {
+                Ok(_) => Ok(()),
+                Err(_) => Err(ServiceError::from_last_error()),
+            }
+        }
+    }
+
+    /// # 设置服务持久的启动参数
+    /// 将参数写入服务的可执行文件路径(lpBinaryPathName),每次启动(包括重启系统后)都会传给进程的main函数,
+    /// 原有的参数会被替换。只对本次启动生效的参数请使用start_service_with_args
+    /// ## 参数：
+    /// ### input:
+    /// - args: 启动参数,包含空格或引号的参数会自动加上引号
+    /// ### output:
+    /// - Result<(),ServiceError>
+    /// ## 例子
+    /// ```
+    /// use windows_service_controller::WindowsService;
+    /// let mut service = WindowsService::open("Lers", None, None).unwrap();
+    /// service.set_start_arguments(&["--config", "C:\\My Config.toml"]).unwrap();
+    /// ```
+    pub fn set_start_arguments(&mut self, args: &[&str]) -> Result<(), ServiceError> {
+        self.handle_for(ServiceAccess::SERVICE_QUERY_CONFIG)?;
+        let service_handle = self.handle_for(ServiceAccess::SERVICE_CHANGE_CONFIG)?;
+        let (config, _buffer) = Self::get_config(service_handle)?;
+        let binary_path = wide_to_string(config.lpBinaryPathName);
+        let (executable, _) = path::split_binary_path(&binary_path);
+        let binary_path: Vec<u16> = path::join_binary_path(executable, args)
+            .encode_utf16()
+            .chain(Some(0))
+            .collect();
+        match unsafe {
+            ChangeServiceConfigW(
+                service_handle,
+                ENUM_SERVICE_TYPE(Services::SERVICE_NO_CHANGE),
+                SERVICE_START_TYPE(Services::SERVICE_NO_CHANGE),
+                SERVICE_ERROR(Services::SERVICE_NO_CHANGE),
+                PCWSTR(binary_path.as_ptr()),
+                PCWSTR::null(),
+                None,
+                PCWSTR::null(),
+                PCWSTR::null(),
+                PCWSTR::null(),
+                PCWSTR::null(),
+            )
+        } {
+            Ok(_) => {
+                (self.config, self._config_buffer) = Self::get_config(service_handle)?;
+                Ok(())
+            }
+            Err(_) => Err(ServiceError::from_last_error()),
+        }
+    }
+
+    /// # 停止服务
+    /// 发送停止控制前检查服务是否接受停止,不接受时返回SERVICE_NOT_STOPPABLE,
+    /// 需要跳过检查请使用stop_service_ex
+    /// ## 参数:
+    /// ### output:
+    /// - Result<(),ServiceError>
+    /// ## 例子
+    /// ```
+    /// use windows_service_controller::WindowsService;
+    /// let mut service = WindowsService::open("Lers", None, None).unwrap();
+    ///
+    /// match service.stop_service(){
+    ///     Ok(_) => {
+    ///         println!("succeed")
+    ///     }
+    ///     Err(e) => {
+    ///         println!("{}", e)
+    ///     }
+    /// }
+    /// ```
+    #[must_use = "停止服务可能失败,应当检查返回的结果"]
+    pub fn stop_service(&self) -> Result<(), ServiceError> {
+        self.stop_service_ex(true)
+    }
+
+    /// # 停止服务,可选择是否预先检查
+    /// ## 参数:
+    /// ### input:
+    /// - check_accepted: 为true时先等待服务离开挂起状态,服务正在运行但dwControlsAccepted
+    ///   不包含SERVICE_ACCEPT_STOP时直接返回SERVICE_NOT_STOPPABLE;
+    ///   句柄没有SERVICE_QUERY_STATUS权限时跳过检查。为false时不等待也不检查,立即发送停止控制,
+    ///   服务处于挂起状态时由ControlService返回错误(如ERROR_SERVICE_CANNOT_ACCEPT_CTRL)
+    /// ### output:
+    /// - Result<(),ServiceError>
+    /// ## 例子
+    /// ```
+    /// use windows_service_controller::dword::ServiceError;
+    /// use windows_service_controller::WindowsService;
+    /// let service = WindowsService::open("Lers", None, None).unwrap();
+    /// match service.stop_service_ex(true) {
+    ///     Err(e) if e == ServiceError::SERVICE_NOT_STOPPABLE => println!("该服务无法停止"),
+    ///     result => result.unwrap(),
+    /// }
+    /// ```
+    #[must_use = "停止服务可能失败,应当检查返回的结果"]
+    pub fn stop_service_ex(&self, check_accepted: bool) -> Result<(), ServiceError> {
+        self.send_control(ServiceControlCode::SERVICE_CONTROL_STOP, check_accepted)
+    }
+
+    /// # 切换服务的运行状态
+    /// 先等待服务离开挂起状态,之后服务正在运行(或已暂停)时停止,已停止时启动,再等待服务离开挂起状态,
+    /// 返回最终观察到的状态,可以直接用于更新界面。两次等待共用timeout
+    /// ## 参数：
+    /// ### input:
+    /// - timeout: 最长等待时间
+    /// ### output:
+    /// - Result<ServiceStatus,ServiceError>: 切换后的状态,超时返回ERROR_SERVICE_REQUEST_TIMEOUT;
+    ///   服务无法停止时返回SERVICE_NOT_STOPPABLE
+    /// ## 例子
+    /// ```
+    /// use std::time::Duration;
+    /// use windows_service_controller::WindowsService;
+    /// let service = WindowsService::open("Lers", None, None).unwrap();
+    /// println!("{}", service.toggle(Duration::from_secs(30)).unwrap());
+    /// ```
+    #[must_use = "切换可能失败或超时,应当检查返回的状态"]
+    pub fn toggle(&self, timeout: Duration) -> Result<ServiceStatus, ServiceError> {
+        let start = Instant::now();
+        if self.wait_while_pending(timeout)? == ServiceStatus::SERVICE_STOPPED {
+            self.start_service()?;
+        } else {
+            self.stop_service()?;
+        }
+        self.wait_while_pending(timeout.saturating_sub(start.elapsed()))
+    }
+
+    /// # 关闭服务句柄与服务管理器句柄
+    /// 与drop不同,关闭失败时返回错误而不是忽略。两个句柄都会尝试关闭,
+    /// 关闭后句柄被置空,drop时不会重复关闭,其他方法会返回ERROR_INVALID_HANDLE
+    /// ## 参数:
+    /// ### output:
+    /// - Result<(),ServiceError>: 有句柄关闭失败时返回第一个错误
+    /// ## 例子
+    /// ```
+    /// use windows_service_controller::WindowsService;
+    /// let mut service = WindowsService::open("WSearch", None, None).unwrap();
+    /// service.close_all().unwrap();
+    /// ```
+    pub fn close_all(&mut self) -> Result<(), ServiceError> {
+        let mut result = Ok(());
+        for handle in [&mut self.service_handle, &mut self.sc_manager_handle] {
+            if let Err(e) = handle.close() {
+                result = result.and(Err(e));
+            }
+        }
+        result
+    }
+
+    /// 获取服务句柄,句柄为空时返回ERROR_INVALID_HANDLE,避免以空句柄调用Win32 API
+    fn handle(&self) -> Result<SC_HANDLE, ServiceError> {
+        if self.service_handle.is_invalid() {
+            Err(ServiceError::ERROR_INVALID_HANDLE)
+        } else {
+            Ok(self.service_handle.get())
+        }
+    }
+
+    /// 获取服务句柄,并检查打开服务时是否请求了操作所需的访问权限,
+    /// 缺少时返回INSUFFICIENT_ACCESS_*,而不是调用Win32 API后得到ERROR_ACCESS_DENIED
+    fn handle_for(&self, required: ServiceAccess) -> Result<SC_HANDLE, ServiceError> {
+        let handle = self.handle()?;
+        match ServiceError::missing_access(self.access, required) {
+            None => Ok(handle),
+            Some(e) => Err(e),
+        }
+    }
+
+    /// 发送控制代码所需的访问权限
+    fn control_access(code: u32) -> ServiceAccess {
+        match code {
+            Services::SERVICE_CONTROL_STOP => ServiceAccess::SERVICE_STOP,
+            Services::SERVICE_CONTROL_INTERROGATE => ServiceAccess::SERVICE_INTERROGATE,
+            128..=255 => ServiceAccess::SERVICE_USER_DEFINED_CONTROL,
+            _ => ServiceAccess::SERVICE_PAUSE_CONTINUE,
+        }
+    }
+
+    /// # 通知服务关机
+    /// SERVICE_CONTROL_SHUTDOWN和SERVICE_CONTROL_PRESHUTDOWN只能由系统在关机时发送,
+    /// ControlService无法向服务发送这两个控制代码,因此外部程序无法触发服务的关机处理。
+    /// 该方法不会发送任何控制代码,只用于明确这一限制:
+    /// - 服务不接受关机通知时返回ERROR_SERVICE_CANNOT_ACCEPT_CTRL
+    /// - 服务接受关机通知时返回ERROR_INVALID_SERVICE_CONTROL,表示该控制只能由系统发送
+    ///
+    /// 需要停止服务请使用stop_service
+    /// ## 参数:
+    /// ### output:
+    /// - Result<(),ServiceError>
+    #[must_use = "外部程序无法发送关机控制,返回的错误说明了原因,应当检查"]
+    pub fn shutdown(&self) -> Result<(), ServiceError> {
+        let accepted = self.query_status_ex()?.controls_accepted();
+        if accepted.contains(ControlsAccepted::SERVICE_ACCEPT_SHUTDOWN)
+            || accepted.contains(ControlsAccepted::SERVICE_ACCEPT_PRESHUTDOWN)
+        {
+            Err(ServiceError::ERROR_INVALID_SERVICE_CONTROL)
+        } else {
+            Err(ServiceError::ERROR_SERVICE_CANNOT_ACCEPT_CTRL)
+        }
+    }
+
+    fn open_service(
+        sc_manager_handle: SC_HANDLE,
+        name: &str,
+        access: ServiceAccess,
+    ) -> Result<SC_HANDLE, ServiceError> {
+        let service_handle = unsafe { OpenServiceW(sc_manager_handle, PCWSTR!(name), access.into()) };
+        match service_handle {
+            Ok(handle) => {
+                handle_opened();
+                Ok(handle)
+            }
+            Err(_) => Err(ServiceError::from_last_error()),
+        }
+    }
+
+    fn open_sc_manager(access: ScManagerAccess) -> Result<SC_HANDLE, ServiceError> {
+        Self::open_sc_manager_database(None, access)
+    }
+
+    /// 打开本机指定数据库的SCM,database为None时打开默认的活动数据库
+    fn open_sc_manager_database(database: Option<&str>, access: ScManagerAccess) -> Result<SC_HANDLE, ServiceError> {
+        let database: Option<Vec<u16>> = database.map(|name| OsStr::new(name).encode_wide().chain(Some(0)).collect());
+        let database = database.as_ref().map_or(PCWSTR::null(), |name| PCWSTR(name.as_ptr()));
+        let sc_manager_handle = unsafe { OpenSCManagerW(PCWSTR::null(), database, access.into()) };
+        match sc_manager_handle {
+            Ok(handle) => {
+                handle_opened();
+                Ok(handle)
+            }
+            Err(_) => Err(ServiceError::from_last_error()),
+        }
+    }
+
+    fn get_status_ex(service_handle: SC_HANDLE) -> Result<FullStatus, ServiceError> {
+        let mut status = SERVICE_STATUS_PROCESS::default();
+        let mut cap: u32 = Default::default();
+        let buffer = unsafe {
+            std::slice::from_raw_parts_mut(
+                &mut status as *mut SERVICE_STATUS_PROCESS as *mut u8,
+                std::mem::size_of::<SERVICE_STATUS_PROCESS>(),
+            )
+        };
+        match unsafe { QueryServiceStatusEx(service_handle, SC_STATUS_PROCESS_INFO, Some(buffer), &mut cap) } {
+            Ok(_) => Ok(status.into()),
+            Err(_) => Err(ServiceError::from_last_error()),
+        }
+    }
+
+    /// 查询服务配置
+    /// 返回的结构体中的字符串指针指向同时返回的缓冲区,读取这些字符串时缓冲区必须存活
+    fn get_config(service_handle: SC_HANDLE) -> Result<(RawServiceConfig, Vec<u8>), ServiceError> {
+        let buffer = query_with_buffer(|buffer, bytes_needed| {
+            // Vec<u8>不保证按QUERY_SERVICE_CONFIGW对齐,只传递裸指针并以read_unaligned读取结构体
+            let config = if buffer.is_empty() { None } else { Some(buffer.as_mut_ptr() as *mut RawServiceConfig) };
+            unsafe { QueryServiceConfigW(service_handle, config, buffer.len() as u32, bytes_needed) }
+                .map_err(|_| unsafe { GetLastError() })
+        })?;
+        let config = unsafe { std::ptr::read_unaligned(buffer.as_ptr() as *const RawServiceConfig) };
+        // 移动Vec不会移动堆上的数据,结构体中的指针仍然有效
+        Ok((config, buffer))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::mem::ManuallyDrop;
+    use std::time::Duration;
+
+    use windows::Win32::Foundation::{ERROR_ACCESS_DENIED, ERROR_INSUFFICIENT_BUFFER};
+    use windows::Win32::System::Services::SC_HANDLE;
+    use windows::Win32::System::Threading::{GetCurrentProcess, GetProcessHandleCount};
+
+    use crate::config::{ConfigChange, Dependency};
+    use crate::dword::{LaunchProtected, ScManagerAccess, ServiceAccess, ServiceConfigInfoLevel, ServiceControlCode, ServiceError, ServiceErrorControl, ServiceStartType, ServiceStatus, ServiceType};
+    use crate::failure::FailureActions;
+    use crate::handle::OwnedScHandle;
+    use crate::manager::ServiceManager;
+    use crate::handle::ServiceHandle;
+    use crate::{handles_opened_on_thread, is_elevated, open_handle_count, query_with_buffer, validate_service_name, RawServiceConfig, ServiceName, WindowsService, QUERY_BUFFER_ATTEMPTS};
+
+    /// 压力测试中打开并关闭服务的次数
+    const STRESS_ITERATIONS: usize = 5000;
+
+    /// 压力测试前后句柄数量允许的增长,其他测试在并行运行时也会打开句柄
+    const HANDLE_SLACK: usize = 64;
+
+    fn process_handle_count() -> usize {
+        let mut count: u32 = 0;
+        unsafe { GetProcessHandleCount(GetCurrentProcess(), &mut count) }.expect("GetProcessHandleCount");
+        count as usize
+    }
+
+    #[test]
+    fn validate_name() {
+        assert!(validate_service_name("Lers").is_ok());
+        assert!(validate_service_name(&"a".repeat(256)).is_ok());
+        assert_eq!(validate_service_name(""), Err(ServiceError::ERROR_INVALID_NAME));
+        assert_eq!(validate_service_name("Lers/1"), Err(ServiceError::ERROR_INVALID_NAME));
+        assert_eq!(validate_service_name("Lers\\1"), Err(ServiceError::ERROR_INVALID_NAME));
+        assert_eq!(validate_service_name(&"a".repeat(257)), Err(ServiceError::ERROR_INVALID_NAME));
+        assert_eq!(validate_service_name("Lers\0Lers"), Err(ServiceError::ERROR_INVALID_NAME));
+    }
+
+    #[test]
+    fn service_name() {
+        let name = ServiceName::try_new("Lers").unwrap();
+        assert_eq!(name.as_str(), "Lers");
+        assert_eq!(&*name, "Lers");
+        assert_eq!(name.to_string(), "Lers");
+        assert_eq!(ServiceName::try_from(String::from("Lers")), Ok(name.clone()));
+        assert_eq!(String::from(name), "Lers");
+        for invalid in ["", "Lers/1", "Lers\\1", "Lers\0Lers"] {
+            assert_eq!(ServiceName::try_new(invalid), Err(ServiceError::ERROR_INVALID_NAME));
+        }
+        assert_eq!(ServiceName::try_new(&"a".repeat(257)), Err(ServiceError::ERROR_INVALID_NAME));
+    }
+
+    #[test]
+    #[ignore = "反复打开SCM较慢,使用cargo test -- --ignored open_close_stress单独运行"]
+    fn open_close_stress() {
+        let process_before = process_handle_count();
+        let library_before = open_handle_count();
+        let mut opened = 0;
+        for _ in 0..STRESS_ITERATIONS {
+            // 成功打开后drop,以及打开SCM后打开服务失败的错误路径
+            if WindowsService::open("WSearch", Some(ServiceAccess::GENERIC_READ), None).is_ok() {
+                opened += 1;
+            }
+            assert!(WindowsService::open("LersNotExist", Some(ServiceAccess::GENERIC_READ), None).is_err());
+            assert!(WindowsService::try_open_with_fallback_access("LersNotExist", ServiceAccess::GENERIC_READ, None).is_err());
+            assert!(ServiceHandle::open("LersNotExist", Some(ServiceAccess::GENERIC_READ)).is_err());
+            if let Ok(mut service) = WindowsService::open("WSearch", Some(ServiceAccess::GENERIC_READ), None) {
+                drop(service.take_manager());
+            }
+        }
+        println!("opened {} of {}", opened, STRESS_ITERATIONS);
+        // 泄漏时每次循环至少多出一个句柄,远超允许的增长
+        let process_after = process_handle_count();
+        println!("process handles: {} -> {}", process_before, process_after);
+        assert!(process_after <= process_before + HANDLE_SLACK);
+        // 只在debug构建中可用
+        if let (Some(before), Some(after)) = (library_before, open_handle_count()) {
+            println!("library handles: {} -> {}", before, after);
+            assert!(after <= before + HANDLE_SLACK);
+        }
+    }
+
+    #[test]
+    fn identity() {
+        let service = |name: &str| WindowsService {
+            name: ServiceName(name.to_string()),
+            service_handle: OwnedScHandle::default(),
+            sc_manager_handle: OwnedScHandle::default(),
+            access: ServiceAccess::SERVICE_ALL_ACCESS,
+            config: RawServiceConfig::default(),
+            _config_buffer: Vec::new(),
+        };
+        assert!(service("Lers") == service("lers"));
+        assert!(service("Lers") != service("LersB"));
+        let mut services = vec![service("Lers"), service("LERS"), service("LersB")];
+        services.dedup();
+        assert_eq!(services.len(), 2);
+        let a = WindowsService::open("WSearch", Some(ServiceAccess::GENERIC_READ), None);
+        // 访问权限不同也是同一个服务;open会查询配置,需要SERVICE_QUERY_CONFIG
+        let b = WindowsService::open(
+            "wsearch",
+            Some(ServiceAccess::SERVICE_QUERY_CONFIG | ServiceAccess::SERVICE_QUERY_STATUS),
+            None,
+        );
+        match (a, b) {
+            (Ok(a), Ok(b)) => assert!(a == b),
+            (Err(e), _) | (_, Err(e)) => println!("{}", e),
+        }
+    }
+
+    #[test]
+    fn query_buffer_retry() {
+        // 第一次调用只返回所需大小,第二次成功
+        let mut calls = 0;
+        let result = query_with_buffer(|buffer, bytes_needed| {
+            calls += 1;
+            if buffer.len() < 16 {
+                *bytes_needed = 16;
+                return Err(ERROR_INSUFFICIENT_BUFFER);
+            }
+            buffer[0] = 1;
+            Ok(())
+        });
+        assert_eq!(calls, 2);
+        assert_eq!(result.unwrap().len(), 16);
+        // 数据在每次调用之间不断变大
+        let mut calls = 0;
+        let result = query_with_buffer(|buffer, bytes_needed| {
+            calls += 1;
+            *bytes_needed = buffer.len() as u32 + 16;
+            Err(ERROR_INSUFFICIENT_BUFFER)
+        });
+        assert_eq!(calls, QUERY_BUFFER_ATTEMPTS);
+        assert_eq!(result, Err(ServiceError::BUFFER_STILL_TOO_SMALL));
+        // 其他错误直接返回
+        let result = query_with_buffer(|_, _| Err(ERROR_ACCESS_DENIED));
+        assert_eq!(result, Err(ServiceError::ERROR_ACCESS_DENIED));
+    }
+
+    #[test]
+    fn open_service() {
+        let service = WindowsService::open("WSearch", Some(ServiceAccess::GENERIC_READ), None);
+        match service {
+            Ok(s) => {
+                println!("{:?}", s.config())
+            }
+            Err(e) => {
+                println!("{}", e)
+            }
+        }
+    }
+
+    #[test]
+    fn null_handle() {
+        let service = WindowsService {
+            name: ServiceName(String::new()),
+            service_handle: OwnedScHandle::default(),
+            sc_manager_handle: OwnedScHandle::default(),
+            access: ServiceAccess::SERVICE_ALL_ACCESS,
+            config: RawServiceConfig::default(),
+            _config_buffer: Vec::new(),
+        };
+        assert_eq!(service.query_service_status().err(), Some(ServiceError::ERROR_INVALID_HANDLE));
+        assert_eq!(service.query_status_ex().err(), Some(ServiceError::ERROR_INVALID_HANDLE));
+        assert_eq!(service.start_service(), Err(ServiceError::ERROR_INVALID_HANDLE));
+        assert_eq!(service.stop_service(), Err(ServiceError::ERROR_INVALID_HANDLE));
+        assert_eq!(service.delete_service(), Err(ServiceError::ERROR_INVALID_HANDLE));
+        assert_eq!(service.shutdown(), Err(ServiceError::ERROR_INVALID_HANDLE));
+        assert!(service.watch(|_| {}).is_err());
+        let mut service = service;
+        assert_eq!(service.close_all(), Ok(()));
+        assert_eq!(
+            service.wait_while_pending(std::time::Duration::from_secs(1)).err(),
+            Some(ServiceError::ERROR_INVALID_HANDLE)
+        );
+    }
+
+    /// 持有非空假句柄的服务,只用于通过空句柄检查,权限检查失败时句柄不会被使用。
+    /// 它不是真正打开的句柄,用ManuallyDrop避免断言失败时drop关闭它
+    fn fake_service(access: ServiceAccess) -> ManuallyDrop<WindowsService> {
+        ManuallyDrop::new(WindowsService {
+            name: ServiceName("Lers".to_string()),
+            service_handle: OwnedScHandle::new(SC_HANDLE(std::ptr::dangling_mut())),
+            sc_manager_handle: OwnedScHandle::default(),
+            access,
+            config: RawServiceConfig::default(),
+            _config_buffer: Vec::new(),
+        })
+    }
+
+    #[test]
+    fn query_config_and_status_access() {
+        let mut service = fake_service(ServiceAccess::SERVICE_QUERY_CONFIG);
+        assert_eq!(
+            service.query_config_and_status().err(),
+            Some(ServiceError::INSUFFICIENT_ACCESS_SERVICE_QUERY_STATUS)
+        );
+        service.access = ServiceAccess::SERVICE_QUERY_STATUS;
+        let error = service.query_config_and_status().err().unwrap();
+        assert_eq!(error, ServiceError::INSUFFICIENT_ACCESS_SERVICE_QUERY_CONFIG);
+        assert!(!error.to_string().contains("未知错误"));
+    }
+
+    #[test]
+    fn update_config_access() {
+        // 没有SERVICE_QUERY_CONFIG时在修改前失败,不会修改成功后才报错
+        let mut service = fake_service(ServiceAccess::SERVICE_CHANGE_CONFIG);
+        let config = service.config();
+        assert_eq!(
+            service.update_service_config(&config, None).err(),
+            Some(ServiceError::INSUFFICIENT_ACCESS_SERVICE_QUERY_CONFIG)
+        );
+        let change = ConfigChange { display_name: Some("Lers".to_string()), ..Default::default() };
+        assert_eq!(service.apply_config_change(change).err(), Some(ServiceError::INSUFFICIENT_ACCESS_SERVICE_QUERY_CONFIG));
+        assert_eq!(
+            service.set_dependencies(&[Dependency::Service("RpcSs".to_string())]).err(),
+            Some(ServiceError::INSUFFICIENT_ACCESS_SERVICE_QUERY_CONFIG)
+        );
+        assert_eq!(service.set_start_arguments(&["--v2"]).err(), Some(ServiceError::INSUFFICIENT_ACCESS_SERVICE_QUERY_CONFIG));
+    }
+
+    #[test]
+    fn query_status_ex() {
+        let service = WindowsService::open("WSearch", Some(ServiceAccess::GENERIC_READ), None);
+        match service {
+            Ok(s) => match s.query_status_ex() {
+                Ok(status) => {
+                    println!("{:?}", status.service_flags());
+                    // WSearch运行在自己的SearchIndexer.exe进程中
+                    assert!(!status.runs_in_system_process());
+                    assert_eq!(status.service_type(), s.config().service_type);
+                    if status.current_state() == ServiceStatus::SERVICE_STOPPED {
+                        assert_eq!(status.process_id(), 0);
+                    }
+                }
+                Err(e) => {
+                    println!("{}", e)
+                }
+            },
+            Err(e) => {
+                println!("{}", e)
+            }
+        }
+    }
+
+    #[test]
+    fn watch_service() {
+        let service = WindowsService::open("WSearch", Some(ServiceAccess::GENERIC_READ), None);
+        match service {
+            Ok(s) => match s.watch(|status| println!("{}", status.current_state())) {
+                Ok(handle) => {
+                    std::thread::sleep(std::time::Duration::from_secs(1));
+                    handle.stop()
+                }
+                Err(e) => {
+                    println!("{}", e)
+                }
+            },
+            Err(e) => {
+                println!("{}", e)
+            }
+        }
+    }
+
+    #[test]
+    fn watch_service_channel() {
+        let service = WindowsService::open("WSearch", Some(ServiceAccess::GENERIC_READ), None);
+        match service {
+            Ok(s) => match s.watch_channel(std::time::Duration::from_millis(100)) {
+                Ok(receiver) => match receiver.recv_timeout(std::time::Duration::from_secs(1)) {
+                    Ok(status) => println!("{}", status.current_state()),
+                    Err(e) => println!("{}", e),
+                },
+                Err(e) => {
+                    println!("{}", e)
+                }
+            },
+            Err(e) => {
+                println!("{}", e)
+            }
+        }
+    }
+
+    #[test]
+    fn close_all() {
+        let service = WindowsService::open("WSearch", Some(ServiceAccess::GENERIC_READ), None);
+        match service {
+            Ok(mut s) => {
+                match s.close_all() {
+                    Ok(_) => {
+                        println!("succeed")
+                    }
+                    Err(e) => {
+                        println!("{}", e)
+                    }
                 }
+                assert_eq!(s.query_service_status().err(), Some(ServiceError::ERROR_INVALID_HANDLE));
+            }
+            Err(e) => {
+                println!("{}", e)
+            }
+        }
+    }
+
+    #[test]
+    fn open_with_fallback_access() {
+        let service = WindowsService::try_open_with_fallback_access("WSearch", ServiceAccess::SERVICE_ALL_ACCESS, None);
+        match service {
+            Ok(s) => {
+                assert!(s.access().contains(ServiceAccess::GENERIC_READ));
+                println!("{:?}", s.access())
+            }
+            Err(e) => {
+                println!("{}", e)
+            }
+        }
+    }
+
+    #[test]
+    fn insufficient_access() {
+        let service = WindowsService::open("WSearch", Some(ServiceAccess::GENERIC_READ), None);
+        match service {
+            Ok(s) => {
+                assert!(s.access().contains(ServiceAccess::SERVICE_QUERY_STATUS));
+                assert!(!s.access().contains(ServiceAccess::SERVICE_STOP));
+                assert_eq!(s.stop_service(), Err(ServiceError::INSUFFICIENT_ACCESS_SERVICE_STOP));
+                assert_eq!(s.delete_service(), Err(ServiceError::INSUFFICIENT_ACCESS_DELETE));
+            }
+            Err(e) => {
+                println!("{}", e)
             }
         }
     }
-}
-
-#[cfg(test)]
-mod test {
-    use lers_windows_macro::PWSTR;
-
-    use crate::dword::{ScManagerAccess, ServiceAccess, ServiceErrorControl, ServiceStartType, ServiceType};
-    use crate::WindowsService;
 
     #[test]
-    fn open_service() {
+    fn query_config2_raw() {
         let service = WindowsService::open("WSearch", Some(ServiceAccess::GENERIC_READ), None);
         match service {
-            Ok(s) => {
-                println!("{:?}", s.config)
-            }
+            Ok(s) => match s.query_config2_raw(ServiceConfigInfoLevel::SERVICE_CONFIG_PRESHUTDOWN_INFO) {
+                Ok(data) => {
+                    assert!(data.len() >= 4);
+                    println!("{:?}", data)
+                }
+                Err(e) => {
+                    println!("{}", e)
+                }
+            },
             Err(e) => {
                 println!("{}", e)
             }
@@ -366,7 +2455,7 @@ mod test {
         );
         match service {
             Ok(s) => {
-                println!("{:?}", s.config)
+                println!("{:?}", s.config())
             }
             Err(e) => {
                 println!("{}", e)
@@ -374,6 +2463,25 @@ mod test {
         }
     }
 
+    #[test]
+    fn create_service_name_too_long() {
+        // 200个字符,但每个字符占两个UTF-16单元,共400个
+        let name = "\u{1D11E}".repeat(200);
+        assert!(name.chars().count() <= 256);
+        let service = WindowsService::new(
+            &name,
+            None,
+            None,
+            None,
+            ServiceType::SERVICE_WIN32_OWN_PROCESS,
+            ServiceStartType::SERVICE_DEMAND_START,
+            ServiceErrorControl::SERVICE_ERROR_NORMAL,
+            "C:\\WINDOWS\\system32\\cmd.exe",
+            None,
+        );
+        assert_eq!(service.err(), Some(ServiceError::ERROR_INVALID_NAME));
+    }
+
     #[test]
     fn delete_service() {
         let service = WindowsService::open("Lers", None, None);
@@ -397,8 +2505,9 @@ mod test {
         let service = WindowsService::open("Lers", None, None);
         match service {
             Ok(mut s) => {
-                s.config.lpDisplayName = PWSTR!("lers test");
-                match s.update_service_config(None) {
+                let mut config = s.config();
+                config.display_name = "lers test".to_string();
+                match s.update_service_config(&config, None) {
                     Ok(_) => {
                         println!("succeed")
                     }
@@ -433,6 +2542,369 @@ mod test {
         }
     }
 
+    #[test]
+    fn start_service_os_args() {
+        let service = WindowsService::open("InstallService", None, None);
+        match service {
+            Ok(s) => {
+                match s.start_os(&[std::ffi::OsStr::new("--config"), std::ffi::OsStr::new("C:\\Lers\\lers.toml")]) {
+                    Ok(_) => {
+                        println!("succeed")
+                    }
+                    Err(e) => {
+                        println!("{}", e);
+                    }
+                }
+            }
+            Err(e) => {
+                println!("{}", e);
+            }
+        }
+    }
+
+    #[test]
+    fn failure_reboot_message() {
+        match WindowsService::open("Lers", None, None) {
+            Ok(s) => {
+                match s.set_failure_reboot_message(Some("Lers")) {
+                    Ok(_) => {
+                        println!("{:?}", s.failure_reboot_message());
+                        let _ = s.set_failure_reboot_message(None);
+                    }
+                    Err(e) => {
+                        println!("{}", e);
+                    }
+                }
+            }
+            Err(e) => {
+                println!("{}", e);
+            }
+        }
+    }
+
+    #[test]
+    fn effective_start_behavior() {
+        match WindowsService::open("W32Time", Some(ServiceAccess::GENERIC_READ), None) {
+            Ok(s) => match s.effective_start_behavior() {
+                Ok(behavior) => println!("{:?}", behavior),
+                Err(e) => println!("{}", e),
+            },
+            Err(e) => {
+                println!("{}", e);
+            }
+        }
+    }
+
+    #[test]
+    fn failure_command() {
+        match WindowsService::open("WSearch", Some(ServiceAccess::GENERIC_READ), None) {
+            Ok(s) => match s.failure_command() {
+                Ok(command) => println!("{:?}", command),
+                Err(e) => println!("{}", e),
+            },
+            Err(e) => {
+                println!("{}", e);
+            }
+        }
+    }
+
+    #[test]
+    fn reads_reuse_handle() {
+        match WindowsService::open("WSearch", Some(ServiceAccess::GENERIC_READ), None) {
+            Ok(s) => {
+                let opens = handles_opened_on_thread();
+                for _ in 0..3 {
+                    let _ = s.query_service_status();
+                    let _ = s.query_status_ex();
+                    let _ = s.query_config_and_status();
+                    let _ = s.query_config_text();
+                }
+                assert_eq!(handles_opened_on_thread(), opens);
+            }
+            Err(e) => {
+                println!("{}", e);
+            }
+        }
+    }
+
+    #[test]
+    fn netbind_change() {
+        match WindowsService::open("Dnscache", Some(ServiceAccess::GENERIC_READ | ServiceAccess::SERVICE_PAUSE_CONTINUE), None) {
+            Ok(s) => {
+                match s.netbind_enable() {
+                    Ok(_) => {
+                        println!("succeed")
+                    }
+                    Err(e) => {
+                        println!("{}", e);
+                    }
+                }
+            }
+            Err(e) => {
+                println!("{}", e);
+            }
+        }
+    }
+
+    #[test]
+    fn open_for_delete() {
+        match WindowsService::open_for_delete("LersDelete") {
+            Ok(s) => {
+                assert_eq!(s.query_service_status().err(), Some(ServiceError::INSUFFICIENT_ACCESS_SERVICE_QUERY_STATUS));
+                match s.delete_service() {
+                    Ok(_) => {
+                        println!("succeed")
+                    }
+                    Err(e) => {
+                        println!("{}", e);
+                    }
+                }
+            }
+            Err(e) => {
+                println!("{}", e);
+            }
+        }
+    }
+
+    #[test]
+    fn elevated() {
+        match is_elevated() {
+            Ok(elevated) => println!("elevated: {}", elevated),
+            Err(e) => println!("{}", e),
+        }
+    }
+
+    #[test]
+    fn open_with_scm() {
+        let manager = match ServiceManager::open(Some(ScManagerAccess::SC_MANAGER_CONNECT)) {
+            Ok(manager) => manager,
+            Err(e) => {
+                println!("{}", e);
+                return;
+            }
+        };
+        let opens = handles_opened_on_thread();
+        for name in ["WSearch", "Dnscache"] {
+            match WindowsService::open_with_scm(&manager, name, Some(ServiceAccess::GENERIC_READ)) {
+                Ok(s) => println!("{}", s.config().display_name),
+                Err(e) => println!("{}", e),
+            }
+        }
+        // 复用管理器的SCM句柄,每个服务最多打开一个服务句柄
+        let opened = handles_opened_on_thread().zip(opens).map(|(after, before)| after - before);
+        assert!(opened.is_none_or(|opened| opened <= 2));
+    }
+
+    #[cfg(feature = "eventlog")]
+    #[test]
+    fn recent_failures() {
+        match WindowsService::open("WSearch", Some(ServiceAccess::GENERIC_READ), None) {
+            Ok(s) => match s.recent_failures(5) {
+                Ok(failures) => {
+                    assert!(failures.len() <= 5);
+                    for failure in failures {
+                        println!("{:?}", failure);
+                    }
+                }
+                Err(e) => println!("{}", e),
+            },
+            Err(e) => {
+                println!("{}", e);
+            }
+        }
+    }
+
+    #[test]
+    fn clear_failure_actions() {
+        match WindowsService::open("Lers", None, None) {
+            Ok(s) => {
+                let actions = FailureActions::auto_restart(Duration::from_secs(10), Duration::from_secs(86400), None).unwrap();
+                match s.set_failure_actions(&actions).and_then(|_| s.set_failure_actions(&FailureActions::default())) {
+                    Ok(_) => assert!(s.failure_actions().unwrap().actions.is_empty()),
+                    Err(e) => println!("{}", e),
+                }
+            }
+            Err(e) => {
+                println!("{}", e);
+            }
+        }
+    }
+
+    #[test]
+    fn set_auto_restart() {
+        match WindowsService::open("Lers", None, None) {
+            Ok(s) => {
+                assert_eq!(
+                    s.set_auto_restart(Duration::from_secs(10), Duration::from_secs(86400), Some(u32::MAX)),
+                    Err(ServiceError::ERROR_INVALID_PARAMETER)
+                );
+                match s.set_auto_restart(Duration::from_secs(10), Duration::from_secs(86400), Some(3)) {
+                    Ok(_) => {
+                        println!("{:?}", s.failure_actions());
+                    }
+                    Err(e) => {
+                        println!("{}", e);
+                    }
+                }
+            }
+            Err(e) => {
+                println!("{}", e);
+            }
+        }
+    }
+
+    #[test]
+    fn set_dependencies() {
+        let invalid = [Dependency::Service("+RpcSs".to_string())];
+        match WindowsService::open("Lers", None, None) {
+            Ok(mut s) => {
+                assert_eq!(s.set_dependencies(&invalid), Err(ServiceError::ERROR_INVALID_PARAMETER));
+                let dependencies = [Dependency::Service("RpcSs".to_string()), Dependency::Group("NetworkProvider".to_string())];
+                match s.set_dependencies(&dependencies) {
+                    Ok(_) => assert_eq!(s.dependencies(), dependencies),
+                    Err(e) => println!("{}", e),
+                }
+            }
+            Err(e) => {
+                println!("{}", e);
+            }
+        }
+    }
+
+    #[test]
+    fn is_critical() {
+        for name in ["RpcSs", "WSearch"] {
+            match WindowsService::open(name, Some(ServiceAccess::GENERIC_READ), None) {
+                Ok(s) => match s.is_critical() {
+                    Ok(critical) => println!("{}: {}", name, critical),
+                    Err(e) => println!("{}", e),
+                },
+                Err(e) => {
+                    println!("{}", e);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn launch_protected() {
+        match WindowsService::open("WinDefend", Some(ServiceAccess::GENERIC_READ), None) {
+            Ok(s) => match s.launch_protected() {
+                Ok(level) => {
+                    assert_eq!(s.is_protected().ok(), Some(level != LaunchProtected::SERVICE_LAUNCH_PROTECTED_NONE));
+                    println!("{:?}", level);
+                }
+                Err(e) => println!("{}", e),
+            },
+            Err(e) => {
+                println!("{}", e);
+            }
+        }
+    }
+
+    #[test]
+    fn control_and_wait() {
+        match WindowsService::open("Lers", None, None) {
+            Ok(s) => {
+                let code = ServiceControlCode::SERVICE_CONTROL_STOP;
+                let expected = code.expected_state().unwrap();
+                match s.control_and_wait(code, expected, Duration::from_secs(30)) {
+                    Ok(state) => assert_eq!(state, ServiceStatus::SERVICE_STOPPED),
+                    Err(e) => println!("{}", e),
+                }
+            }
+            Err(e) => {
+                println!("{}", e);
+            }
+        }
+    }
+
+    #[test]
+    fn enumerate_dependents() {
+        match WindowsService::open("RpcSs", Some(ServiceAccess::GENERIC_READ), None) {
+            Ok(s) => match s.enumerate_dependents(true) {
+                Ok(dependents) => {
+                    for info in dependents {
+                        println!("{}\t{}", info.service_name, info.status.current_state());
+                    }
+                }
+                Err(e) => println!("{}", e),
+            },
+            Err(e) => {
+                println!("{}", e);
+            }
+        }
+    }
+
+    #[test]
+    fn wait_for_ready() {
+        match WindowsService::open("Dnscache", Some(ServiceAccess::GENERIC_READ), None) {
+            Ok(s) => match s.wait_for_ready(Duration::from_secs(5)) {
+                Ok(status) => {
+                    assert_eq!(status.current_state(), ServiceStatus::SERVICE_RUNNING);
+                    println!("{} {}", status.process_id(), status.runs_in_system_process());
+                }
+                Err(e) => println!("{}", e),
+            },
+            Err(e) => {
+                println!("{}", e);
+            }
+        }
+    }
+
+    #[test]
+    fn take_manager() {
+        match WindowsService::open("WSearch", Some(ServiceAccess::GENERIC_READ), None) {
+            Ok(mut s) => {
+                let manager = s.take_manager();
+                assert!(manager.is_some());
+                assert!(s.take_manager().is_none());
+                let opens = handles_opened_on_thread();
+                match WindowsService::open_with_scm(&manager.unwrap(), "Dnscache", Some(ServiceAccess::GENERIC_READ)) {
+                    Ok(sibling) => println!("{:?}", sibling.query_service_status()),
+                    Err(e) => println!("{}", e),
+                }
+                // 复用取出的SCM句柄,只打开了服务句柄
+                let opened = handles_opened_on_thread().zip(opens).map(|(after, before)| after - before);
+                assert!(opened.is_none_or(|opened| opened <= 1));
+                println!("{:?}", s.query_service_status());
+            }
+            Err(e) => {
+                println!("{}", e);
+            }
+        }
+    }
+
+    #[test]
+    fn shared_process_host() {
+        match WindowsService::open("Dnscache", Some(ServiceAccess::GENERIC_READ), None) {
+            Ok(s) => {
+                assert_eq!(s.name(), "Dnscache");
+                println!("{} {:?}", s.is_shared_process(), s.host_process_name());
+            }
+            Err(e) => {
+                println!("{}", e);
+            }
+        }
+    }
+
+    #[test]
+    fn toggle() {
+        match WindowsService::open("Lers", None, None) {
+            Ok(s) => match s.toggle(Duration::from_secs(30)) {
+                Ok(state) => {
+                    println!("{}", state);
+                }
+                Err(e) => {
+                    println!("{}", e);
+                }
+            },
+            Err(e) => {
+                println!("{}", e);
+            }
+        }
+    }
+
     #[test]
     fn stop_service() {
         let service = WindowsService::open("InstallService", None, None);
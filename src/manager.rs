@@ -0,0 +1,363 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use windows::Win32::System::Services::SC_HANDLE;
+
+use crate::builder::ServiceBuilder;
+use crate::config::ServiceConfig;
+use crate::dword::{ScManagerAccess, ServiceAccess, ServiceError};
+use crate::handle::OwnedScHandle;
+use crate::{validate_service_name, WindowsService};
+
+/// 活动的服务数据库,OpenSCManagerW未指定数据库时打开的就是它
+pub const SERVICES_ACTIVE_DATABASE: &str = "ServicesActive";
+
+/// 服务控制管理器
+/// 只打开一次SCM,在同一个句柄上完成多个服务的操作
+/// ## 例子
+/// ```
+/// use windows_service_controller::builder::ServiceBuilder;
+/// use windows_service_controller::dword::ServiceStartType;
+/// use windows_service_controller::manager::ServiceManager;
+/// let manager = ServiceManager::open(None).unwrap();
+/// for name in ["LersA", "LersB"] {
+///     let spec = ServiceBuilder::new(name, "C:\\Lers\\lers.exe").start_type(ServiceStartType::SERVICE_AUTO_START);
+///     manager.install_and_start(&spec).unwrap();
+/// }
+/// ```
+pub struct ServiceManager {
+    handle: OwnedScHandle,
+    /// 打开的服务数据库名称
+    database: String,
+    cache: Option<Mutex<ConfigCache>>,
+}
+
+/// 按服务名称缓存的服务配置
+/// 只缓存配置而不缓存服务句柄:打开的句柄会让被标记删除的服务一直无法真正删除
+struct ConfigCache {
+    capacity: usize,
+    ttl: Duration,
+    /// 键为小写的服务名称(SCM不区分大小写),值为缓存时间和配置
+    entries: HashMap<String, (Instant, ServiceConfig)>,
+}
+
+impl ConfigCache {
+    fn new(capacity: usize, ttl: Duration) -> ConfigCache {
+        ConfigCache { capacity, ttl, entries: HashMap::new() }
+    }
+
+    /// 取出未过期的配置,过期的条目会被移除
+    fn get(&mut self, name: &str, now: Instant) -> Option<ServiceConfig> {
+        let key = name.to_lowercase();
+        match self.entries.get(&key) {
+            Some((cached_at, config)) if now.duration_since(*cached_at) < self.ttl => Some(config.clone()),
+            Some(_) => {
+                self.entries.remove(&key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// 缓存配置,已满时先移除过期的条目,仍然已满时移除最早缓存的条目
+    fn insert(&mut self, name: &str, config: ServiceConfig, now: Instant) {
+        if self.capacity == 0 {
+            return;
+        }
+        let key = name.to_lowercase();
+        if !self.entries.contains_key(&key) && self.entries.len() >= self.capacity {
+            let ttl = self.ttl;
+            self.entries.retain(|_, (cached_at, _)| now.duration_since(*cached_at) < ttl);
+            if self.entries.len() >= self.capacity {
+                let oldest = self.entries.iter().min_by_key(|(_, (cached_at, _))| *cached_at).map(|(k, _)| k.clone());
+                if let Some(oldest) = oldest {
+                    self.entries.remove(&oldest);
+                }
+            }
+        }
+        self.entries.insert(key, (now, config));
+    }
+
+    fn invalidate(&mut self, name: &str) {
+        self.entries.remove(&name.to_lowercase());
+    }
+}
+
+impl ServiceManager {
+    /// # 打开服务控制管理器
+    /// ## 参数
+    /// ### input:
+    /// - access: 默认为SC_MANAGER_CONNECT|SC_MANAGER_CREATE_SERVICE
+    /// ### output:
+    /// - Result<ServiceManager,ServiceError>
+    pub fn open(access: Option<ScManagerAccess>) -> Result<ServiceManager, ServiceError> {
+        let handle = WindowsService::open_sc_manager(access.unwrap_or_else(|| {
+            ScManagerAccess::SC_MANAGER_CONNECT | ScManagerAccess::SC_MANAGER_CREATE_SERVICE
+        }))?;
+        Ok(ServiceManager::from_handle(handle))
+    }
+
+    /// # 打开指定数据库的服务控制管理器
+    /// 目前Windows只支持活动数据库SERVICES_ACTIVE_DATABASE(名称不区分大小写),
+    /// 其他名称返回ERROR_INVALID_NAME或ERROR_DATABASE_DOES_NOT_EXIST。打开后可以通过database_name确认
+    /// ## 参数
+    /// ### input:
+    /// - database: 数据库名称
+    /// - access: 默认为SC_MANAGER_CONNECT|SC_MANAGER_CREATE_SERVICE
+    /// ### output:
+    /// - Result<ServiceManager,ServiceError>
+    /// ## 例子
+    /// ```
+    /// use windows_service_controller::manager::{ServiceManager, SERVICES_ACTIVE_DATABASE};
+    /// let manager = ServiceManager::open_database(SERVICES_ACTIVE_DATABASE, None).unwrap();
+    /// assert_eq!(manager.database_name(), SERVICES_ACTIVE_DATABASE);
+    /// ```
+    pub fn open_database(database: &str, access: Option<ScManagerAccess>) -> Result<ServiceManager, ServiceError> {
+        if database.contains('\0') {
+            return Err(ServiceError::ERROR_INVALID_NAME);
+        }
+        let handle = WindowsService::open_sc_manager_database(
+            Some(database),
+            access.unwrap_or(ScManagerAccess::SC_MANAGER_CONNECT | ScManagerAccess::SC_MANAGER_CREATE_SERVICE),
+        )?;
+        let mut manager = ServiceManager::from_handle(handle);
+        manager.database = database.to_string();
+        Ok(manager)
+    }
+
+    /// 接管已打开的活动数据库的SCM句柄,之后由ServiceManager负责关闭
+    pub(crate) fn from_handle(handle: SC_HANDLE) -> ServiceManager {
+        ServiceManager {
+            handle: OwnedScHandle::new(handle),
+            database: SERVICES_ACTIVE_DATABASE.to_string(),
+            cache: None,
+        }
+    }
+
+    /// # 打开的服务数据库名称
+    /// 通过open、WindowsService::take_manager得到的管理器为SERVICES_ACTIVE_DATABASE,
+    /// 通过open_database得到的管理器为打开时传入的名称
+    pub fn database_name(&self) -> &str {
+        &self.database
+    }
+
+    /// # 启用服务配置缓存
+    /// 启用后query_config会按服务名称缓存查询到的配置,在ttl内重复查询同一服务时不再打开服务和调用QueryServiceConfigW。
+    /// 通过本管理器修改服务(update_config、install_and_start)时会使对应服务的缓存失效;
+    /// 其他途径(其他WindowsService实例、其他进程、sc.exe)的修改只有在缓存过期或调用invalidate后才能看到,
+    /// ttl应按能够接受的过期时间设置
+    /// ## 参数
+    /// ### input:
+    /// - capacity: 最多缓存的服务数量,已满时移除最早缓存的配置;为0时不缓存
+    /// - ttl: 缓存的有效时间
+    /// ### output:
+    /// - ServiceManager
+    /// ## 例子
+    /// ```
+    /// use std::time::Duration;
+    /// use windows_service_controller::manager::ServiceManager;
+    /// let manager = ServiceManager::open(None).unwrap().with_config_cache(256, Duration::from_secs(30));
+    /// let config = manager.query_config("WSearch").unwrap();
+    /// // 30秒内不再查询SCM
+    /// let cached = manager.query_config("WSearch").unwrap();
+    /// assert_eq!(config.binary_path, cached.binary_path);
+    /// ```
+    pub fn with_config_cache(mut self, capacity: usize, ttl: Duration) -> ServiceManager {
+        self.cache = Some(Mutex::new(ConfigCache::new(capacity, ttl)));
+        self
+    }
+
+    /// # 查询服务配置
+    /// 以SERVICE_QUERY_CONFIG权限打开服务查询配置后立即关闭;启用了配置缓存时优先返回未过期的缓存
+    /// ## 参数
+    /// ### input:
+    /// - name: 服务名称(不是显示名称)
+    /// ### output:
+    /// - Result<ServiceConfig,ServiceError>
+    pub fn query_config(&self, name: &str) -> Result<ServiceConfig, ServiceError> {
+        validate_service_name(name)?;
+        if let Some(config) = self.with_cache(|cache| cache.get(name, Instant::now())).flatten() {
+            return Ok(config);
+        }
+        let service_handle =
+            OwnedScHandle::new(WindowsService::open_service(self.handle(), name, ServiceAccess::SERVICE_QUERY_CONFIG)?);
+        let config = WindowsService::get_config(service_handle.get());
+        drop(service_handle);
+        // 在缓冲区释放前复制出字符串
+        let config = config.map(|(config, _buffer)| ServiceConfig::from_raw(&config))?;
+        self.with_cache(|cache| cache.insert(name, config.clone(), Instant::now()));
+        Ok(config)
+    }
+
+    /// # 通过管理器修改服务配置
+    /// 与WindowsService::update_service_config相同,无论成功与否都会使该服务的缓存失效(修改可能只完成了一部分)
+    /// ## 参数
+    /// ### input:
+    /// - name: 服务名称(不是显示名称)
+    /// - config: 新的服务配置,一般由query_config获取后修改
+    /// - passwd: 修改服务密码,不修改请传入None
+    /// ### output:
+    /// - Result<(),ServiceError>
+    #[must_use = "修改配置可能失败,应当检查返回的结果"]
+    pub fn update_config(&self, name: &str, config: &ServiceConfig, passwd: Option<&str>) -> Result<(), ServiceError> {
+        let result = WindowsService::open_with_scm(
+            self,
+            name,
+            Some(ServiceAccess::SERVICE_QUERY_CONFIG | ServiceAccess::SERVICE_CHANGE_CONFIG),
+        )
+        .and_then(|mut service| service.update_service_config(config, passwd));
+        self.invalidate(name);
+        result
+    }
+
+    /// # 使服务的缓存失效
+    /// 服务在本管理器之外被修改后调用,下次query_config会重新查询。没有启用缓存时不做任何事
+    /// ## 参数
+    /// ### input:
+    /// - name: 服务名称(不是显示名称)
+    pub fn invalidate(&self, name: &str) {
+        self.with_cache(|cache| cache.invalidate(name));
+    }
+
+    /// # 清空配置缓存
+    pub fn clear_cache(&self) {
+        self.with_cache(|cache| cache.entries.clear());
+    }
+
+    /// 在启用了缓存时对缓存执行f,没有启用时返回None
+    fn with_cache<T>(&self, f: impl FnOnce(&mut ConfigCache) -> T) -> Option<T> {
+        // 缓存只保存查询结果,持有锁的线程panic后缓存仍然可用
+        self.cache.as_ref().map(|cache| f(&mut cache.lock().unwrap_or_else(|e| e.into_inner())))
+    }
+
+    /// SCM句柄,仍由ServiceManager持有
+    pub(crate) fn handle(&self) -> SC_HANDLE {
+        self.handle.get()
+    }
+
+    /// # 创建服务并立即启动
+    /// 使用已打开的SCM句柄创建服务后启动,启动失败时删除刚创建的服务并返回启动的错误。
+    /// 回滚需要服务句柄具有DELETE权限(构建器默认的SERVICE_ALL_ACCESS包含该权限),
+    /// 删除也失败时返回SERVICE_CREATED_NOT_STARTED,此时服务仍然存在
+    /// ## 参数
+    /// ### input:
+    /// - spec: 服务构建器
+    /// ### output:
+    /// - Result<WindowsService,ServiceError>
+    #[must_use = "创建或启动服务可能失败,应当检查返回的结果"]
+    pub fn install_and_start(&self, spec: &ServiceBuilder) -> Result<WindowsService, ServiceError> {
+        let service = spec.create_in(self.handle())?;
+        // 同名服务可能在之前被删除时留下了缓存
+        self.invalidate(service.name());
+        match service.start_service() {
+            Ok(_) => Ok(service),
+            Err(e) => match service.delete_service() {
+                Ok(_) => Err(e),
+                Err(_) => Err(ServiceError::SERVICE_CREATED_NOT_STARTED),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, Instant};
+
+    use crate::builder::ServiceBuilder;
+    use crate::config::ServiceConfig;
+    use crate::dword::{ScManagerAccess, ServiceError};
+    use crate::manager::{ConfigCache, ServiceManager, SERVICES_ACTIVE_DATABASE};
+    use crate::{RawServiceConfig, WindowsService};
+
+    fn config(display_name: &str) -> ServiceConfig {
+        let mut config = ServiceConfig::from_raw(&RawServiceConfig::default());
+        config.display_name = display_name.to_string();
+        config
+    }
+
+    #[test]
+    fn config_cache() {
+        let now = Instant::now();
+        let mut cache = ConfigCache::new(2, Duration::from_secs(10));
+        cache.insert("WSearch", config("a"), now);
+        assert_eq!(cache.get("wsearch", now).unwrap().display_name, "a");
+        // 过期后移除
+        assert!(cache.get("WSearch", now + Duration::from_secs(10)).is_none());
+        assert!(cache.entries.is_empty());
+        // 已满时移除最早缓存的条目
+        cache.insert("A", config("a"), now);
+        cache.insert("B", config("b"), now + Duration::from_secs(1));
+        cache.insert("C", config("c"), now + Duration::from_secs(2));
+        assert!(cache.get("A", now + Duration::from_secs(2)).is_none());
+        assert_eq!(cache.get("B", now + Duration::from_secs(2)).unwrap().display_name, "b");
+        // 更新已有的条目不会移除其他条目
+        cache.insert("c", config("c2"), now + Duration::from_secs(3));
+        assert_eq!(cache.entries.len(), 2);
+        assert_eq!(cache.get("C", now + Duration::from_secs(3)).unwrap().display_name, "c2");
+        cache.invalidate("b");
+        assert!(cache.get("B", now + Duration::from_secs(3)).is_none());
+        let mut disabled = ConfigCache::new(0, Duration::from_secs(10));
+        disabled.insert("A", config("a"), now);
+        assert!(disabled.get("A", now).is_none());
+    }
+
+    #[test]
+    fn cached_query_config() {
+        let manager = match ServiceManager::open(None) {
+            Ok(manager) => manager.with_config_cache(16, Duration::from_secs(30)),
+            Err(e) => {
+                println!("{}", e);
+                return;
+            }
+        };
+        match manager.query_config("WSearch") {
+            Ok(config) => {
+                let cached = manager.query_config("wsearch").unwrap();
+                assert_eq!(config.binary_path, cached.binary_path);
+                manager.invalidate("WSearch");
+                println!("{}", config.binary_path);
+            }
+            Err(e) => println!("{}", e),
+        }
+    }
+
+    #[test]
+    fn install_and_start_rollback() {
+        let manager = match ServiceManager::open(None) {
+            Ok(manager) => manager,
+            Err(e) => {
+                println!("{}", e);
+                return;
+            }
+        };
+        // cmd.exe不是服务程序,启动会失败,服务应当被删除
+        let spec = ServiceBuilder::new("LersRollback", "C:\\WINDOWS\\system32\\cmd.exe");
+        match manager.install_and_start(&spec) {
+            Ok(_) => {
+                println!("succeed")
+            }
+            Err(e) => {
+                println!("{}", e);
+                assert!(WindowsService::open("LersRollback", None, None).is_err());
+            }
+        }
+    }
+
+    #[test]
+    fn database_name() {
+        assert_eq!(ServiceManager::open_database("Lers\0", None).err(), Some(ServiceError::ERROR_INVALID_NAME));
+        match ServiceManager::open_database(SERVICES_ACTIVE_DATABASE, Some(ScManagerAccess::SC_MANAGER_CONNECT)) {
+            Ok(manager) => assert_eq!(manager.database_name(), SERVICES_ACTIVE_DATABASE),
+            Err(e) => println!("{}", e),
+        }
+        match ServiceManager::open(Some(ScManagerAccess::SC_MANAGER_CONNECT)) {
+            Ok(manager) => assert_eq!(manager.database_name(), SERVICES_ACTIVE_DATABASE),
+            Err(e) => println!("{}", e),
+        }
+        match ServiceManager::open_database("LersDatabase", Some(ScManagerAccess::SC_MANAGER_CONNECT)) {
+            Ok(manager) => println!("{}", manager.database_name()),
+            Err(e) => println!("{}", e),
+        }
+    }
+}
@@ -0,0 +1,204 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+use windows::Win32::System::Services::{SC_HANDLE, SERVICE_DRIVER, SERVICE_WIN32};
+
+use crate::config::Dependency;
+use crate::dword::{ScManagerAccess, ServiceAccess, ServiceError};
+use crate::enumerate::enum_services_status;
+use crate::registry::read_multi_sz;
+use crate::handle::OwnedScHandle;
+use crate::{multi_sz_to_vec, wide_to_string, WindowsService};
+
+/// 服务组的启动顺序所在的注册表项
+const SERVICE_GROUP_ORDER_KEY: &str = "SYSTEM\\CurrentControlSet\\Control\\ServiceGroupOrder";
+
+/// 参与启动顺序计算的服务
+#[derive(Debug, Clone)]
+pub(crate) struct StartNode {
+    pub(crate) name: String,
+    pub(crate) start_type: u32,
+    pub(crate) group: String,
+    pub(crate) dependencies: Vec<String>,
+}
+
+/// # 计算开机时服务的启动顺序
+/// 对所有启动类型为BOOT_START、SYSTEM_START、AUTO_START的服务和驱动进行拓扑排序:
+/// - 依赖项(包括以'+'开头的服务组依赖)总是排在依赖它的服务之前
+/// - 没有依赖关系约束时,按启动类型、ServiceGroupOrder中服务组的顺序、服务名称排序
+///
+/// 结果是SCM启动服务顺序的近似,可用于诊断开机缓慢和启动顺序问题。
+/// 需要逐个查询服务配置,无法查询配置的服务会被忽略
+/// ## 参数
+/// ### output:
+/// - Result<Vec<String>,ServiceError>: 按启动顺序排列的服务名称,存在循环依赖时返回ERROR_CIRCULAR_DEPENDENCY
+/// ## 例子
+/// ```
+/// use windows_service_controller::order::effective_start_order;
+/// for name in effective_start_order().unwrap() {
+///     println!("{}", name);
+/// }
+/// ```
+pub fn effective_start_order() -> Result<Vec<String>, ServiceError> {
+    let group_order = read_multi_sz(SERVICE_GROUP_ORDER_KEY, "List").unwrap_or_default();
+    let sc_manager_handle = OwnedScHandle::new(WindowsService::open_sc_manager(ScManagerAccess::ENUMERATE)?);
+    let nodes = collect_start_nodes(sc_manager_handle.get())?;
+    drop(sc_manager_handle);
+    sort_start_order(&nodes, &group_order)
+}
+
+/// 查询所有开机启动的服务的配置
+fn collect_start_nodes(sc_manager_handle: SC_HANDLE) -> Result<Vec<StartNode>, ServiceError> {
+    let mut nodes = Vec::new();
+    for info in enum_services_status(sc_manager_handle, SERVICE_WIN32 | SERVICE_DRIVER)? {
+        let service_handle = match WindowsService::open_service(
+            sc_manager_handle,
+            &info.service_name,
+            ServiceAccess::SERVICE_QUERY_CONFIG,
+        ) {
+            Ok(handle) => OwnedScHandle::new(handle),
+            Err(_) => continue,
+        };
+        let config = WindowsService::get_config(service_handle.get());
+        drop(service_handle);
+        if let Ok((config, _buffer)) = config {
+            if config.dwStartType.0 <= 2 {
+                nodes.push(StartNode {
+                    name: info.service_name,
+                    start_type: config.dwStartType.0,
+                    group: wide_to_string(config.lpLoadOrderGroup),
+                    dependencies: multi_sz_to_vec(config.lpDependencies),
+                });
+            }
+        }
+    }
+    Ok(nodes)
+}
+
+/// 对服务进行拓扑排序
+pub(crate) fn sort_start_order(nodes: &[StartNode], group_order: &[String]) -> Result<Vec<String>, ServiceError> {
+    let index: HashMap<String, usize> = nodes
+        .iter()
+        .enumerate()
+        .map(|(i, node)| (node.name.to_lowercase(), i))
+        .collect();
+    let group_rank = |group: &str| -> usize {
+        if group.is_empty() {
+            return group_order.len() + 1;
+        }
+        group_order
+            .iter()
+            .position(|g| g.eq_ignore_ascii_case(group))
+            .unwrap_or(group_order.len())
+    };
+    // 排序键:(启动类型,组的位置,小写的服务名)
+    type SortKey = (u32, usize, String);
+    let keys: Vec<SortKey> = nodes
+        .iter()
+        .map(|node| (node.start_type, group_rank(&node.group), node.name.to_lowercase()))
+        .collect();
+
+    // dependents[i]: 依赖于第i个服务的服务
+    let mut dependents: Vec<Vec<usize>> = vec![Vec::new(); nodes.len()];
+    let mut in_degree: Vec<usize> = vec![0; nodes.len()];
+    for (i, node) in nodes.iter().enumerate() {
+        for dependency in &node.dependencies {
+            let targets: Vec<usize> = match Dependency::parse(dependency) {
+                Dependency::Group(group) => nodes
+                    .iter()
+                    .enumerate()
+                    .filter(|(j, other)| *j != i && other.group.eq_ignore_ascii_case(&group))
+                    .map(|(j, _)| j)
+                    .collect(),
+                // 依赖的服务不是开机启动的,由SCM按需启动,不影响顺序
+                Dependency::Service(name) => index.get(&name.to_lowercase()).copied().into_iter().collect(),
+            };
+            for j in targets {
+                dependents[j].push(i);
+                in_degree[i] += 1;
+            }
+        }
+    }
+
+    let mut ready: BinaryHeap<Reverse<(&SortKey, usize)>> = in_degree
+        .iter()
+        .enumerate()
+        .filter(|(_, degree)| **degree == 0)
+        .map(|(i, _)| Reverse((&keys[i], i)))
+        .collect();
+    let mut order = Vec::with_capacity(nodes.len());
+    while let Some(Reverse((_, i))) = ready.pop() {
+        order.push(nodes[i].name.clone());
+        for &j in &dependents[i] {
+            in_degree[j] -= 1;
+            if in_degree[j] == 0 {
+                ready.push(Reverse((&keys[j], j)));
+            }
+        }
+    }
+    if order.len() != nodes.len() {
+        return Err(ServiceError::ERROR_CIRCULAR_DEPENDENCY);
+    }
+    Ok(order)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::dword::ServiceError;
+    use crate::order::{effective_start_order, sort_start_order, StartNode};
+
+    fn node(name: &str, start_type: u32, group: &str, dependencies: &[&str]) -> StartNode {
+        StartNode {
+            name: name.to_string(),
+            start_type,
+            group: group.to_string(),
+            dependencies: dependencies.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn sort_by_start_type_and_group() {
+        let nodes = vec![
+            node("Auto", 2, "", &[]),
+            node("NetDriver", 1, "NDIS", &[]),
+            node("BootDriver", 0, "Boot Bus Extender", &[]),
+            node("BaseDriver", 1, "Base", &[]),
+        ];
+        let group_order = vec!["Boot Bus Extender".to_string(), "Base".to_string(), "NDIS".to_string()];
+        assert_eq!(
+            sort_start_order(&nodes, &group_order).unwrap(),
+            vec!["BootDriver", "BaseDriver", "NetDriver", "Auto"]
+        );
+    }
+
+    #[test]
+    fn sort_respects_dependencies() {
+        let nodes = vec![
+            node("A", 2, "", &["b"]),
+            node("B", 2, "", &["+Late"]),
+            node("C", 2, "Late", &[]),
+            node("D", 2, "", &["NotAutoStart"]),
+        ];
+        assert_eq!(sort_start_order(&nodes, &[]).unwrap(), vec!["C", "B", "A", "D"]);
+    }
+
+    #[test]
+    fn sort_detects_cycle() {
+        let nodes = vec![node("A", 2, "", &["B"]), node("B", 2, "", &["A"])];
+        assert_eq!(sort_start_order(&nodes, &[]), Err(ServiceError::ERROR_CIRCULAR_DEPENDENCY));
+    }
+
+    #[test]
+    fn list_start_order() {
+        match effective_start_order() {
+            Ok(order) => {
+                for name in order {
+                    println!("{}", name)
+                }
+            }
+            Err(e) => {
+                println!("{}", e)
+            }
+        }
+    }
+}
@@ -0,0 +1,345 @@
+//! 服务可执行文件路径(lpBinaryPathName)的解析与比较
+
+/// # 将服务的lpBinaryPathName拆分为可执行文件路径和参数
+/// - 以引号开头时,引号内为可执行文件路径
+/// - 否则取第一个以".exe"结尾的部分(路径可能包含未加引号的空格),没有时取第一个空格之前的部分
+/// ## 例子
+/// ```
+/// use windows_service_controller::path::split_binary_path;
+/// let (executable, args) = split_binary_path("C:\\WINDOWS\\system32\\svchost.exe -k netsvcs -p");
+/// assert_eq!(executable, "C:\\WINDOWS\\system32\\svchost.exe");
+/// assert_eq!(args, "-k netsvcs -p");
+/// ```
+pub fn split_binary_path(binary_path: &str) -> (&str, &str) {
+    let binary_path = binary_path.trim();
+    if let Some(rest) = binary_path.strip_prefix('"') {
+        return match rest.find('"') {
+            Some(end) => (&rest[..end], rest[end + 1..].trim_start()),
+            None => (rest, ""),
+        };
+    }
+    let lower = binary_path.to_ascii_lowercase();
+    let mut search_from = 0;
+    while let Some(pos) = lower[search_from..].find(".exe") {
+        let end = search_from + pos + 4;
+        if end == binary_path.len() || binary_path[end..].starts_with([' ', '\t']) {
+            return (&binary_path[..end], binary_path[end..].trim_start());
+        }
+        search_from = end;
+    }
+    match binary_path.find([' ', '\t']) {
+        Some(end) => (&binary_path[..end], binary_path[end..].trim_start()),
+        None => (binary_path, ""),
+    }
+}
+
+/// 按CommandLineToArgvW的规则为参数加上引号
+pub(crate) fn quote_arg(arg: &str) -> String {
+    if !arg.is_empty() && !arg.contains([' ', '\t', '"']) {
+        return arg.to_string();
+    }
+    let mut result = String::from('"');
+    let mut backslashes = 0;
+    for c in arg.chars() {
+        match c {
+            '\\' => backslashes += 1,
+            '"' => {
+                // 引号前的反斜杠需要加倍,引号本身需要转义
+                result.push_str(&"\\".repeat(backslashes * 2 + 1));
+                result.push('"');
+                backslashes = 0;
+            }
+            _ => {
+                result.push_str(&"\\".repeat(backslashes));
+                result.push(c);
+                backslashes = 0;
+            }
+        }
+    }
+    // 结尾的引号前的反斜杠也需要加倍
+    result.push_str(&"\\".repeat(backslashes * 2));
+    result.push('"');
+    result
+}
+
+/// 按CommandLineToArgvW的规则拆分命令行,是quote_arg的逆操作
+pub(crate) fn split_args(command_line: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut in_quotes = false;
+    let mut backslashes = 0;
+    for c in command_line.chars() {
+        match c {
+            '\\' => {
+                backslashes += 1;
+                in_arg = true;
+            }
+            '"' => {
+                // 引号前的2n个反斜杠表示n个反斜杠,2n+1个表示n个反斜杠和一个引号
+                current.push_str(&"\\".repeat(backslashes / 2));
+                if backslashes % 2 == 1 {
+                    current.push('"');
+                } else {
+                    in_quotes = !in_quotes;
+                }
+                backslashes = 0;
+                in_arg = true;
+            }
+            ' ' | '\t' if !in_quotes => {
+                current.push_str(&"\\".repeat(backslashes));
+                backslashes = 0;
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            _ => {
+                current.push_str(&"\\".repeat(backslashes));
+                backslashes = 0;
+                current.push(c);
+                in_arg = true;
+            }
+        }
+    }
+    current.push_str(&"\\".repeat(backslashes));
+    if in_arg {
+        args.push(current);
+    }
+    args
+}
+
+/// 由可执行文件路径和参数拼接lpBinaryPathName
+pub(crate) fn join_binary_path(executable: &str, args: &[&str]) -> String {
+    let mut result = format!("\"{}\"", executable);
+    for arg in args {
+        result.push(' ');
+        result.push_str(&quote_arg(arg));
+    }
+    result
+}
+
+/// 取出lpBinaryPathName中的可执行文件路径并展开环境变量
+/// - %NAME%由lookup查询,查询不到时保持原样
+/// - 驱动常用的\SystemRoot\前缀展开为%SystemRoot%
+pub(crate) fn expand_executable(binary_path: &str, lookup: impl Fn(&str) -> Option<String>) -> String {
+    let (executable, _) = split_binary_path(binary_path);
+    let executable = match executable.get(..12) {
+        Some(prefix) if prefix.eq_ignore_ascii_case("\\SystemRoot\\") => format!("%SystemRoot%\\{}", &executable[12..]),
+        _ => executable.to_string(),
+    };
+    let mut result = String::new();
+    let mut rest = executable.as_str();
+    while let Some(start) = rest.find('%') {
+        let Some(len) = rest[start + 1..].find('%') else {
+            break;
+        };
+        let name = &rest[start + 1..start + 1 + len];
+        result.push_str(&rest[..start]);
+        match lookup(name) {
+            Some(value) if !name.is_empty() => result.push_str(&value),
+            _ => result.push_str(&rest[start..start + len + 2]),
+        }
+        rest = &rest[start + len + 2..];
+    }
+    result.push_str(rest);
+    result
+}
+
+/// 取出服务实际加载的可执行文件路径
+/// 在expand_executable的基础上:
+/// - 去掉NT路径前缀\??\
+/// - 驱动的相对路径(如System32\drivers\ACPI.sys)相对于%SystemRoot%
+pub(crate) fn resolve_executable(binary_path: &str, is_driver: bool, lookup: impl Fn(&str) -> Option<String>) -> String {
+    let binary_path = binary_path.trim();
+    let binary_path = binary_path.strip_prefix("\\??\\").unwrap_or(binary_path);
+    let relative = !binary_path.starts_with(['\\', '"', '%']) && binary_path.get(1..2) != Some(":");
+    if is_driver && relative {
+        expand_executable(&format!("%SystemRoot%\\{}", binary_path), lookup)
+    } else {
+        expand_executable(binary_path, lookup)
+    }
+}
+
+/// # 规范化lpBinaryPathName
+/// 去掉引号和参数(参数可以通过split_binary_path取得),按当前进程的环境展开%SystemRoot%等环境变量,
+/// 把'/'统一为'\'并把盘符转为小写,得到可以直接比较的可执行文件路径。
+/// 除盘符外不改变大小写,需要忽略大小写时请在比较前自行转换
+/// ## 参数
+/// ### input:
+/// - path: lpBinaryPathName,可以带引号和参数
+/// ### output:
+/// - String
+/// ## 例子
+/// ```
+/// use windows_service_controller::path::normalize_binary_path;
+/// assert_eq!(
+///     normalize_binary_path("\"C:/Program Files/Lers/lers.exe\" --run"),
+///     "c:\\Program Files\\Lers\\lers.exe"
+/// );
+/// ```
+pub fn normalize_binary_path(path: &str) -> String {
+    normalize_with(path, |name| std::env::var(name).ok())
+}
+
+/// normalize_binary_path的实现,环境变量由lookup查询
+fn normalize_with(path: &str, lookup: impl Fn(&str) -> Option<String>) -> String {
+    let mut executable = expand_executable(path, lookup).replace('/', "\\");
+    if executable.get(1..2) == Some(":") {
+        executable[..1].make_ascii_lowercase();
+    }
+    executable
+}
+
+/// 取出svchost.exe承载的服务所属的组(`-k`之后的参数)
+/// 可执行文件不是svchost.exe或没有`-k`参数时返回None
+pub(crate) fn svchost_group(binary_path: &str) -> Option<String> {
+    let (executable, args) = split_binary_path(binary_path);
+    let file_name = executable.rsplit(['\\', '/']).next().unwrap_or(executable);
+    if !file_name.eq_ignore_ascii_case("svchost.exe") {
+        return None;
+    }
+    let args = split_args(args);
+    let position = args.iter().position(|arg| arg.eq_ignore_ascii_case("-k") || arg.eq_ignore_ascii_case("/k"))?;
+    args.get(position + 1).cloned()
+}
+
+/// 判断两个lpBinaryPathName是否指向同一个可执行文件
+/// 两边都先通过normalize_binary_path规范化后再比较
+pub(crate) fn same_executable(binary_path: &str, path: &str, case_insensitive: bool) -> bool {
+    let (left, right) = (normalize_binary_path(binary_path), normalize_binary_path(path));
+    if case_insensitive {
+        left.to_lowercase() == right.to_lowercase()
+    } else {
+        left == right
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::path::{
+        expand_executable, join_binary_path, normalize_with, quote_arg, resolve_executable, same_executable, split_args,
+        split_binary_path, svchost_group,
+    };
+
+    #[test]
+    fn split_path() {
+        assert_eq!(
+            split_binary_path("\"C:\\Program Files\\App\\app.exe\" -k run"),
+            ("C:\\Program Files\\App\\app.exe", "-k run")
+        );
+        assert_eq!(
+            split_binary_path("C:\\Program Files\\App\\app.exe -k run"),
+            ("C:\\Program Files\\App\\app.exe", "-k run")
+        );
+        assert_eq!(
+            split_binary_path("C:\\WINDOWS\\system32\\svchost.exe -k netsvcs -p"),
+            ("C:\\WINDOWS\\system32\\svchost.exe", "-k netsvcs -p")
+        );
+        assert_eq!(split_binary_path("\\SystemRoot\\System32\\drivers\\ACPI.sys"), ("\\SystemRoot\\System32\\drivers\\ACPI.sys", ""));
+    }
+
+    #[test]
+    fn quote() {
+        assert_eq!(quote_arg("simple"), "simple");
+        assert_eq!(quote_arg(""), "\"\"");
+        assert_eq!(quote_arg("with space"), "\"with space\"");
+        assert_eq!(quote_arg("say \"hi\""), "\"say \\\"hi\\\"\"");
+        assert_eq!(quote_arg("C:\\dir with space\\"), "\"C:\\dir with space\\\\\"");
+    }
+
+    #[test]
+    fn split_command_line() {
+        assert_eq!(split_args("a  b\tc"), vec!["a", "b", "c"]);
+        assert_eq!(split_args("\"with space\" \"\""), vec!["with space", ""]);
+        assert_eq!(split_args("\"say \\\"hi\\\"\""), vec!["say \"hi\""]);
+        assert_eq!(split_args("C:\\dir\\ \"C:\\dir with space\\\\\""), vec!["C:\\dir\\", "C:\\dir with space\\"]);
+        for arg in ["simple", "", "with space", "say \"hi\"", "C:\\dir with space\\"] {
+            assert_eq!(split_args(&quote_arg(arg)), vec![arg]);
+        }
+    }
+
+    #[test]
+    fn join_path() {
+        assert_eq!(
+            join_binary_path("C:\\App\\app.exe", &["--config", "C:\\My Config.toml"]),
+            "\"C:\\App\\app.exe\" --config \"C:\\My Config.toml\""
+        );
+    }
+
+    #[test]
+    fn expand_path() {
+        let lookup = |name: &str| match name {
+            "SystemRoot" => Some("C:\\WINDOWS".to_string()),
+            _ => None,
+        };
+        assert_eq!(
+            expand_executable("%SystemRoot%\\system32\\svchost.exe -k netsvcs", lookup),
+            "C:\\WINDOWS\\system32\\svchost.exe"
+        );
+        assert_eq!(
+            expand_executable("\\SystemRoot\\System32\\drivers\\ACPI.sys", lookup),
+            "C:\\WINDOWS\\System32\\drivers\\ACPI.sys"
+        );
+        assert_eq!(expand_executable("\"C:\\100%\\%Missing%\\a.exe\"", lookup), "C:\\100%\\%Missing%\\a.exe");
+    }
+
+    #[test]
+    fn resolve_path() {
+        let lookup = |name: &str| match name {
+            "SystemRoot" => Some("C:\\WINDOWS".to_string()),
+            _ => None,
+        };
+        assert_eq!(
+            resolve_executable("System32\\drivers\\ACPI.sys", true, lookup),
+            "C:\\WINDOWS\\System32\\drivers\\ACPI.sys"
+        );
+        assert_eq!(resolve_executable("\\??\\C:\\Drivers\\lers.sys", true, lookup), "C:\\Drivers\\lers.sys");
+        assert_eq!(resolve_executable("lers.exe --run", false, lookup), "lers.exe");
+        assert_eq!(
+            resolve_executable("\"C:\\Program Files\\Lers\\lers.exe\" --run", false, lookup),
+            "C:\\Program Files\\Lers\\lers.exe"
+        );
+    }
+
+    #[test]
+    fn normalize_path() {
+        let lookup = |name: &str| match name {
+            "SystemRoot" => Some("C:\\WINDOWS".to_string()),
+            _ => None,
+        };
+        assert_eq!(
+            normalize_with("%SystemRoot%\\system32\\svchost.exe -k netsvcs", lookup),
+            "c:\\WINDOWS\\system32\\svchost.exe"
+        );
+        assert_eq!(normalize_with("\"D:/Lers/lers.exe\" --run", lookup), "d:\\Lers\\lers.exe");
+        assert_eq!(normalize_with("lers.exe", lookup), "lers.exe");
+    }
+
+    #[test]
+    fn match_executable() {
+        assert!(same_executable(
+            "\"C:\\Program Files\\App\\app.exe\" -k run",
+            "C:/Program Files/App/app.exe",
+            false
+        ));
+        assert!(same_executable("C:\\WINDOWS\\system32\\svchost.exe -k netsvcs", "c:\\windows\\System32\\SVCHOST.EXE", true));
+        assert!(!same_executable("C:\\WINDOWS\\system32\\svchost.exe -k netsvcs", "c:\\windows\\System32\\SVCHOST.EXE", false));
+        assert!(!same_executable("C:\\App\\app.exe", "C:\\App\\app2.exe", true));
+    }
+
+    #[test]
+    fn svchost_group_name() {
+        assert_eq!(
+            svchost_group("C:\\WINDOWS\\system32\\svchost.exe -k netsvcs -p").as_deref(),
+            Some("netsvcs")
+        );
+        assert_eq!(
+            svchost_group("%SystemRoot%\\System32\\SVCHOST.EXE -k LocalServiceNetworkRestricted").as_deref(),
+            Some("LocalServiceNetworkRestricted")
+        );
+        assert_eq!(svchost_group("\"C:\\WINDOWS\\system32\\svchost.exe\" /k DcomLaunch").as_deref(), Some("DcomLaunch"));
+        assert_eq!(svchost_group("C:\\WINDOWS\\system32\\svchost.exe"), None);
+        assert_eq!(svchost_group("C:\\Lers\\lers.exe -k netsvcs"), None);
+    }
+}
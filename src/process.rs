@@ -0,0 +1,67 @@
+use windows::Win32::Foundation::CloseHandle;
+use windows::Win32::System::Diagnostics::ToolHelp::{CreateToolhelp32Snapshot, PROCESSENTRY32W, Process32FirstW, Process32NextW, TH32CS_SNAPPROCESS};
+use windows::Win32::System::RemoteDesktop::ProcessIdToSessionId;
+use windows::Win32::System::Threading::GetCurrentProcessId;
+
+/// # 判断当前进程是否作为服务运行
+/// 服务进程都由SCM(services.exe)启动并且运行在会话0中,
+/// 因此当前进程位于会话0且父进程为services.exe时认为是作为服务运行。
+/// 这是启发式的判断,不会调用StartServiceCtrlDispatcherW,可以在进程启动时安全调用
+/// ## 参数
+/// ### output:
+/// - bool
+/// ## 例子
+/// ```
+/// use windows_service_controller::process::is_running_as_service;
+/// if is_running_as_service() {
+///     // 进入服务模式
+/// } else {
+///     // 以控制台程序运行
+/// }
+/// ```
+pub fn is_running_as_service() -> bool {
+    let process_id = unsafe { GetCurrentProcessId() };
+    let mut session_id: u32 = 0;
+    if unsafe { ProcessIdToSessionId(process_id, &mut session_id) }.is_err() || session_id != 0 {
+        return false;
+    }
+    match parent_process_name(process_id) {
+        Some(name) => name.eq_ignore_ascii_case("services.exe"),
+        None => false,
+    }
+}
+
+/// 获取指定进程的父进程的可执行文件名
+fn parent_process_name(process_id: u32) -> Option<String> {
+    let snapshot = unsafe { CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0) }.ok()?;
+    let mut entries = Vec::new();
+    let mut entry = PROCESSENTRY32W {
+        dwSize: std::mem::size_of::<PROCESSENTRY32W>() as u32,
+        ..Default::default()
+    };
+    let mut found = unsafe { Process32FirstW(snapshot, &mut entry) }.is_ok();
+    while found {
+        let len = entry.szExeFile.iter().position(|c| *c == 0).unwrap_or(entry.szExeFile.len());
+        entries.push((
+            entry.th32ProcessID,
+            entry.th32ParentProcessID,
+            String::from_utf16_lossy(&entry.szExeFile[..len]),
+        ));
+        found = unsafe { Process32NextW(snapshot, &mut entry) }.is_ok();
+    }
+    unsafe {
+        let _ = CloseHandle(snapshot);
+    }
+    let parent_id = entries.iter().find(|(id, _, _)| *id == process_id)?.1;
+    entries.into_iter().find(|(id, _, _)| *id == parent_id).map(|(_, _, name)| name)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::process::is_running_as_service;
+
+    #[test]
+    fn not_running_as_service() {
+        assert!(!is_running_as_service());
+    }
+}
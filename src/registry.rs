@@ -0,0 +1,64 @@
+use std::ffi::c_void;
+
+use lers_windows_macro::PCWSTR;
+use windows::core::PWSTR;
+use windows::Win32::Foundation::{ERROR_FILE_NOT_FOUND, ERROR_SUCCESS};
+use windows::Win32::System::Registry::{HKEY_LOCAL_MACHINE, RegDeleteTreeW, RegGetValueW, RRF_RT_REG_MULTI_SZ};
+
+use crate::dword::ServiceError;
+use crate::multi_sz_to_vec;
+
+/// 读取HKEY_LOCAL_MACHINE下的REG_MULTI_SZ值
+pub(crate) fn read_multi_sz(subkey: &str, value: &str) -> Result<Vec<String>, ServiceError> {
+    let mut size: u32 = 0;
+    let error = unsafe {
+        RegGetValueW(HKEY_LOCAL_MACHINE, PCWSTR!(subkey), PCWSTR!(value), RRF_RT_REG_MULTI_SZ, None, None, Some(&mut size))
+    };
+    if error != ERROR_SUCCESS {
+        return Err(error.into());
+    }
+    // 多预留两个字符,保证即使数据没有正确结尾也能以两个NUL结束
+    let mut buffer: Vec<u16> = vec![0; (size as usize).div_ceil(2) + 2];
+    let mut size = (buffer.len() * 2) as u32;
+    let error = unsafe {
+        RegGetValueW(
+            HKEY_LOCAL_MACHINE,
+            PCWSTR!(subkey),
+            PCWSTR!(value),
+            RRF_RT_REG_MULTI_SZ,
+            None,
+            Some(buffer.as_mut_ptr() as *mut c_void),
+            Some(&mut size),
+        )
+    };
+    if error != ERROR_SUCCESS {
+        return Err(error.into());
+    }
+    Ok(multi_sz_to_vec(PWSTR(buffer.as_mut_ptr())))
+}
+
+/// 删除HKEY_LOCAL_MACHINE下的键及其所有子键和值,键不存在时视为成功
+pub(crate) fn delete_tree(subkey: &str) -> Result<(), ServiceError> {
+    match unsafe { RegDeleteTreeW(HKEY_LOCAL_MACHINE, PCWSTR!(subkey)) } {
+        ERROR_SUCCESS | ERROR_FILE_NOT_FOUND => Ok(()),
+        error => Err(error.into()),
+    }
+}
+
+/// 服务在应用程序日志中注册的事件源所在的键
+pub(crate) fn eventlog_source_key(name: &str) -> String {
+    format!("SYSTEM\\CurrentControlSet\\Services\\EventLog\\Application\\{}", name)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::registry::eventlog_source_key;
+
+    #[test]
+    fn eventlog_key() {
+        assert_eq!(
+            eventlog_source_key("Lers"),
+            "SYSTEM\\CurrentControlSet\\Services\\EventLog\\Application\\Lers"
+        );
+    }
+}
@@ -0,0 +1,159 @@
+use std::fmt::Write;
+
+use crate::{multi_sz_to_vec, wide_to_string, RawServiceConfig};
+
+/// sc.exe中服务类型的名称
+fn type_name(service_type: u32) -> &'static str {
+    match service_type & 0xff {
+        0x1 => "KERNEL_DRIVER",
+        0x2 => "FILE_SYSTEM_DRIVER",
+        0x4 => "ADAPTER",
+        0x8 => "RECOGNIZER_DRIVER",
+        0x10 => "WIN32_OWN_PROCESS",
+        0x20 => "WIN32_SHARE_PROCESS",
+        0x50 => "USER_OWN_PROCESS",
+        0x60 => "USER_SHARE_PROCESS",
+        _ => "",
+    }
+}
+
+/// sc.exe中启动类型的名称
+fn start_type_name(start_type: u32) -> &'static str {
+    match start_type {
+        0 => "BOOT_START",
+        1 => "SYSTEM_START",
+        2 => "AUTO_START",
+        3 => "DEMAND_START",
+        4 => "DISABLED",
+        _ => "",
+    }
+}
+
+/// sc.exe中错误控制的名称
+fn error_control_name(error_control: u32) -> &'static str {
+    match error_control {
+        0 => "IGNORE",
+        1 => "NORMAL",
+        2 => "SEVERE",
+        3 => "CRITICAL",
+        _ => "",
+    }
+}
+
+/// 按`sc qc`的格式输出服务配置,包括开头的`[SC] QueryServiceConfig SUCCESS`和空行
+/// - delayed: 是否为延迟自动启动,为true时启动类型后追加`(DELAYED)`
+/// - 服务类型包含SERVICE_INTERACTIVE_PROCESS(0x100)时追加`(interactive)`
+pub(crate) fn format_qc(name: &str, config: &RawServiceConfig, delayed: bool) -> String {
+    let mut text = String::new();
+    let _ = writeln!(text, "[SC] QueryServiceConfig SUCCESS");
+    let _ = writeln!(text);
+    let _ = writeln!(text, "SERVICE_NAME: {}", name);
+    let _ = writeln!(
+        text,
+        "        TYPE               : {:<3} {}{}",
+        format!("{:x}", config.dwServiceType.0),
+        type_name(config.dwServiceType.0),
+        if config.dwServiceType.0 & 0x100 != 0 { "  (interactive)" } else { "" }
+    );
+    let _ = writeln!(
+        text,
+        "        START_TYPE         : {:<3} {}{}",
+        config.dwStartType.0,
+        start_type_name(config.dwStartType.0),
+        if delayed { "  (DELAYED)" } else { "" }
+    );
+    let _ = writeln!(
+        text,
+        "        ERROR_CONTROL      : {:<3} {}",
+        config.dwErrorControl.0,
+        error_control_name(config.dwErrorControl.0)
+    );
+    let _ = writeln!(text, "        BINARY_PATH_NAME   : {}", wide_to_string(config.lpBinaryPathName));
+    let _ = writeln!(text, "        LOAD_ORDER_GROUP   : {}", wide_to_string(config.lpLoadOrderGroup));
+    let _ = writeln!(text, "        TAG                : {}", config.dwTagId);
+    let _ = writeln!(text, "        DISPLAY_NAME       : {}", wide_to_string(config.lpDisplayName));
+    let dependencies = multi_sz_to_vec(config.lpDependencies);
+    if dependencies.is_empty() {
+        let _ = writeln!(text, "        DEPENDENCIES       : ");
+    }
+    for (i, dependency) in dependencies.iter().enumerate() {
+        if i == 0 {
+            let _ = writeln!(text, "        DEPENDENCIES       : {}", dependency);
+        } else {
+            let _ = writeln!(text, "                           : {}", dependency);
+        }
+    }
+    let _ = writeln!(text, "        SERVICE_START_NAME : {}", wide_to_string(config.lpServiceStartName));
+    text
+}
+
+#[cfg(test)]
+mod test {
+    use windows::core::PWSTR;
+    use windows::Win32::System::Services::{
+        ENUM_SERVICE_TYPE, SERVICE_AUTO_START, SERVICE_ERROR_NORMAL, SERVICE_WIN32_OWN_PROCESS,
+    };
+
+    use crate::sc::format_qc;
+    use crate::RawServiceConfig;
+
+    fn wide(s: &str) -> Vec<u16> {
+        s.encode_utf16().chain(Some(0)).collect()
+    }
+
+    #[test]
+    fn format_qc_output() {
+        let mut binary_path = wide("C:\\WINDOWS\\system32\\SearchIndexer.exe /Embedding");
+        let mut group = wide("");
+        let mut dependencies: Vec<u16> = "RPCSS\0BrokerInfrastructure\0\0".encode_utf16().collect();
+        let mut start_name = wide("LocalSystem");
+        let mut display_name = wide("Windows Search");
+        let config = RawServiceConfig {
+            dwServiceType: SERVICE_WIN32_OWN_PROCESS,
+            dwStartType: SERVICE_AUTO_START,
+            dwErrorControl: SERVICE_ERROR_NORMAL,
+            lpBinaryPathName: PWSTR(binary_path.as_mut_ptr()),
+            lpLoadOrderGroup: PWSTR(group.as_mut_ptr()),
+            dwTagId: 0,
+            lpDependencies: PWSTR(dependencies.as_mut_ptr()),
+            lpServiceStartName: PWSTR(start_name.as_mut_ptr()),
+            lpDisplayName: PWSTR(display_name.as_mut_ptr()),
+        };
+        assert_eq!(
+            format_qc("WSearch", &config, false),
+            "[SC] QueryServiceConfig SUCCESS\n\
+             \n\
+             SERVICE_NAME: WSearch\n\
+             \x20       TYPE               : 10  WIN32_OWN_PROCESS\n\
+             \x20       START_TYPE         : 2   AUTO_START\n\
+             \x20       ERROR_CONTROL      : 1   NORMAL\n\
+             \x20       BINARY_PATH_NAME   : C:\\WINDOWS\\system32\\SearchIndexer.exe /Embedding\n\
+             \x20       LOAD_ORDER_GROUP   : \n\
+             \x20       TAG                : 0\n\
+             \x20       DISPLAY_NAME       : Windows Search\n\
+             \x20       DEPENDENCIES       : RPCSS\n\
+             \x20                          : BrokerInfrastructure\n\
+             \x20       SERVICE_START_NAME : LocalSystem\n"
+        );
+    }
+
+    #[test]
+    fn format_qc_delayed_interactive() {
+        let mut empty = wide("");
+        let mut dependencies: Vec<u16> = vec![0, 0];
+        let config = RawServiceConfig {
+            dwServiceType: ENUM_SERVICE_TYPE(SERVICE_WIN32_OWN_PROCESS.0 | 0x100),
+            dwStartType: SERVICE_AUTO_START,
+            dwErrorControl: SERVICE_ERROR_NORMAL,
+            lpBinaryPathName: PWSTR(empty.as_mut_ptr()),
+            lpLoadOrderGroup: PWSTR(empty.as_mut_ptr()),
+            dwTagId: 0,
+            lpDependencies: PWSTR(dependencies.as_mut_ptr()),
+            lpServiceStartName: PWSTR(empty.as_mut_ptr()),
+            lpDisplayName: PWSTR(empty.as_mut_ptr()),
+        };
+        let text = format_qc("Lers", &config, true);
+        assert!(text.contains("        TYPE               : 110 WIN32_OWN_PROCESS  (interactive)\n"));
+        assert!(text.contains("        START_TYPE         : 2   AUTO_START  (DELAYED)\n"));
+    }
+}
@@ -0,0 +1,43 @@
+use std::ffi::c_void;
+
+use windows::core::{HRESULT, PCWSTR};
+use windows::Win32::Foundation::{HWND, TRUST_E_NOSIGNATURE, TRUST_E_PROVIDER_UNKNOWN, TRUST_E_SUBJECT_FORM_UNKNOWN};
+use windows::Win32::Security::WinTrust::{
+    WinVerifyTrust, WINTRUST_ACTION_GENERIC_VERIFY_V2, WINTRUST_DATA, WINTRUST_DATA_0, WINTRUST_FILE_INFO, WTD_CHOICE_FILE,
+    WTD_REVOKE_NONE, WTD_STATEACTION_CLOSE, WTD_STATEACTION_VERIFY, WTD_UI_NONE,
+};
+
+use crate::dword::ServiceError;
+
+/// 使用WinVerifyTrust检查文件的Authenticode签名
+/// - 没有签名(或文件格式不支持签名)时返回BINARY_NOT_SIGNED
+/// - 签名无效或证书链不受信任时返回BINARY_NOT_TRUSTED
+pub(crate) fn verify_signature(path: &str) -> Result<(), ServiceError> {
+    let path: Vec<u16> = path.encode_utf16().chain(Some(0)).collect();
+    let mut file_info = WINTRUST_FILE_INFO {
+        cbStruct: std::mem::size_of::<WINTRUST_FILE_INFO>() as u32,
+        pcwszFilePath: PCWSTR(path.as_ptr()),
+        ..Default::default()
+    };
+    let mut data = WINTRUST_DATA {
+        cbStruct: std::mem::size_of::<WINTRUST_DATA>() as u32,
+        dwUIChoice: WTD_UI_NONE,
+        fdwRevocationChecks: WTD_REVOKE_NONE,
+        dwUnionChoice: WTD_CHOICE_FILE,
+        Anonymous: WINTRUST_DATA_0 { pFile: &mut file_info },
+        dwStateAction: WTD_STATEACTION_VERIFY,
+        ..Default::default()
+    };
+    let mut action = WINTRUST_ACTION_GENERIC_VERIFY_V2;
+    let status = unsafe { WinVerifyTrust(HWND::default(), &mut action, &mut data as *mut WINTRUST_DATA as *mut c_void) };
+    // 释放验证时分配的状态数据
+    data.dwStateAction = WTD_STATEACTION_CLOSE;
+    unsafe {
+        WinVerifyTrust(HWND::default(), &mut action, &mut data as *mut WINTRUST_DATA as *mut c_void);
+    }
+    match HRESULT(status) {
+        HRESULT(0) => Ok(()),
+        TRUST_E_NOSIGNATURE | TRUST_E_SUBJECT_FORM_UNKNOWN | TRUST_E_PROVIDER_UNKNOWN => Err(ServiceError::BINARY_NOT_SIGNED),
+        _ => Err(ServiceError::BINARY_NOT_TRUSTED),
+    }
+}
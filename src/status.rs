@@ -0,0 +1,229 @@
+use std::time::{Duration, Instant};
+
+use windows::Win32::System::Services::SERVICE_STATUS_PROCESS;
+
+use crate::dword::{ControlsAccepted, ServiceFlags, ServiceStatus, ServiceType};
+use crate::DEFAULT_PENDING_TIMEOUT;
+
+/// 判断停滞时wait_hint的下限,与等待时的最短查询间隔一致
+const MIN_STALL_WINDOW: Duration = Duration::from_secs(1);
+
+/// 服务的扩展状态(QueryServiceStatusEx的结果)
+#[derive(Debug, Clone, Copy)]
+#[must_use]
+pub struct FullStatus(SERVICE_STATUS_PROCESS);
+
+impl From<SERVICE_STATUS_PROCESS> for FullStatus {
+    fn from(value: SERVICE_STATUS_PROCESS) -> Self {
+        FullStatus(value)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for FullStatus {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+        let mut state = serializer.serialize_struct("FullStatus", 9)?;
+        state.serialize_field("service_type", &self.0.dwServiceType.0)?;
+        state.serialize_field("current_state", &self.0.dwCurrentState.0)?;
+        state.serialize_field("controls_accepted", &self.0.dwControlsAccepted)?;
+        state.serialize_field("win32_exit_code", &self.0.dwWin32ExitCode)?;
+        state.serialize_field("service_specific_exit_code", &self.0.dwServiceSpecificExitCode)?;
+        state.serialize_field("check_point", &self.0.dwCheckPoint)?;
+        state.serialize_field("wait_hint", &self.0.dwWaitHint)?;
+        state.serialize_field("process_id", &self.0.dwProcessId)?;
+        state.serialize_field("service_flags", &self.0.dwServiceFlags.0)?;
+        state.end()
+    }
+}
+
+impl FullStatus {
+    /// # 服务类型
+    pub fn service_type(&self) -> ServiceType {
+        self.0.dwServiceType.into()
+    }
+
+    /// # 服务当前状态
+    pub fn current_state(&self) -> ServiceStatus {
+        self.0.dwCurrentState.into()
+    }
+
+    /// # 服务接受的控制代码
+    pub fn controls_accepted(&self) -> ControlsAccepted {
+        self.0.dwControlsAccepted.into()
+    }
+
+    /// # 服务启动或停止时报告的错误代码
+    pub fn win32_exit_code(&self) -> u32 {
+        self.0.dwWin32ExitCode
+    }
+
+    /// # 服务自定义的错误代码
+    pub fn service_specific_exit_code(&self) -> u32 {
+        self.0.dwServiceSpecificExitCode
+    }
+
+    /// # 服务进程的PID,服务未运行时为0
+    pub fn process_id(&self) -> u32 {
+        self.0.dwProcessId
+    }
+
+    /// # 服务在挂起操作中的进度
+    /// 服务在挂起操作中定期增加该值,不在挂起状态时为0
+    pub fn checkpoint(&self) -> u32 {
+        self.0.dwCheckPoint
+    }
+
+    /// # 服务预计完成挂起操作所需的时间
+    /// 由dwWaitHint(毫秒)转换,u32::MAX约为49.7天,转换不会溢出
+    pub fn wait_hint(&self) -> Duration {
+        Duration::from_millis(u64::from(self.0.dwWaitHint))
+    }
+
+    /// # 服务进程标志
+    /// 目前只定义了SERVICE_RUNS_IN_SYSTEM_PROCESS,表示服务运行在系统进程中
+    pub fn service_flags(&self) -> ServiceFlags {
+        self.0.dwServiceFlags.into()
+    }
+
+    /// # 服务是否运行在系统进程中
+    /// 系统进程是必须一直运行的进程(如services.exe托管的服务),这类服务所在的进程不能被结束。
+    /// 服务停止时返回false
+    ///
+    /// 注意:SERVICE_STATUS_PROCESS不包含启动原因,无法由此判断本次运行是否由触发器启动。
+    /// 启动原因(SERVICE_DYNAMIC_INFORMATION_LEVEL_START_REASON)只能由服务自身
+    /// 以服务状态句柄调用QueryServiceDynamicInformation获取,控制端无法查询
+    pub fn runs_in_system_process(&self) -> bool {
+        self.service_flags().contains(ServiceFlags::SERVICE_RUNS_IN_SYSTEM_PROCESS)
+    }
+}
+
+/// 挂起操作(启动、停止等)的进度
+/// 服务不会报告总进度,只能观察dwCheckPoint的变化趋势
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StartProgress {
+    /// 目前观察到的最大dwCheckPoint
+    pub checkpoint: u32,
+    /// 服务最近报告的dwWaitHint
+    pub wait_hint: Duration,
+    /// 开始等待以来经过的时间
+    pub elapsed: Duration,
+    /// 距离checkpoint上一次增加经过的时间
+    pub since_progress: Duration,
+}
+
+impl StartProgress {
+    /// # 是否停滞
+    /// checkpoint在wait_hint内没有增加时,认为服务没有响应。
+    /// wait_hint至少按1秒计算;很多服务在挂起时报告的wait_hint为0,此时按默认的30秒计算
+    pub fn is_stalled(&self) -> bool {
+        let window = if self.wait_hint.is_zero() {
+            DEFAULT_PENDING_TIMEOUT
+        } else {
+            self.wait_hint.max(MIN_STALL_WINDOW)
+        };
+        self.since_progress > window
+    }
+}
+
+/// 记录挂起操作中观察到的最大checkpoint及其增加的时间
+pub(crate) struct ProgressTracker {
+    start: Instant,
+    checkpoint: u32,
+    last_progress: Instant,
+}
+
+impl ProgressTracker {
+    pub(crate) fn new(status: &FullStatus, now: Instant) -> ProgressTracker {
+        ProgressTracker {
+            start: now,
+            checkpoint: status.checkpoint(),
+            last_progress: now,
+        }
+    }
+
+    /// 根据新查询到的状态更新进度
+    pub(crate) fn update(&mut self, status: &FullStatus, now: Instant) -> StartProgress {
+        if status.checkpoint() > self.checkpoint {
+            self.checkpoint = status.checkpoint();
+            self.last_progress = now;
+        }
+        StartProgress {
+            checkpoint: self.checkpoint,
+            wait_hint: status.wait_hint(),
+            elapsed: now.saturating_duration_since(self.start),
+            since_progress: now.saturating_duration_since(self.last_progress),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, Instant};
+
+    use windows::Win32::System::Services::{SERVICE_RUNNING, SERVICE_RUNS_IN_SYSTEM_PROCESS, SERVICE_START_PENDING, SERVICE_STATUS_PROCESS};
+
+    use crate::status::{FullStatus, ProgressTracker};
+
+    fn pending(check_point: u32, wait_hint: u32) -> FullStatus {
+        SERVICE_STATUS_PROCESS {
+            dwCurrentState: SERVICE_START_PENDING,
+            dwCheckPoint: check_point,
+            dwWaitHint: wait_hint,
+            ..Default::default()
+        }
+        .into()
+    }
+
+    #[test]
+    fn track_progress() {
+        let start = Instant::now();
+        let mut tracker = ProgressTracker::new(&pending(1, 2000), start);
+        let progress = tracker.update(&pending(3, 2000), start + Duration::from_secs(1));
+        assert_eq!(progress.checkpoint, 3);
+        assert_eq!(progress.since_progress, Duration::ZERO);
+        assert!(!progress.is_stalled());
+        // checkpoint回退时保留最大值
+        let progress = tracker.update(&pending(2, 2000), start + Duration::from_secs(4));
+        assert_eq!(progress.checkpoint, 3);
+        assert_eq!(progress.elapsed, Duration::from_secs(4));
+        assert_eq!(progress.since_progress, Duration::from_secs(3));
+        assert!(progress.is_stalled());
+    }
+
+    #[test]
+    fn zero_wait_hint() {
+        let start = Instant::now();
+        let mut tracker = ProgressTracker::new(&pending(1, 0), start);
+        let progress = tracker.update(&pending(1, 0), start + Duration::from_nanos(10));
+        assert!(!progress.is_stalled());
+        let progress = tracker.update(&pending(1, 0), start + Duration::from_secs(5));
+        assert!(!progress.is_stalled());
+        let progress = tracker.update(&pending(1, 0), start + Duration::from_secs(31));
+        assert!(progress.is_stalled());
+        // 很小的wait_hint至少按1秒计算
+        let mut tracker = ProgressTracker::new(&pending(1, 1), start);
+        assert!(!tracker.update(&pending(1, 1), start + Duration::from_millis(500)).is_stalled());
+        assert!(tracker.update(&pending(1, 1), start + Duration::from_secs(2)).is_stalled());
+    }
+
+    #[test]
+    fn typed_accessors() {
+        let status = pending(5, 3000);
+        assert_eq!(status.checkpoint(), 5);
+        assert_eq!(status.wait_hint(), Duration::from_secs(3));
+        assert_eq!(pending(0, u32::MAX).wait_hint(), Duration::from_millis(u32::MAX as u64));
+    }
+
+    #[test]
+    fn system_process_flag() {
+        let status: FullStatus = SERVICE_STATUS_PROCESS {
+            dwCurrentState: SERVICE_RUNNING,
+            dwServiceFlags: SERVICE_RUNS_IN_SYSTEM_PROCESS,
+            ..Default::default()
+        }
+        .into();
+        assert!(status.runs_in_system_process());
+        assert!(!pending(0, 0).runs_in_system_process());
+    }
+}
@@ -0,0 +1,126 @@
+use std::ops::Deref;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use crate::handle::OwnedScHandle;
+use crate::status::FullStatus;
+use crate::WindowsService;
+
+/// 轮询服务状态的间隔
+pub(crate) const WATCH_INTERVAL: Duration = Duration::from_millis(250);
+
+/// 监听线程使用的句柄,由监听线程独占并在结束时关闭
+pub(crate) struct WatchHandles {
+    pub(crate) service_handle: OwnedScHandle,
+    /// 只为在drop时关闭而持有
+    pub(crate) _sc_manager_handle: OwnedScHandle,
+}
+
+// SCM句柄可以在任意线程中使用,且这里的句柄只由监听线程访问
+unsafe impl Send for WatchHandles {}
+
+/// 服务状态监听句柄
+/// 调用stop或者drop后停止监听
+pub struct WatchHandle {
+    stopped: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl WatchHandle {
+    pub(crate) fn spawn<F>(handles: WatchHandles, mut on_change: F) -> WatchHandle
+    where
+        F: FnMut(FullStatus) + Send + 'static,
+    {
+        let stopped = Arc::new(AtomicBool::new(false));
+        let flag = stopped.clone();
+        let thread = std::thread::spawn(move || {
+            poll_changes(handles, WATCH_INTERVAL, || flag.load(Ordering::Relaxed), |status| {
+                on_change(status);
+                true
+            });
+        });
+        WatchHandle {
+            stopped,
+            thread: Some(thread),
+        }
+    }
+
+    /// # 停止监听
+    /// 等待监听线程退出后返回
+    pub fn stop(mut self) {
+        self.join();
+    }
+
+    fn join(&mut self) {
+        self.stopped.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for WatchHandle {
+    fn drop(&mut self) {
+        self.join();
+    }
+}
+
+/// 服务状态变化的接收端
+/// 可以像Receiver一样使用(recv、try_recv、iter等),drop后轮询线程在下一次轮询时退出
+pub struct StatusReceiver {
+    receiver: Receiver<FullStatus>,
+    stopped: Arc<AtomicBool>,
+}
+
+impl StatusReceiver {
+    pub(crate) fn spawn(handles: WatchHandles, poll_interval: Duration) -> StatusReceiver {
+        let (sender, receiver) = channel();
+        let stopped = Arc::new(AtomicBool::new(false));
+        let flag = stopped.clone();
+        std::thread::spawn(move || {
+            poll_changes(handles, poll_interval, || flag.load(Ordering::Relaxed), |status| sender.send(status).is_ok());
+        });
+        StatusReceiver { receiver, stopped }
+    }
+}
+
+impl Deref for StatusReceiver {
+    type Target = Receiver<FullStatus>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.receiver
+    }
+}
+
+impl Drop for StatusReceiver {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::Relaxed);
+    }
+}
+
+/// 在当前线程中轮询服务状态,开始时以及每次状态变化时调用on_change。
+/// is_stopped返回true、on_change返回false或查询失败(服务被删除、句柄失效)时结束,结束时关闭句柄
+fn poll_changes<S, F>(handles: WatchHandles, interval: Duration, is_stopped: S, mut on_change: F)
+where
+    S: Fn() -> bool,
+    F: FnMut(FullStatus) -> bool,
+{
+    let mut last_state = None;
+    while !is_stopped() {
+        let status = match WindowsService::get_status_ex(handles.service_handle.get()) {
+            Ok(status) => status,
+            Err(_) => break,
+        };
+        let state = status.current_state();
+        if last_state != Some(state) {
+            last_state = Some(state);
+            if !on_change(status) {
+                break;
+            }
+        }
+        std::thread::sleep(interval);
+    }
+}